@@ -63,7 +63,7 @@ fn benchmark(c: &mut Criterion) {
         .as_bytes()
         .to_vec()
         .len();
-    let compressed = compress_proof(&proof).len();
+    let compressed = compress_proof(&proof).unwrap().len();
     proof_sizes[0] = [uncompressed_size, compressed];
     // benchmark degree 2 proof
     for i in 1..7 {
@@ -102,7 +102,7 @@ fn benchmark(c: &mut Criterion) {
             .as_bytes()
             .to_vec()
             .len();
-        let compressed = compress_proof(&proof).len();
+        let compressed = compress_proof(&proof).unwrap().len();
         proof_sizes[i] = [uncompressed_size, compressed];
     }
     println!("Proof size benchmarks: ");
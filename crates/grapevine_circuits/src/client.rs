@@ -0,0 +1,180 @@
+use crate::errors::GrapevineCircuitError;
+use crate::nova::{continue_nova_proof, nova_proof, verify_nova_proof};
+use crate::utils::{compress_proof, decompress_proof};
+use grapevine_common::account::GrapevineAccount;
+use grapevine_common::auth_secret::AuthSecretEncrypted;
+use grapevine_common::models::proof::ProvingData;
+use grapevine_common::{Fr, Params, G1, G2};
+use nova_scotia::{
+    circom::{circuit::R1CS, reader::load_r1cs},
+    FileLocation,
+};
+use std::path::{Path, PathBuf};
+
+/**
+ * Client side prover that loads the grapevine proving artifacts once and reuses them for every proof
+ */
+pub struct GrapevineProver {
+    params: Params,
+    r1cs: R1CS<Fr>,
+    wc_path: PathBuf,
+}
+
+impl GrapevineProver {
+    /**
+     * Load the proving artifacts from a directory
+     * @notice the wasm witness calculator is read from `grapevine.wasm` as served by the server, or
+     *         from `grapevine_js/grapevine.wasm` as emitted by circom
+     *
+     * @param artifacts_dir - the directory containing public_params.json, grapevine.r1cs, and the wasm
+     * @returns - the prover, or an Artifact error if any artifact is missing or malformed
+     */
+    pub fn new(artifacts_dir: &Path) -> Result<Self, GrapevineCircuitError> {
+        // read in the public params
+        let params_path = artifacts_dir.join("public_params.json");
+        let params_file = std::fs::read_to_string(&params_path).map_err(|e| {
+            GrapevineCircuitError::Artifact(format!("{}: {}", params_path.display(), e))
+        })?;
+        let params: Params = serde_json::from_str(&params_file).map_err(|e| {
+            GrapevineCircuitError::Artifact(format!("{}: {}", params_path.display(), e))
+        })?;
+        // read in the r1cs
+        let r1cs_path = artifacts_dir.join("grapevine.r1cs");
+        if !r1cs_path.is_file() {
+            return Err(GrapevineCircuitError::Artifact(format!(
+                "{} does not exist",
+                r1cs_path.display()
+            )));
+        }
+        let r1cs = load_r1cs::<G1, G2>(&FileLocation::PathBuf(r1cs_path));
+        // resolve the witness calculator
+        let wc_path = ["grapevine.wasm", "grapevine_js/grapevine.wasm"]
+            .iter()
+            .map(|path| artifacts_dir.join(path))
+            .find(|path| path.is_file())
+            .ok_or_else(|| {
+                GrapevineCircuitError::Artifact(format!(
+                    "No grapevine.wasm found in {}",
+                    artifacts_dir.display()
+                ))
+            })?;
+        Ok(Self {
+            params,
+            r1cs,
+            wc_path,
+        })
+    }
+
+    /**
+     * Prove knowledge of a phrase as the first degree of a new proof chain
+     *
+     * @param phrase - the secret phrase to prove knowledge of
+     * @param account - the account originating the phrase
+     * @returns - the compressed proof ready to submit to the server
+     */
+    pub fn prove_phrase(
+        &self,
+        phrase: &String,
+        account: &GrapevineAccount,
+    ) -> Result<Vec<u8>, GrapevineCircuitError> {
+        let proof = nova_proof(
+            self.wc_path.clone(),
+            &self.r1cs,
+            &self.params,
+            phrase,
+            &vec![account.username().clone()],
+            &vec![account.auth_secret().clone()],
+        )?;
+        compress_proof(&proof)
+    }
+
+    /**
+     * Prove the next degree of separation on top of a proof retrieved from the server
+     *
+     * @param proving_data - the proof and encrypted auth secret returned by /proof/params/<oid>
+     * @param account - the account the auth secret was encrypted to
+     * @returns - the compressed proof of degree proving_data.degree + 1 ready to submit to the server
+     */
+    pub fn continue_proof(
+        &self,
+        proving_data: &ProvingData,
+        account: &GrapevineAccount,
+    ) -> Result<Vec<u8>, GrapevineCircuitError> {
+        // decrypt the auth secret of the previous user
+        let auth_secret_encrypted = AuthSecretEncrypted {
+            username: proving_data.username.clone(),
+            recipient: account.pubkey().compress(),
+            ephemeral_key: proving_data.ephemeral_key,
+            ciphertext: proving_data.ciphertext,
+            nonce: proving_data.nonce,
+        };
+        let auth_secret = account
+            .decrypt_auth_secret(auth_secret_encrypted)
+            .map_err(|_| {
+                GrapevineCircuitError::WitnessGeneration(format!(
+                    "Could not decrypt auth secret from {}",
+                    proving_data.username
+                ))
+            })?;
+        // verify the previous proof to get its output
+        let mut proof = decompress_proof(&proving_data.proof)?;
+        let iterations = proving_data.degree as usize * 2;
+        let (previous_output, _) = verify_nova_proof(&proof, &self.params, iterations)?;
+        // fold in the next degree
+        continue_nova_proof(
+            &vec![auth_secret.username, account.username().clone()],
+            &vec![auth_secret.auth_secret, account.auth_secret().clone()],
+            &mut proof,
+            previous_output,
+            self.wc_path.clone(),
+            &self.r1cs,
+            &self.params,
+        )?;
+        compress_proof(&proof)
+    }
+
+    /** Return the public params used by this prover */
+    pub fn params(&self) -> &Params {
+        &self.params
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::env::current_dir;
+
+    #[test]
+    fn test_prover() {
+        let prover =
+            GrapevineProver::new(&current_dir().unwrap().join("circom/artifacts")).unwrap();
+        let user_a = GrapevineAccount::new(String::from("prover_a"));
+        let user_b = GrapevineAccount::new(String::from("prover_b"));
+
+        // originate a phrase as A
+        let phrase = String::from("Two birds with one stone");
+        let phrase_proof = prover.prove_phrase(&phrase, &user_a).unwrap();
+        let proof = decompress_proof(&phrase_proof).unwrap();
+        verify_nova_proof(&proof, prover.params(), 2).unwrap();
+
+        // continue the proof as B using the auth secret A shared with B
+        let encrypted = user_a.encrypt_auth_secret(user_b.pubkey());
+        let proving_data = ProvingData {
+            degree: 1,
+            proof: phrase_proof,
+            username: user_a.username().clone(),
+            ephemeral_key: encrypted.ephemeral_key,
+            ciphertext: encrypted.ciphertext,
+            nonce: encrypted.nonce,
+        };
+        let degree_proof = prover.continue_proof(&proving_data, &user_b).unwrap();
+        let proof = decompress_proof(&degree_proof).unwrap();
+        verify_nova_proof(&proof, prover.params(), 4).unwrap();
+    }
+
+    #[test]
+    fn test_missing_artifacts() {
+        let res = GrapevineProver::new(Path::new("does/not/exist"));
+        assert!(matches!(res, Err(GrapevineCircuitError::Artifact(_))));
+    }
+}
@@ -0,0 +1,46 @@
+use grapevine_common::MAX_PHRASE_BYTES;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum GrapevineCircuitError {
+    ProofGeneration(String),
+    Verification(String),
+    Decompression(String),
+    Serialization(String),
+    WitnessGeneration(String),
+    Artifact(String),
+    PhraseTooLong(usize),
+}
+
+impl std::fmt::Display for GrapevineCircuitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            GrapevineCircuitError::ProofGeneration(msg) => {
+                write!(f, "Failed to generate proof: {}", msg)
+            }
+            GrapevineCircuitError::Verification(msg) => {
+                write!(f, "Failed to verify proof: {}", msg)
+            }
+            GrapevineCircuitError::Decompression(msg) => {
+                write!(f, "Failed to decompress proof: {}", msg)
+            }
+            GrapevineCircuitError::Serialization(msg) => {
+                write!(f, "Failed to (de)serialize proof: {}", msg)
+            }
+            GrapevineCircuitError::WitnessGeneration(msg) => {
+                write!(f, "Failed to generate witness: {}", msg)
+            }
+            GrapevineCircuitError::Artifact(msg) => {
+                write!(f, "Failed to load circuit artifact: {}", msg)
+            }
+            GrapevineCircuitError::PhraseTooLong(len) => {
+                write!(
+                    f,
+                    "Phrase of {} bytes exceeds the maximum of {}",
+                    len, MAX_PHRASE_BYTES
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for GrapevineCircuitError {}
@@ -1,5 +1,9 @@
 use grapevine_common::{Fr, Fq, SECRET_FIELD_LENGTH};
+pub mod client;
+pub mod errors;
 pub mod nova;
+pub mod outputs;
+pub mod proof_system;
 pub mod utils;
 
 pub const ZERO: &str = "0x0000000000000000000000000000000000000000000000000000000000000000";
@@ -8,6 +12,9 @@ pub const DEFAULT_WC_PATH: &str = "crates/grapevine_circuits/circom/artifacts/fo
 pub const DEFAULT_R1CS_PATH: &str = "crates/grapevine_circuits/circom/artifacts/folded.r1cs";
 pub const DEFAULT_PUBLIC_PARAMS_PATH: &str =
     "crates/grapevine_circuits/circom/artifacts/public_params.json";
+/** The file next to public_params.json recording the digest of the r1cs they were set up for */
+pub const PARAMS_R1CS_DIGEST_FILE: &str = "public_params.r1cs.sha256";
+pub const CIRCUIT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 /**
  * Default start input is 0 for all elements
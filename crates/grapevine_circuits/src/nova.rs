@@ -1,14 +1,15 @@
 use super::{
+    errors::GrapevineCircuitError,
     start_input,
-    utils::{build_step_inputs, read_public_params},
+    utils::{build_step_inputs, decompress_proof, read_public_params},
     z0_secondary, DEFAULT_PUBLIC_PARAMS_PATH, DEFAULT_R1CS_PATH,
 };
-use grapevine_common::{Fq, Fr, NovaProof, Params, G1, G2};
+use grapevine_common::{Fq, Fr, NovaProof, Params, G1, G2, MAX_PHRASE_BATCH, MAX_PHRASE_BYTES};
 use nova_scotia::{
     circom::{circuit::R1CS, reader::load_r1cs},
     continue_recursive_circuit, create_recursive_circuit, FileLocation,
 };
-use nova_snark::errors::NovaError;
+use rayon::prelude::*;
 use std::{env::current_dir, path::PathBuf};
 
 /**
@@ -41,6 +42,28 @@ pub fn get_r1cs(path: Option<String>) -> R1CS<Fr> {
     load_r1cs::<G1, G2>(&FileLocation::PathBuf(r1cs_file))
 }
 
+/**
+ * Check that public params were generated for a given r1cs of the grapevine circuit
+ * @dev compares the r1cs digest recorded when the params were set up (PARAMS_R1CS_DIGEST_FILE, as
+ *      written by `sha256sum grapevine.r1cs`) rather than rebuilding the params from the r1cs,
+ *      which costs as much as the setup itself. Nova only reports a mismatch as a failed
+ *      verification
+ *
+ * @param r1cs_hash - the sha256 digest of the r1cs of the grapevine circuit
+ * @param recorded - the contents of the digest file recorded alongside the public params
+ * @return - Ok if the params were set up for the r1cs, or an Artifact error naming both digests
+ */
+pub fn check_compatibility(r1cs_hash: &str, recorded: &str) -> Result<(), GrapevineCircuitError> {
+    let recorded_hash = recorded.split_whitespace().next().unwrap_or_default();
+    if !recorded_hash.eq_ignore_ascii_case(r1cs_hash) {
+        return Err(GrapevineCircuitError::Artifact(format!(
+            "Public params were set up for the r1cs with digest {:?} but the r1cs has digest {}",
+            recorded_hash, r1cs_hash
+        )));
+    }
+    Ok(())
+}
+
 /**
  * Create a nova proof for N degrees of separation, where N is the length of the usernames vector - 1
  * @notice - proving knowledge of preimage is degree 0 hence 1 username means N = 0
@@ -51,6 +74,9 @@ pub fn get_r1cs(path: Option<String>) -> R1CS<Fr> {
  * @param phrase - the secret phrase to prove knowledge of
  * @param usernames - the usernames to use in the chain of degrees of separation
  * @param auth_secrets - the auth secrets to use to make it impossible to prove degree of separation without previous user giving the secret
+ * @return - the proof, a PhraseTooLong error if the phrase is over MAX_PHRASE_BYTES, or a
+ *           WitnessGeneration error if the inputs cannot be marshalled into the circuit and a
+ *           ProofGeneration error if folding fails
  */
 pub fn nova_proof(
     wc_path: PathBuf,
@@ -59,7 +85,19 @@ pub fn nova_proof(
     phrase: &String,
     usernames: &Vec<String>,
     auth_secrets: &Vec<Fr>,
-) -> Result<NovaProof, std::io::Error> {
+) -> Result<NovaProof, GrapevineCircuitError> {
+    if usernames.len() == 0 || usernames.len() != auth_secrets.len() {
+        return Err(GrapevineCircuitError::WitnessGeneration(format!(
+            "Expected the same nonzero number of usernames and auth secrets, got {} and {}",
+            usernames.len(),
+            auth_secrets.len()
+        )));
+    }
+    // the circuit packs the phrase's utf-8 bytes and cannot encode more, so fail before spending
+    // time on a proof
+    if phrase.len() > MAX_PHRASE_BYTES {
+        return Err(GrapevineCircuitError::PhraseTooLong(phrase.len()));
+    }
     // marshall private inputs into circom inputs
     let mut private_inputs = Vec::new();
     for i in 0..usernames.len() {
@@ -91,7 +129,7 @@ pub fn nova_proof(
             phrase_input,
             username_input,
             auth_secret_input,
-        );
+        )?;
     }
 
     // generate the a recursive Nova proof of the grapevine circuit
@@ -102,35 +140,111 @@ pub fn nova_proof(
         start_input().to_vec(),
         &public_params,
     )
+    .map_err(|e| GrapevineCircuitError::ProofGeneration(e.to_string()))
+}
+
+/**
+ * Create a degree 1 nova proof for each of several phrases for submission as one batch
+ * @notice - the grapevine circuit folds a single phrase, so each phrase gets an independent proof
+ *
+ * @param wc_path - the relative path to the witness generator file
+ * @param r1cs - the r1cs of the grapevine circuit
+ * @param public_params - the public params to use to compute the proofs
+ * @param phrases - the secret phrases to prove knowledge of (at most MAX_PHRASE_BATCH)
+ * @param username - the username of the user creating the phrases
+ * @param auth_secret - the auth secret of the user creating the phrases
+ * @return - the proof for each phrase in the order given, or a WitnessGeneration error if more
+ *           than MAX_PHRASE_BATCH phrases are given
+ */
+pub fn nova_proof_batch(
+    wc_path: PathBuf,
+    r1cs: &R1CS<Fr>,
+    public_params: &Params,
+    phrases: &Vec<String>,
+    username: &String,
+    auth_secret: &Fr,
+) -> Result<Vec<NovaProof>, GrapevineCircuitError> {
+    if phrases.len() > MAX_PHRASE_BATCH {
+        return Err(GrapevineCircuitError::WitnessGeneration(format!(
+            "Batch of {} phrases exceeds the maximum of {}",
+            phrases.len(),
+            MAX_PHRASE_BATCH
+        )));
+    }
+    let usernames = vec![username.clone()];
+    let auth_secrets = vec![auth_secret.clone()];
+    phrases
+        .iter()
+        .map(|phrase| {
+            nova_proof(
+                wc_path.clone(),
+                r1cs,
+                public_params,
+                phrase,
+                &usernames,
+                &auth_secrets,
+            )
+        })
+        .collect()
 }
 
 /**
  * Verify the correct execution of a nova-grapevine proof of the grapevine circuit
+ * @dev there is no step by step (early exit) mode: a RecursiveSNARK only keeps the running
+ *      instance folded from every step and the instance of the last step, so the steps cannot be
+ *      checked one at a time. Verification is a single satisfiability check of those instances
+ *      whose cost does not grow with the number of iterations, and nova rejects a proof folded
+ *      over a different number of iterations before running it
  *
  * @param proof - the proof to verify
  * @param public_params - the public params to use to verify the proof
  * @param iterations - the number of iterations to run the verification ((degrees_of_separation + 1) * 2)
- * @return - true if the proof is valid, false otherwise
+ * @return - the primary and secondary outputs of the proof, or a Verification error if the proof
+ *           is invalid
  */
 pub fn verify_nova_proof(
     proof: &NovaProof,
     public_params: &Params,
     iterations: usize,
-) -> Result<(Vec<Fr>, Vec<Fq>), NovaError> {
-    proof.verify(public_params, iterations, &start_input(), &z0_secondary())
+) -> Result<(Vec<Fr>, Vec<Fq>), GrapevineCircuitError> {
+    proof
+        .verify(public_params, iterations, &start_input(), &z0_secondary())
+        .map_err(|e| GrapevineCircuitError::Verification(format!("{:?}", e)))
+}
+
+/**
+ * Decompress and verify many independent nova-grapevine proofs in parallel on the rayon pool
+ *
+ * @param proofs - the gzip-compressed proofs to verify, each with its number of iterations
+ * @param public_params - the public params to use to verify the proofs
+ * @return - the primary and secondary outputs of each proof in the order given, or a
+ *           Decompression or Verification error for each proof that is invalid
+ */
+pub fn verify_nova_proofs(
+    proofs: &[(Vec<u8>, usize)],
+    public_params: &Params,
+) -> Vec<Result<(Vec<Fr>, Vec<Fq>), GrapevineCircuitError>> {
+    proofs
+        .par_iter()
+        .map(|(proof, iterations)| {
+            decompress_proof(proof)
+                .and_then(|proof| verify_nova_proof(&proof, public_params, *iterations))
+        })
+        .collect()
 }
 
 /**
  * Prove another degree of separation using an existing proof from a grapevine circuit
  *
  * @param usernames - the usernames to use in the chain of degrees of separation [prev username, current username]
- *   - todo: check size of usernames is 2
  * @param auth_secrets - the auth_secrets to use to obscure hash at each degree of separation
  * @param proof - the proof of degrees of separation to incrementally prove
  * @param previous_output - the output of the previous proof (z_last)
  * @param wc_path - the relative path to the witness calculator file (if none use default)
  * @param r1cs - the r1cs of the grapevine circuit
  * @param public_params - the public params to use to compute the proof
+ * @return - a WitnessGeneration error if the inputs cannot be marshalled into the circuit and a
+ *           ProofGeneration error if the proof cannot be folded from the previous output
  */
 pub fn continue_nova_proof(
     usernames: &Vec<String>,
@@ -140,9 +254,21 @@ pub fn continue_nova_proof(
     wc_path: PathBuf,
     r1cs: &R1CS<Fr>,
     public_params: &Params,
-) -> Result<(), std::io::Error> {
-    // get WC file location
-    let root = current_dir().unwrap();
+) -> Result<(), GrapevineCircuitError> {
+    if usernames.len() != 2 || auth_secrets.len() != 2 {
+        return Err(GrapevineCircuitError::WitnessGeneration(format!(
+            "Expected 2 usernames and 2 auth secrets, got {} and {}",
+            usernames.len(),
+            auth_secrets.len()
+        )));
+    }
+    if previous_output.len() != start_input().len() {
+        return Err(GrapevineCircuitError::ProofGeneration(format!(
+            "Expected previous output of {} elements, got {}",
+            start_input().len(),
+            previous_output.len()
+        )));
+    }
 
     // compute the private inputs for this degree's compute/ chaff step
     let mut private_inputs = Vec::new();
@@ -151,7 +277,7 @@ pub fn continue_nova_proof(
         None,
         [Some(usernames[0].clone()), Some(usernames[1].clone())],
         [Some(auth_secrets[0]), Some(auth_secrets[1])],
-    );
+    )?;
 
     // compute the next round of the proof
     continue_recursive_circuit(
@@ -163,6 +289,7 @@ pub fn continue_nova_proof(
         start_input().to_vec(),
         &public_params,
     )
+    .map_err(|e| GrapevineCircuitError::ProofGeneration(e.to_string()))
 }
 
 // /**
@@ -181,6 +308,7 @@ pub fn continue_nova_proof(
 mod test {
     use super::*;
     use crate::utils::{compress_proof, decompress_proof, read_proof, write_proof};
+    use crate::PARAMS_R1CS_DIGEST_FILE;
     use grapevine_common::utils::random_fr;
 
     #[test]
@@ -215,6 +343,55 @@ mod test {
         println!("Verified: {:?}", verified);
     }
 
+    #[test]
+    fn test_check_compatibility() {
+        let digest_path = current_dir()
+            .unwrap()
+            .join("circom/artifacts")
+            .join(PARAMS_R1CS_DIGEST_FILE);
+        let recorded = std::fs::read_to_string(digest_path).unwrap();
+        let r1cs_hash = recorded.split_whitespace().next().unwrap();
+        assert!(check_compatibility(r1cs_hash, &recorded).is_ok());
+        assert!(check_compatibility(&r1cs_hash.to_uppercase(), &recorded).is_ok());
+
+        // params set up for a different circuit are rejected before any proof is verified
+        let res = check_compatibility(&"0".repeat(64), &recorded);
+        assert!(matches!(
+            res,
+            Err(GrapevineCircuitError::Artifact(msg)) if msg.contains(r1cs_hash)
+        ));
+        // as are params with no digest recorded
+        let res = check_compatibility(r1cs_hash, "");
+        assert!(matches!(res, Err(GrapevineCircuitError::Artifact(_))));
+    }
+
+    #[test]
+    fn test_phrase_too_long() {
+        let phrase = "a".repeat(MAX_PHRASE_BYTES + 1);
+        let usernames = vec![String::from("mach34")];
+        let auth_secrets = vec![random_fr()];
+        let params_path = String::from("circom/artifacts/public_params.json");
+        let r1cs_path = String::from("circom/artifacts/grapevine.r1cs");
+        let wc_path = current_dir()
+            .unwrap()
+            .join("circom/artifacts/grapevine_js/grapevine.wasm");
+        let r1cs = get_r1cs(Some(r1cs_path));
+        let public_params = get_public_params(Some(params_path));
+
+        let res = nova_proof(
+            wc_path,
+            &r1cs,
+            &public_params,
+            &phrase,
+            &usernames,
+            &auth_secrets,
+        );
+        assert!(matches!(
+            res,
+            Err(GrapevineCircuitError::PhraseTooLong(len)) if len == MAX_PHRASE_BYTES + 1
+        ));
+    }
+
     #[test]
     fn test_degree_1() {
         // Test proving knowledge of a secret (1 degree of separation) and the second degree of separation
@@ -468,7 +645,7 @@ mod test {
         .unwrap();
 
         // compress the proof
-        let compressed_proof = compress_proof(&proof);
+        let compressed_proof = compress_proof(&proof).unwrap();
 
         // compare proof sizes when compressed vs uncompressed
         let serialized = serde_json::to_string(&proof).unwrap().as_bytes().to_vec();
@@ -476,10 +653,145 @@ mod test {
         println!("Compressed proof size: {}", compressed_proof.len());
 
         // decompress the proof
-        let decompressed_proof = decompress_proof(&compressed_proof[..]);
+        let decompressed_proof = decompress_proof(&compressed_proof[..]).unwrap();
 
         // verify the compressed then uncompressed proof
         let iterations = usernames.len() * 2;
         verify_nova_proof(&decompressed_proof, &public_params, iterations).unwrap();
     }
+
+    #[test]
+    fn test_mismatched_inputs() {
+        let phrase = String::from("Not enough secrets to go around");
+        let usernames = vec![String::from("mach34"), String::from("jp4g")];
+        let auth_secrets = vec![random_fr()];
+        let r1cs_path = String::from("circom/artifacts/grapevine.r1cs");
+        let params_path = String::from("circom/artifacts/public_params.json");
+        let wc_path = current_dir()
+            .unwrap()
+            .join("circom/artifacts/grapevine_js/grapevine.wasm");
+        let r1cs = get_r1cs(Some(r1cs_path));
+        let public_params = get_public_params(Some(params_path));
+
+        let res = nova_proof(
+            wc_path,
+            &r1cs,
+            &public_params,
+            &phrase,
+            &usernames,
+            &auth_secrets,
+        );
+        assert!(matches!(
+            res,
+            Err(GrapevineCircuitError::WitnessGeneration(_))
+        ));
+    }
+
+    #[test]
+    fn test_verification_failure() {
+        // verify a degree 1 proof as if it were a degree 2 proof
+        let phrase = String::from("Counting is hard");
+        let usernames = vec![String::from("mach34")];
+        let auth_secrets = vec![random_fr()];
+        let r1cs_path = String::from("circom/artifacts/grapevine.r1cs");
+        let params_path = String::from("circom/artifacts/public_params.json");
+        let wc_path = current_dir()
+            .unwrap()
+            .join("circom/artifacts/grapevine_js/grapevine.wasm");
+        let r1cs = get_r1cs(Some(r1cs_path));
+        let public_params = get_public_params(Some(params_path));
+        let proof = nova_proof(
+            wc_path,
+            &r1cs,
+            &public_params,
+            &phrase,
+            &usernames,
+            &auth_secrets,
+        )
+        .unwrap();
+
+        let res = verify_nova_proof(&proof, &public_params, 4);
+        assert!(matches!(res, Err(GrapevineCircuitError::Verification(_))));
+    }
+
+    #[test]
+    fn test_continue_with_invalid_previous_output() {
+        let phrase = String::from("Where did the output go");
+        let usernames = vec![String::from("mach34"), String::from("jp4g")];
+        let auth_secrets = vec![random_fr(), random_fr()];
+        let r1cs_path = String::from("circom/artifacts/grapevine.r1cs");
+        let params_path = String::from("circom/artifacts/public_params.json");
+        let wc_path = current_dir()
+            .unwrap()
+            .join("circom/artifacts/grapevine_js/grapevine.wasm");
+        let r1cs = get_r1cs(Some(r1cs_path));
+        let public_params = get_public_params(Some(params_path));
+        let mut proof = nova_proof(
+            wc_path.clone(),
+            &r1cs,
+            &public_params,
+            &phrase,
+            &vec![usernames[0].clone()],
+            &vec![auth_secrets[0]],
+        )
+        .unwrap();
+
+        let res = continue_nova_proof(
+            &usernames,
+            &auth_secrets,
+            &mut proof,
+            vec![],
+            wc_path,
+            &r1cs,
+            &public_params,
+        );
+        assert!(matches!(
+            res,
+            Err(GrapevineCircuitError::ProofGeneration(_))
+        ));
+    }
+
+    #[test]
+    fn test_batch_verification() {
+        // compress two valid proofs around one corrupted proof
+        let usernames = vec![String::from("mach34")];
+        let auth_secrets = vec![random_fr()];
+        let r1cs_path = String::from("circom/artifacts/grapevine.r1cs");
+        let params_path = String::from("circom/artifacts/public_params.json");
+        let wc_path = current_dir()
+            .unwrap()
+            .join("circom/artifacts/grapevine_js/grapevine.wasm");
+        let r1cs = get_r1cs(Some(r1cs_path));
+        let public_params = get_public_params(Some(params_path));
+        let phrases = vec![
+            String::from("First of the batch"),
+            String::from("Middle of the batch"),
+            String::from("Last of the batch"),
+        ];
+        let mut proofs = nova_proof_batch(
+            wc_path,
+            &r1cs,
+            &public_params,
+            &phrases,
+            &usernames[0],
+            &auth_secrets[0],
+        )
+        .unwrap()
+        .iter()
+        .map(|proof| (compress_proof(proof).unwrap(), 2))
+        .collect::<Vec<(Vec<u8>, usize)>>();
+        let middle = proofs[1].0.len() / 2;
+        proofs[1].0[middle] ^= 0xff;
+
+        // each proof gets its own result in the order given
+        let results = verify_nova_proofs(&proofs, &public_params);
+        assert_eq!(results.len(), 3);
+        assert!(results[1].is_err());
+        for i in [0, 2] {
+            let expected =
+                verify_nova_proof(&decompress_proof(&proofs[i].0).unwrap(), &public_params, 2)
+                    .unwrap();
+            assert_eq!(results[i].as_ref().unwrap().0, expected.0);
+        }
+    }
 }
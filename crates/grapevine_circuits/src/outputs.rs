@@ -0,0 +1,94 @@
+use crate::errors::GrapevineCircuitError;
+use grapevine_common::{Fq, Fr};
+
+/** The number of primary outputs (step_out) of the grapevine circuit */
+pub const PRIMARY_OUTPUTS: usize = 4;
+
+/**
+ * The primary outputs of a verified grapevine proof, in the order of the circuit's step_out
+ * @dev the layout of step_out is only documented here, so callers never index into it
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct PublicOutputs {
+    // the degrees of separation proven (the number of compute steps, so N for N * 2 iterations)
+    pub degree: Fr,
+    // the poseidon hash of the phrase at the start of the chain
+    pub phrase_hash: Fr,
+    // the hash of the phrase hash with the auth secret of the last user in the chain
+    pub auth_hash: Fr,
+    // whether the next step folded must be a chaff step (0 for a complete proof, which ends on one)
+    pub chaff: Fr,
+}
+
+impl PublicOutputs {
+    /**
+     * Name the outputs returned by verifying a grapevine proof
+     *
+     * @param res - the primary and secondary outputs returned by verification
+     * @return - the named primary outputs, or a Verification error if the proof did not output
+     *           PRIMARY_OUTPUTS primary outputs
+     */
+    pub fn from_verification(res: &(Vec<Fr>, Vec<Fq>)) -> Result<Self, GrapevineCircuitError> {
+        match res.0.as_slice() {
+            [degree, phrase_hash, auth_hash, chaff] => Ok(PublicOutputs {
+                degree: *degree,
+                phrase_hash: *phrase_hash,
+                auth_hash: *auth_hash,
+                chaff: *chaff,
+            }),
+            outputs => Err(GrapevineCircuitError::Verification(format!(
+                "Expected {} primary outputs, got {}",
+                PRIMARY_OUTPUTS,
+                outputs.len()
+            ))),
+        }
+    }
+}
+
+/**
+ * The number of iterations a proof of a stored degree was folded over
+ * @dev a degree 0 knowledge proof is a single compute step for the prover alone, so it is folded
+ *      over the same 2 iterations as a degree 1 proof and only differs in how it is stored
+ *
+ * @param degree - the degree the proof is stored with
+ * @return - the number of iterations to verify the proof with
+ */
+pub fn degree_iterations(degree: u8) -> usize {
+    match degree {
+        0 => 2,
+        degree => degree as usize * 2,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_from_verification() {
+        let res = (
+            vec![Fr::from(3), Fr::from(11), Fr::from(12), Fr::from(0)],
+            vec![Fq::from(0)],
+        );
+        let outputs = PublicOutputs::from_verification(&res).unwrap();
+        assert_eq!(outputs.degree, Fr::from(3));
+        assert_eq!(outputs.phrase_hash, Fr::from(11));
+        assert_eq!(outputs.auth_hash, Fr::from(12));
+        assert_eq!(outputs.chaff, Fr::from(0));
+
+        // a proof with a different output layout is rejected rather than misread
+        let res = (vec![Fr::from(3), Fr::from(11)], vec![Fq::from(0)]);
+        assert!(matches!(
+            PublicOutputs::from_verification(&res),
+            Err(GrapevineCircuitError::Verification(_))
+        ));
+    }
+
+    #[test]
+    fn test_degree_iterations() {
+        // knowledge proofs fold the same steps as the proof originating a phrase
+        assert_eq!(degree_iterations(0), 2);
+        assert_eq!(degree_iterations(1), 2);
+        assert_eq!(degree_iterations(4), 8);
+    }
+}
@@ -0,0 +1,189 @@
+use crate::errors::GrapevineCircuitError;
+use crate::nova::{nova_proof, verify_nova_proof};
+use crate::utils::{compress_proof, decompress_proof};
+use grapevine_common::{Fq, Fr, NovaProof, Params};
+use nova_scotia::circom::circuit::R1CS;
+use rayon::prelude::*;
+use std::path::PathBuf;
+
+/**
+ * A backend that proves and verifies execution of the grapevine circuit
+ * @dev the backend owns whatever artifacts it proves and verifies with, so callers only pass the
+ *      inputs of the circuit
+ */
+pub trait ProofSystem: Send + Sync {
+    /** The proof produced by the backend before compression */
+    type Proof;
+
+    /**
+     * Prove N degrees of separation, where N is the length of the usernames vector - 1
+     *
+     * @param phrase - the secret phrase to prove knowledge of
+     * @param usernames - the usernames to use in the chain of degrees of separation
+     * @param auth_secrets - the auth secret of each user in the chain
+     * @return - the proof, or the error raised while proving
+     */
+    fn prove(
+        &self,
+        phrase: &String,
+        usernames: &Vec<String>,
+        auth_secrets: &Vec<Fr>,
+    ) -> Result<Self::Proof, GrapevineCircuitError>;
+
+    /**
+     * Verify the correct execution of the grapevine circuit
+     *
+     * @param proof - the proof to verify
+     * @param iterations - the number of iterations the proof claims (degree * 2)
+     * @return - the primary and secondary outputs of the proof, or a Verification error if the
+     *           proof is invalid
+     */
+    fn verify(
+        &self,
+        proof: &Self::Proof,
+        iterations: usize,
+    ) -> Result<(Vec<Fr>, Vec<Fq>), GrapevineCircuitError>;
+
+    /**
+     * Compress a proof for transit to the server and storage
+     *
+     * @param proof - the proof to compress
+     * @return - the compressed proof, or a Serialization error if the proof could not be encoded
+     */
+    fn compress(&self, proof: &Self::Proof) -> Result<Vec<u8>, GrapevineCircuitError>;
+
+    /**
+     * Decompress a proof received or stored by the server
+     *
+     * @param proof - the compressed proof
+     * @return - the proof, or a Decompression or Serialization error if the bytes are malformed
+     */
+    fn decompress(&self, proof: &[u8]) -> Result<Self::Proof, GrapevineCircuitError>;
+}
+
+/**
+ * Verify compressed proofs without naming the proof type of the backend, so a verifier can be
+ * chosen at runtime
+ * @dev implemented for every ProofSystem
+ */
+pub trait CompressedVerifier: Send + Sync {
+    /**
+     * Decompress and verify a proof
+     *
+     * @param proof - the compressed proof to verify
+     * @param iterations - the number of iterations the proof claims (degree * 2)
+     * @return - the primary and secondary outputs of the proof, or the error raised decompressing
+     *           or verifying it
+     */
+    fn verify_compressed(
+        &self,
+        proof: &[u8],
+        iterations: usize,
+    ) -> Result<(Vec<Fr>, Vec<Fq>), GrapevineCircuitError>;
+
+    /**
+     * Decompress and verify many independent proofs in parallel on the rayon pool
+     *
+     * @param proofs - the compressed proofs to verify, each with its number of iterations
+     * @return - the outputs of each proof in the order given, or the error for each proof that is
+     *           invalid
+     */
+    fn verify_compressed_batch(
+        &self,
+        proofs: &[(Vec<u8>, usize)],
+    ) -> Vec<Result<(Vec<Fr>, Vec<Fq>), GrapevineCircuitError>>;
+}
+
+impl<T: ProofSystem> CompressedVerifier for T {
+    fn verify_compressed(
+        &self,
+        proof: &[u8],
+        iterations: usize,
+    ) -> Result<(Vec<Fr>, Vec<Fq>), GrapevineCircuitError> {
+        self.decompress(proof)
+            .and_then(|proof| self.verify(&proof, iterations))
+    }
+
+    fn verify_compressed_batch(
+        &self,
+        proofs: &[(Vec<u8>, usize)],
+    ) -> Vec<Result<(Vec<Fr>, Vec<Fq>), GrapevineCircuitError>> {
+        proofs
+            .par_iter()
+            .map(|(proof, iterations)| self.verify_compressed(proof, *iterations))
+            .collect()
+    }
+}
+
+/** The Nova folding backend the grapevine circuit is built for */
+pub struct NovaProofSystem {
+    public_params: Params,
+    // the witness calculator and r1cs to prove with, if the backend proves as well as verifies
+    prover: Option<(PathBuf, R1CS<Fr>)>,
+}
+
+impl NovaProofSystem {
+    /**
+     * Create a Nova backend that verifies proofs
+     *
+     * @param public_params - the public params to verify (and prove) with
+     * @return - the backend
+     */
+    pub fn new(public_params: Params) -> Self {
+        NovaProofSystem {
+            public_params,
+            prover: None,
+        }
+    }
+
+    /**
+     * Load the circuit artifacts needed to prove as well as verify
+     *
+     * @param wc_path - the path to the witness calculator wasm of the grapevine circuit
+     * @param r1cs - the r1cs of the grapevine circuit
+     * @return - the backend able to prove
+     */
+    pub fn with_prover(mut self, wc_path: PathBuf, r1cs: R1CS<Fr>) -> Self {
+        self.prover = Some((wc_path, r1cs));
+        self
+    }
+}
+
+impl ProofSystem for NovaProofSystem {
+    type Proof = NovaProof;
+
+    fn prove(
+        &self,
+        phrase: &String,
+        usernames: &Vec<String>,
+        auth_secrets: &Vec<Fr>,
+    ) -> Result<NovaProof, GrapevineCircuitError> {
+        let (wc_path, r1cs) = self.prover.as_ref().ok_or_else(|| {
+            GrapevineCircuitError::Artifact(String::from("No r1cs or witness calculator loaded"))
+        })?;
+        nova_proof(
+            wc_path.clone(),
+            r1cs,
+            &self.public_params,
+            phrase,
+            usernames,
+            auth_secrets,
+        )
+    }
+
+    fn verify(
+        &self,
+        proof: &NovaProof,
+        iterations: usize,
+    ) -> Result<(Vec<Fr>, Vec<Fq>), GrapevineCircuitError> {
+        verify_nova_proof(proof, &self.public_params, iterations)
+    }
+
+    fn compress(&self, proof: &NovaProof) -> Result<Vec<u8>, GrapevineCircuitError> {
+        compress_proof(proof)
+    }
+
+    fn decompress(&self, proof: &[u8]) -> Result<NovaProof, GrapevineCircuitError> {
+        decompress_proof(proof)
+    }
+}
@@ -1,3 +1,4 @@
+use crate::errors::GrapevineCircuitError;
 use crate::{EMPTY_SECRET, SECRET_FIELD_LENGTH, ZERO};
 use flate2::read::GzDecoder;
 use flate2::write::GzEncoder;
@@ -16,20 +17,19 @@ use std::{collections::HashMap, env::current_dir};
  * @param secret - optionally provide the secret to prove knowledge of if degree is 0
  * @param username - optionally provide one or both usernames to hash against
  *   - note: usernames[1] will never be 0 in practice
- * @return - the inputs for one computation step and chaff step
+ * @return - the inputs for one computation step and chaff step, or a WitnessGeneration error if
+ *           the phrase or a username cannot be encoded as circuit inputs
  */
 pub fn build_step_inputs(
     input: &mut Vec<HashMap<String, Value>>,
     secret: Option<String>,
     usernames: [Option<String>; 2],
     auth_secrets: [Option<Fr>; 2],
-) {
-    // @TODO: FIX convert_phrase_to_fr and convert_username_to_fr inputs
-
+) -> Result<(), GrapevineCircuitError> {
     // convert the compute step input to strings, or get the default value
     let secret_input: [String; SECRET_FIELD_LENGTH] = match secret {
         Some(phrase) => convert_phrase_to_fr(&phrase)
-            .unwrap()
+            .map_err(|e| GrapevineCircuitError::WitnessGeneration(e.to_string()))?
             .iter()
             .map(|chunk| format!("0x{}", hex::encode(chunk)))
             .collect::<Vec<String>>()
@@ -46,13 +46,13 @@ pub fn build_step_inputs(
     let usernames_input: [String; 2] = usernames
         .iter()
         .map(|username| match username {
-            Some(username) => format!(
-                "0x{}",
-                hex::encode(convert_username_to_fr(username).unwrap())
-            ),
-            None => String::from(ZERO),
+            Some(username) => match convert_username_to_fr(username) {
+                Ok(bytes) => Ok(format!("0x{}", hex::encode(bytes))),
+                Err(e) => Err(GrapevineCircuitError::WitnessGeneration(e.to_string())),
+            },
+            None => Ok(String::from(ZERO)),
         })
-        .collect::<Vec<String>>()
+        .collect::<Result<Vec<String>, GrapevineCircuitError>>()?
         .try_into()
         .unwrap();
     let auth_secrets_input: [String; 2] = auth_secrets
@@ -79,6 +79,7 @@ pub fn build_step_inputs(
     // push the compute and chaff step inputs to the input vector
     input.push(compute_step);
     input.push(chaff_step);
+    Ok(())
 }
 
 /**
@@ -111,7 +112,7 @@ pub fn read_public_params<G1, G2>(path: &str) -> Params {
  */
 pub fn write_proof(proof: &NovaProof, path: std::path::PathBuf) {
     // compress the proof
-    let compressed_proof = compress_proof(proof);
+    let compressed_proof = compress_proof(proof).unwrap();
     // write the proof to fs
     std::fs::write(path, compressed_proof).expect("Unable to write proof");
 }
@@ -125,41 +126,65 @@ pub fn read_proof(path: std::path::PathBuf) -> NovaProof {
     // read the proof from fs
     let compressed_proof = std::fs::read(path).expect("Unable to read proof");
     // decompress the proof
-    decompress_proof(&compressed_proof[..])
+    decompress_proof(&compressed_proof[..]).unwrap()
 }
 
 /**
  * Compress a Nova Proof with flate2 for transit to the server and storage
  *
  * @param proof - the Nova Proof to compress
- * @return - the compressed proof
+ * @return - the compressed proof, or a Serialization error if the proof could not be encoded
  */
-pub fn compress_proof(proof: &NovaProof) -> Vec<u8> {
+pub fn compress_proof(proof: &NovaProof) -> Result<Vec<u8>, GrapevineCircuitError> {
     // serialize proof to json
-    let serialized = serde_json::to_string(&proof).unwrap();
+    let serialized = serde_json::to_string(&proof)
+        .map_err(|e| GrapevineCircuitError::Serialization(e.to_string()))?;
     // compress serialized proof
     let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
-    encoder.write_all(serialized.as_bytes()).unwrap();
+    encoder
+        .write_all(serialized.as_bytes())
+        .map_err(|e| GrapevineCircuitError::Serialization(e.to_string()))?;
     // return compressed proof
-    encoder.finish().unwrap()
+    encoder
+        .finish()
+        .map_err(|e| GrapevineCircuitError::Serialization(e.to_string()))
 }
 
 /**
  * Decompress a Nova Proof with flate2 for transit to the server and storage
  *
  * @param proof - the compressed Nova Proof to decompress
- * @return - the decompressed proof
+ * @return - the decompressed proof, or a Decompression error if the bytes are not valid gzip and
+ *           a Serialization error if the decompressed json is not a Nova Proof
  */
-pub fn decompress_proof(proof: &[u8]) -> NovaProof {
+pub fn decompress_proof(proof: &[u8]) -> Result<NovaProof, GrapevineCircuitError> {
     // decompress the proof into the serialized json string
     let mut decoder = GzDecoder::new(proof);
     let mut serialized = String::new();
-    decoder.read_to_string(&mut serialized).unwrap();
+    decoder
+        .read_to_string(&mut serialized)
+        .map_err(|e| GrapevineCircuitError::Decompression(e.to_string()))?;
     // deserialize the proof
-    serde_json::from_str(&serialized).unwrap()
+    serde_json::from_str(&serialized)
+        .map_err(|e| GrapevineCircuitError::Serialization(e.to_string()))
 }
 
+/**
+ * Get the size of a compressed Nova Proof once decompressed without deserializing it
+ *
+ * @param proof - the compressed Nova Proof
+ * @return - the number of bytes of serialized proof, or a Decompression error if the bytes are not
+ *           valid gzip
+ */
+pub fn decompressed_size(proof: &[u8]) -> Result<u64, GrapevineCircuitError> {
+    let mut decoder = GzDecoder::new(proof);
+    std::io::copy(&mut decoder, &mut std::io::sink())
+        .map_err(|e| GrapevineCircuitError::Decompression(e.to_string()))
+}
+
+#[cfg(test)]
 mod test {
+    use super::*;
     use grapevine_common::utils::{convert_phrase_to_fr, convert_username_to_fr};
 
     #[test]
@@ -175,4 +200,51 @@ mod test {
         let bytes = convert_username_to_fr(&username);
         println!("User bytes {:?}", bytes);
     }
+
+    #[test]
+    fn test_decompress_invalid_gzip() {
+        let res = decompress_proof(&[1, 2, 3, 4]);
+        assert!(matches!(res, Err(GrapevineCircuitError::Decompression(_))));
+    }
+
+    #[test]
+    fn test_decompress_invalid_proof() {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(b"{\"not\": \"a proof\"}").unwrap();
+        let compressed = encoder.finish().unwrap();
+        let res = decompress_proof(&compressed);
+        assert!(matches!(res, Err(GrapevineCircuitError::Serialization(_))));
+    }
+
+    #[test]
+    fn test_step_inputs_phrase_too_long() {
+        let phrase = "a".repeat(200);
+        let mut inputs = Vec::new();
+        let res = build_step_inputs(
+            &mut inputs,
+            Some(phrase),
+            [None, Some(String::from("mach34"))],
+            [None, Some(Fr::from(1))],
+        );
+        assert!(matches!(
+            res,
+            Err(GrapevineCircuitError::WitnessGeneration(_))
+        ));
+        assert!(inputs.is_empty());
+    }
+
+    #[test]
+    fn test_step_inputs_username_too_long() {
+        let mut inputs = Vec::new();
+        let res = build_step_inputs(
+            &mut inputs,
+            None,
+            [Some(String::from("mach34")), Some("a".repeat(31))],
+            [Some(Fr::from(1)), Some(Fr::from(2))],
+        );
+        assert!(matches!(
+            res,
+            Err(GrapevineCircuitError::WitnessGeneration(_))
+        ));
+    }
 }
@@ -1,22 +1,25 @@
 use crate::errors::GrapevineCLIError;
 use crate::http::{
-    add_relationship_req, create_user_req, degree_proof_req, get_available_proofs_req, get_degrees_req, get_nonce_req, get_proof_with_params_req, get_pubkey_req, new_phrase_req
+    add_relationship_req, create_user_req, degree_proof_req, get_available_proofs_req, get_challenge_req, get_degrees_req, get_nonce_req, get_proof_with_params_req, get_pubkey_req, new_phrase_batch_req, new_phrase_req
 };
 use crate::utils::artifacts_guard;
 use crate::utils::fs::{use_public_params, use_r1cs, use_wasm, ACCOUNT_PATH};
 use babyjubjub_rs::{decompress_point, PrivateKey};
-use grapevine_circuits::nova::{continue_nova_proof, nova_proof, verify_nova_proof};
+use grapevine_circuits::nova::{
+    continue_nova_proof, nova_proof, nova_proof_batch, verify_nova_proof,
+};
 use grapevine_circuits::utils::{compress_proof, decompress_proof};
 use grapevine_common::account::GrapevineAccount;
 use grapevine_common::auth_secret::AuthSecretEncrypted;
-use grapevine_common::errors::GrapevineServerError;
+use grapevine_common::errors::GrapevineClientError;
 use grapevine_common::http::requests::{
-    CreateUserRequest, DegreeProofRequest, NewPhraseRequest, NewRelationshipRequest,
-    TestProofCompressionRequest,
+    CreateUserRequest, DegreeProofRequest, NewPhraseBatchRequest, NewPhraseRequest,
+    NewRelationshipRequest, TestProofCompressionRequest,
 };
 use grapevine_common::http::responses::DegreeData;
 use grapevine_common::models::proof::ProvingData;
 use grapevine_common::utils::random_fr;
+use grapevine_common::MAX_PHRASE_BYTES;
 
 use std::path::Path;
 
@@ -54,7 +57,7 @@ pub async fn register(username: Option<String>) -> Result<String, GrapevineCLIEr
         None => return Err(GrapevineCLIError::NoInput(String::from("username"))),
     };
     // check username is < 30 chars
-    if username.len() > 30 {
+    if username.chars().count() > 30 {
         return Err(GrapevineCLIError::UsernameTooLong(username));
     }
     // check username is ascii
@@ -63,8 +66,13 @@ pub async fn register(username: Option<String>) -> Result<String, GrapevineCLIEr
     }
     // make account (or retrieve from fs)
     let account = make_or_get_account(username.clone())?;
+    // get a challenge to sign for registration
+    let challenge = match get_challenge_req().await {
+        Ok(challenge) => challenge,
+        Err(e) => return Err(GrapevineCLIError::from(e)),
+    };
     // build request body
-    let body = account.create_user_request();
+    let body = account.create_user_request(challenge);
     // send create user request
     let res = create_user_req(body).await;
     match res {
@@ -138,8 +146,8 @@ pub async fn synchronize_nonce() -> Result<String, GrapevineCLIError> {
  * @param phrase - the phrase to create
  */
 pub async fn create_new_phrase(phrase: String) -> Result<String, GrapevineCLIError> {
-    // check that phrase is > 180 chars
-    if phrase.len() > 180 {
+    // check that phrase is <= MAX_PHRASE_BYTES
+    if phrase.len() > MAX_PHRASE_BYTES {
         return Err(GrapevineCLIError::PhraseTooLong);
     }
     // ensure artifacts are present
@@ -162,7 +170,10 @@ pub async fn create_new_phrase(phrase: String) -> Result<String, GrapevineCLIErr
             return Err(GrapevineCLIError::PhraseCreationProofFailed(phrase));
         }
     };
-    let compressed = compress_proof(&proof);
+    let compressed = match compress_proof(&proof) {
+        Ok(compressed) => compressed,
+        Err(e) => return Err(GrapevineCLIError::PhraseCreationProofFailed(phrase)),
+    };
 
     // build request body
     let body = NewPhraseRequest { proof: compressed };
@@ -174,6 +185,58 @@ pub async fn create_new_phrase(phrase: String) -> Result<String, GrapevineCLIErr
     }
 }
 
+pub async fn create_new_phrases(phrases: Vec<String>) -> Result<String, GrapevineCLIError> {
+    // check that each phrase is <= MAX_PHRASE_BYTES
+    if phrases.iter().any(|phrase| phrase.len() > MAX_PHRASE_BYTES) {
+        return Err(GrapevineCLIError::PhraseTooLong);
+    }
+    // ensure artifacts are present
+    artifacts_guard().await.unwrap();
+    // get account
+    let mut account = get_account()?;
+    // get proving artifacts
+    let params = use_public_params().unwrap();
+    let r1cs = use_r1cs().unwrap();
+    let wc_path = use_wasm().unwrap();
+    // create a proof for each phrase
+    let res = nova_proof_batch(
+        wc_path,
+        &r1cs,
+        &params,
+        &phrases,
+        account.username(),
+        account.auth_secret(),
+    );
+    let proofs = match res {
+        Ok(proofs) => proofs,
+        Err(e) => {
+            return Err(GrapevineCLIError::PhraseCreationProofFailed(
+                phrases.join(", "),
+            ));
+        }
+    };
+    let mut compressed = Vec::new();
+    for proof in proofs {
+        match compress_proof(&proof) {
+            Ok(proof) => compressed.push(proof),
+            Err(e) => {
+                return Err(GrapevineCLIError::PhraseCreationProofFailed(
+                    phrases.join(", "),
+                ))
+            }
+        }
+    }
+
+    // build request body
+    let body = NewPhraseBatchRequest { proofs: compressed };
+    // send request
+    let res = new_phrase_batch_req(&mut account, body).await;
+    match res {
+        Ok(_) => Ok(format!("Created {} new phrases", phrases.len())),
+        Err(e) => Err(GrapevineCLIError::from(e)),
+    }
+}
+
 pub async fn prove_all_available() -> Result<String, GrapevineCLIError> {
     /// GETTING
     // get account
@@ -219,11 +282,25 @@ pub async fn prove_all_available() -> Result<String, GrapevineCLIError> {
         let auth_secret_encrypted = AuthSecretEncrypted {
             ephemeral_key: proving_data.ephemeral_key,
             ciphertext: proving_data.ciphertext,
-            username: proving_data.username,
+            nonce: proving_data.nonce,
+            username: proving_data.username.clone(),
             recipient: account.pubkey().compress(),
         };
-        let auth_secret = account.decrypt_auth_secret(auth_secret_encrypted);
-        let mut proof = decompress_proof(&proving_data.proof);
+        let auth_secret = match account.decrypt_auth_secret(auth_secret_encrypted) {
+            Ok(auth_secret) => auth_secret,
+            Err(_) => {
+                return Err(GrapevineCLIError::AuthSecretDecryptionFailed(
+                    proving_data.username,
+                ))
+            }
+        };
+        let mut proof = match decompress_proof(&proving_data.proof) {
+            Ok(proof) => proof,
+            Err(e) => {
+                println!("Decompression Failed");
+                return Err(GrapevineCLIError::DegreeProofVerificationFailed);
+            }
+        };
         let verified =
             verify_nova_proof(&proof, &public_params, (proving_data.degree * 2) as usize);
         let previous_output = match verified {
@@ -251,7 +328,13 @@ pub async fn prove_all_available() -> Result<String, GrapevineCLIError> {
                 return Err(GrapevineCLIError::DegreeProofVerificationFailed);
             }
         }
-        let compressed = compress_proof(&proof);
+        let compressed = match compress_proof(&proof) {
+            Ok(compressed) => compressed,
+            Err(e) => {
+                println!("Proof compression failed");
+                return Err(GrapevineCLIError::DegreeProofVerificationFailed);
+            }
+        };
         // build request body
         let body = DegreeProofRequest {
             proof: compressed,
@@ -260,7 +343,7 @@ pub async fn prove_all_available() -> Result<String, GrapevineCLIError> {
             degree: proving_data.degree + 1,
         };
         // handle response from server
-        let res: Result<(), GrapevineServerError> = degree_proof_req(&mut account, body).await;
+        let res: Result<(), GrapevineClientError> = degree_proof_req(&mut account, body).await;
         match res {
             Ok(_) => (),
             Err(e) => return Err(GrapevineCLIError::from(e)),
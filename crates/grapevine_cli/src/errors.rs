@@ -1,4 +1,5 @@
-use grapevine_common::errors::GrapevineServerError;
+use grapevine_common::errors::{GrapevineClientError, GrapevineServerError};
+use grapevine_common::MAX_PHRASE_BYTES;
 
 #[derive(Debug)]
 pub enum GrapevineCLIError {
@@ -19,6 +20,7 @@ pub enum GrapevineCLIError {
     PhraseCreationProofFailed(String),
     DegreeProofFailed,
     DegreeProofVerificationFailed,
+    AuthSecretDecryptionFailed(String),
     UnknownServerError,
 }
 
@@ -38,10 +40,11 @@ impl std::fmt::Display for GrapevineCLIError {
             GrapevineCLIError::ServerError(msg) => write!(f, "Server error: {}", msg),
             GrapevineCLIError::FsError(msg) => write!(f, "Filesystem error: {}", msg),
             GrapevineCLIError::SerdeError(msg) => write!(f, "Error deserializing {}", msg),
-            GrapevineCLIError::PhraseTooLong => write!(f, "Phrase must be <= 180 characters"),
+            GrapevineCLIError::PhraseTooLong => write!(f, "Phrase must be <= {} bytes", MAX_PHRASE_BYTES),
             GrapevineCLIError::PhraseCreationProofFailed(msg) => write!(f, "Failed to create proof for new phrase {}", msg),
             GrapevineCLIError::DegreeProofFailed => write!(f, "Failed to create degree proof"),
             GrapevineCLIError::DegreeProofVerificationFailed => write!(f, "Failed to verify degree proof"),
+            GrapevineCLIError::AuthSecretDecryptionFailed(msg) => write!(f, "Failed to decrypt auth secret from {}", msg),
             GrapevineCLIError::UnknownServerError => write!(f, "Unknown server error"),
         }
     }
@@ -57,6 +60,7 @@ impl From<GrapevineServerError> for GrapevineCLIError {
             GrapevineServerError::UsernameTooLong(msg) => GrapevineCLIError::UsernameTooLong(msg),
             GrapevineServerError::UsernameNotAscii(msg) => GrapevineCLIError::UsernameNotAscii(msg),
             GrapevineServerError::UserNotFound(msg) => GrapevineCLIError::UserNotFound(msg),
+            GrapevineServerError::UserGone(msg) => GrapevineCLIError::UserNotFound(msg),
             GrapevineServerError::RelationshipSenderIsTarget => GrapevineCLIError::RelationshipSenderIsTarget,
             GrapevineServerError::NonceMismatch(expected, actual) => GrapevineCLIError::NonceMismatch(expected, actual),
             GrapevineServerError::SerdeError(msg) => GrapevineCLIError::SerdeError(msg),
@@ -66,4 +70,21 @@ impl From<GrapevineServerError> for GrapevineCLIError {
     }
 }
 
+impl From<GrapevineClientError> for GrapevineCLIError {
+    fn from(err: GrapevineClientError) -> GrapevineCLIError {
+        // errors without a cli equivalent keep the status and server error in their message
+        match err {
+            GrapevineClientError::Server(status, err) => match err.clone().into() {
+                GrapevineCLIError::UnknownServerError => {
+                    GrapevineCLIError::ServerError(format!("{} {}", status, err))
+                }
+                err => err,
+            },
+            GrapevineClientError::Message(status, msg) => {
+                GrapevineCLIError::ServerError(format!("{} {}", status, msg))
+            }
+        }
+    }
+}
+
 impl std::error::Error for GrapevineCLIError {}
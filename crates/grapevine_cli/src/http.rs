@@ -1,13 +1,18 @@
 use crate::errors::GrapevineCLIError;
 use crate::utils::fs::ACCOUNT_PATH;
 use babyjubjub_rs::{decompress_point, Point};
+use grapevine_common::crypto::verify_proving_data;
 use grapevine_common::http::requests::{
-    CreateUserRequest, DegreeProofRequest, GetNonceRequest, NewPhraseRequest,
-    NewRelationshipRequest,
+    CreateUserRequest, DegreeProofRequest, GetNonceRequest, NewPhraseBatchRequest,
+    NewPhraseRequest, NewRelationshipRequest,
 };
 use grapevine_common::http::responses::DegreeData;
 use grapevine_common::models::proof::ProvingData;
-use grapevine_common::{account::GrapevineAccount, errors::GrapevineServerError};
+use grapevine_common::{
+    account::GrapevineAccount,
+    errors::{GrapevineClientError, GrapevineServerError, NonceError},
+    nonce::Nonce,
+};
 use reqwest::{Client, StatusCode};
 use lazy_static::lazy_static;
 
@@ -24,7 +29,7 @@ lazy_static! {
  * @param username - the username of the user to get the public key of
  * @returns - the public key of the user
  */
-pub async fn get_pubkey_req(username: String) -> Result<Point, GrapevineServerError> {
+pub async fn get_pubkey_req(username: String) -> Result<Point, GrapevineClientError> {
     let url = format!("{}/user/{}/pubkey", &**SERVER_URL, username);
     let res = reqwest::get(&url).await.unwrap();
     match res.status() {
@@ -32,35 +37,57 @@ pub async fn get_pubkey_req(username: String) -> Result<Point, GrapevineServerEr
             let pubkey = res.text().await.unwrap();
             Ok(decompress_point(hex::decode(pubkey).unwrap().try_into().unwrap()).unwrap())
         }
-        StatusCode::NOT_FOUND => Err(GrapevineServerError::UserNotFound(username)),
-        _ => Err(res.json::<GrapevineServerError>().await.unwrap()),
+        StatusCode::NOT_FOUND => Err(GrapevineClientError::Server(
+            404,
+            GrapevineServerError::UserNotFound(username),
+        )),
+        _ => Err(GrapevineClientError::from_response(res).await),
+    }
+}
+
+/**
+ * Makes an HTTP Request to get a single-use challenge for creating a new user
+ *
+ * @returns - the challenge to sign when registering an account
+ */
+pub async fn get_challenge_req() -> Result<[u8; 32], GrapevineClientError> {
+    let url = format!("{}/user/challenge", &**SERVER_URL);
+    let res = reqwest::get(&url).await.unwrap();
+    match res.status() {
+        StatusCode::OK => {
+            let challenge = res.text().await.unwrap();
+            Ok(hex::decode(challenge).unwrap().try_into().unwrap())
+        }
+        _ => Err(GrapevineClientError::from_response(res).await),
     }
 }
 
-pub async fn get_nonce_req(body: GetNonceRequest) -> Result<u64, GrapevineServerError> {
+pub async fn get_nonce_req(body: GetNonceRequest) -> Result<Nonce, GrapevineClientError> {
     let url = format!("{}/user/nonce", &**SERVER_URL);
     let client = Client::new();
     let res = client.post(&url).json(&body).send().await.unwrap();
     match res.status() {
         StatusCode::OK => {
             let nonce = res.text().await.unwrap();
-            Ok(nonce.parse().unwrap())
+            nonce.parse().map_err(|e: NonceError| {
+                GrapevineClientError::Server(200, GrapevineServerError::SerdeError(e.to_string()))
+            })
         }
-        _ => Err(res.json::<GrapevineServerError>().await.unwrap()),
+        _ => Err(GrapevineClientError::from_response(res).await),
     }
 }
 
 pub async fn get_available_proofs_req(
     account: &mut GrapevineAccount,
-) -> Result<Vec<String>, GrapevineServerError> {
+) -> Result<Vec<String>, GrapevineClientError> {
     let url = format!("{}/proof/available", &**SERVER_URL);
     // produce signature over current nonce
-    let signature = hex::encode(account.sign_nonce().compress());
+    let (auth_header, signature) = account.auth_header();
     let client = Client::new();
     let res = client
         .get(&url)
         .header("X-Username", account.username())
-        .header("X-Authorization", signature)
+        .header(auth_header, signature)
         .send()
         .await
         .unwrap();
@@ -73,22 +100,45 @@ pub async fn get_available_proofs_req(
             let proofs = res.json::<Vec<String>>().await.unwrap();
             Ok(proofs)
         }
-        _ => Err(res.json::<GrapevineServerError>().await.unwrap()),
+        _ => Err(GrapevineClientError::from_response(res).await),
+    }
+}
+
+/**
+ * Makes an HTTP Request to get the public key the server signs proving data with
+ *
+ * @returns - the compressed public key of the server
+ */
+pub async fn get_server_pubkey_req() -> Result<[u8; 32], GrapevineClientError> {
+    let url = format!("{}/server/pubkey", &**SERVER_URL);
+    let res = reqwest::get(&url).await.unwrap();
+    match res.status() {
+        StatusCode::OK => {
+            let pubkey = res.text().await.unwrap();
+            match hex::decode(pubkey).ok().and_then(|pubkey| pubkey.try_into().ok()) {
+                Some(pubkey) => Ok(pubkey),
+                None => Err(GrapevineClientError::Server(
+                    200,
+                    GrapevineServerError::SerdeError(String::from("server pubkey")),
+                )),
+            }
+        }
+        _ => Err(GrapevineClientError::from_response(res).await),
     }
 }
 
 pub async fn get_proof_with_params_req(
     account: &mut GrapevineAccount,
     oid: String,
-) -> Result<ProvingData, GrapevineServerError> {
+) -> Result<ProvingData, GrapevineClientError> {
     let url = format!("{}/proof/params/{}", &**SERVER_URL, oid);
     // produce signature over current nonce
-    let signature = hex::encode(account.sign_nonce().compress());
+    let (auth_header, signature) = account.auth_header();
     let client = Client::new();
     let res = client
         .get(&url)
         .header("X-Username", account.username())
-        .header("X-Authorization", signature)
+        .header(auth_header, signature)
         .send()
         .await
         .unwrap();
@@ -98,10 +148,31 @@ pub async fn get_proof_with_params_req(
             account
                 .increment_nonce(Some((&**ACCOUNT_PATH).to_path_buf()))
                 .unwrap();
+            // check the server's signature over the proving data
+            let signature: Option<[u8; 64]> = res
+                .headers()
+                .get("X-Grapevine-Signature")
+                .and_then(|header| hex::decode(header.as_bytes()).ok())
+                .and_then(|signature| signature.try_into().ok());
             let proof = res.json::<ProvingData>().await.unwrap();
-            Ok(proof)
+            let server_pubkey = get_server_pubkey_req().await?;
+            match signature {
+                Some(signature) if verify_proving_data(&proof, &signature, &server_pubkey) => {
+                    Ok(proof)
+                }
+                _ => Err(GrapevineClientError::Server(
+                    200,
+                    GrapevineServerError::Signature(String::from(
+                        "Proving data is not signed by the server",
+                    )),
+                )),
+            }
         }
-        _ => Err(res.json::<GrapevineServerError>().await.unwrap()),
+        StatusCode::NOT_FOUND => Err(GrapevineClientError::Server(
+            404,
+            GrapevineServerError::ProofNotFound(oid),
+        )),
+        _ => Err(GrapevineClientError::from_response(res).await),
     }
 }
 
@@ -112,13 +183,14 @@ pub async fn get_proof_with_params_req(
  * @param body - the CreateUserRequest data to provide as the body of the http request
  * @returns - Ok if 201, or the error type otherwise
  */
-pub async fn create_user_req(body: CreateUserRequest) -> Result<(), GrapevineServerError> {
+pub async fn create_user_req(body: CreateUserRequest) -> Result<(), GrapevineClientError> {
     let url = format!("{}/user/create", &**SERVER_URL);
     let client = Client::new();
     let res = client.post(&url).json(&body).send().await.unwrap();
     match res.status() {
-        StatusCode::CREATED => return Ok(()),
-        _ => Err(res.json::<GrapevineServerError>().await.unwrap()),
+        // the server returns 200 if the identical user was already created (i.e. a retry)
+        StatusCode::CREATED | StatusCode::OK => return Ok(()),
+        _ => Err(GrapevineClientError::from_response(res).await),
     }
 }
 
@@ -131,16 +203,16 @@ pub async fn create_user_req(body: CreateUserRequest) -> Result<(), GrapevineSer
 pub async fn add_relationship_req(
     account: &mut GrapevineAccount,
     body: NewRelationshipRequest,
-) -> Result<(), GrapevineServerError> {
+) -> Result<(), GrapevineClientError> {
     let url = format!("{}/user/relationship", &**SERVER_URL);
     // produce signature over current nonce
-    let signature = hex::encode(account.sign_nonce().compress());
+    let (auth_header, signature) = account.auth_header();
     let client = Client::new();
     let res = client
         .post(&url)
         .json(&body)
         .header("X-Username", account.username())
-        .header("X-Authorization", signature)
+        .header(auth_header, signature)
         .send()
         .await
         .unwrap();
@@ -152,7 +224,7 @@ pub async fn add_relationship_req(
                 .unwrap();
             return Ok(());
         }
-        _ => Err(res.json::<GrapevineServerError>().await.unwrap()),
+        _ => Err(GrapevineClientError::from_response(res).await),
     }
 }
 
@@ -165,18 +237,48 @@ pub async fn add_relationship_req(
 pub async fn new_phrase_req(
     account: &mut GrapevineAccount,
     body: NewPhraseRequest,
-) -> Result<(), GrapevineServerError> {
+) -> Result<(), GrapevineClientError> {
     let url = format!("{}/proof/create", &**SERVER_URL);
     // serialize the proof
     let serialized: Vec<u8> = bincode::serialize(&body).unwrap();
     // produce signature over current nonce
-    let signature = hex::encode(account.sign_nonce().compress());
+    let (auth_header, signature) = account.auth_header();
+    let client = Client::new();
+    let res = client
+        .post(&url)
+        .body(serialized)
+        .header("X-Username", account.username())
+        .header(auth_header, signature)
+        .send()
+        .await
+        .unwrap();
+    match res.status() {
+        StatusCode::CREATED => {
+            // increment nonce
+            account
+                .increment_nonce(Some((&**ACCOUNT_PATH).to_path_buf()))
+                .unwrap();
+            return Ok(());
+        }
+        _ => Err(GrapevineClientError::from_response(res).await),
+    }
+}
+
+pub async fn new_phrase_batch_req(
+    account: &mut GrapevineAccount,
+    body: NewPhraseBatchRequest,
+) -> Result<(), GrapevineClientError> {
+    let url = format!("{}/proof/create/batch", &**SERVER_URL);
+    // serialize the proofs
+    let serialized: Vec<u8> = bincode::serialize(&body).unwrap();
+    // produce signature over current nonce
+    let (auth_header, signature) = account.auth_header();
     let client = Client::new();
     let res = client
         .post(&url)
         .body(serialized)
         .header("X-Username", account.username())
-        .header("X-Authorization", signature)
+        .header(auth_header, signature)
         .send()
         .await
         .unwrap();
@@ -188,26 +290,26 @@ pub async fn new_phrase_req(
                 .unwrap();
             return Ok(());
         }
-        _ => {
-            // let y = &res.;
-            println!("res: {:#?}", res.text().await.unwrap());
-            // Err(res.json::<GrapevineServerError>().await.unwrap())
-            Err(GrapevineServerError::InternalError)
-        },
+        // the batch size limit is reported as text rather than a serialized error
+        StatusCode::PAYLOAD_TOO_LARGE => Err(GrapevineClientError::Server(
+            413,
+            GrapevineServerError::BatchTooLarge(body.proofs.len()),
+        )),
+        _ => Err(GrapevineClientError::from_response(res).await),
     }
 }
 
 pub async fn get_degrees_req(
     account: &mut GrapevineAccount,
-) -> Result<Vec<DegreeData>, GrapevineServerError> {
+) -> Result<Vec<DegreeData>, GrapevineClientError> {
     let url = format!("{}/user/degrees", &**SERVER_URL);
     // produce signature over current nonce
-    let signature = hex::encode(account.sign_nonce().compress());
+    let (auth_header, signature) = account.auth_header();
     let client = Client::new();
     let res = client
         .get(&url)
         .header("X-Username", account.username())
-        .header("X-Authorization", signature)
+        .header(auth_header, signature)
         .send()
         .await
         .unwrap();
@@ -220,7 +322,7 @@ pub async fn get_degrees_req(
             let degrees = res.json::<Vec<DegreeData>>().await.unwrap();
             Ok(degrees)
         }
-        _ => Err(res.json::<GrapevineServerError>().await.unwrap()),
+        _ => Err(GrapevineClientError::from_response(res).await),
     }
 }
 
@@ -235,18 +337,18 @@ pub async fn get_degrees_req(
 pub async fn degree_proof_req(
     account: &mut GrapevineAccount,
     body: DegreeProofRequest,
-) -> Result<(), GrapevineServerError> {
+) -> Result<(), GrapevineClientError> {
     let url = format!("{}/proof/continue", &**SERVER_URL);
     // serialize the proof
     let serialized: Vec<u8> = bincode::serialize(&body).unwrap();
     // produce signature over current nonce
-    let signature = hex::encode(account.sign_nonce().compress());
+    let (auth_header, signature) = account.auth_header();
     let client = Client::new();
     let res = client
         .post(&url)
         .body(serialized)
         .header("X-Username", account.username())
-        .header("X-Authorization", signature)
+        .header(auth_header, signature)
         .send()
         .await
         .unwrap();
@@ -258,6 +360,6 @@ pub async fn degree_proof_req(
                 .unwrap();
             return Ok(());
         }
-        _ => Err(res.json::<GrapevineServerError>().await.unwrap()),
+        _ => Err(GrapevineClientError::from_response(res).await),
     }
 }
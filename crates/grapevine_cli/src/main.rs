@@ -47,6 +47,10 @@ enum Commands {
     /// usage: `grapevine create-phrase <phrase>`
     #[command(verbatim_doc_comment)]
     CreatePhrase(CreatePhrase),
+    /// Create several new phrases in one request
+    /// usage: `grapevine create-phrases <phrase> <phrase> ...`
+    #[command(verbatim_doc_comment)]
+    CreatePhrases(CreatePhrases),
     /// Prove all the the new degrees of separation available
     /// usage: `grapevine prove-new`
     #[command(verbatim_doc_comment)]
@@ -76,6 +80,11 @@ struct CreatePhrase {
     phrase: Option<String>,
 }
 
+#[derive(Args)]
+struct CreatePhrases {
+    phrases: Vec<String>,
+}
+
 #[derive(Args)]
 struct ProveSeparationArgs {
     username: Option<String>,
@@ -99,6 +108,7 @@ pub async fn main() {
         Commands::CreatePhrase(cmd) => {
             controllers::create_new_phrase(cmd.phrase.clone().unwrap()).await
         }
+        Commands::CreatePhrases(cmd) => controllers::create_new_phrases(cmd.phrases.clone()).await,
         Commands::ProveNew => controllers::prove_all_available().await,
         Commands::GetDegrees => controllers::get_my_proofs().await,
 
@@ -1,9 +1,12 @@
 use crate::auth_secret::{AuthSecret, AuthSecretEncrypted, AuthSecretEncryptedUser};
-use crate::crypto::{new_private_key, nonce_hash};
+use crate::crypto::{creation_hash, message_hash, new_private_key, nonce_hash, username_bytes};
 use crate::http::requests::{
     CreateUserRequest, GetNonceRequest, NewPhraseRequest, NewRelationshipRequest,
+    UpdateRelationshipRequest,
 };
-use crate::utils::{convert_username_to_fr, random_fr};
+use crate::http::{AUTHORIZATION_HEADER, USERNAME_HEADER};
+use crate::nonce::Nonce;
+use crate::utils::random_fr;
 use crate::{Fr, Params};
 use babyjubjub_rs::{Point, PrivateKey, Signature};
 use num_bigint::{BigInt, Sign};
@@ -15,7 +18,7 @@ pub struct GrapevineAccount {
     username: String,
     auth_secret: Fr,
     private_key: [u8; 32],
-    nonce: u64,
+    nonce: Nonce,
 }
 
 impl GrapevineAccount {
@@ -32,7 +35,7 @@ impl GrapevineAccount {
             username,
             auth_secret,
             private_key,
-            nonce: 0,
+            nonce: Nonce::default(),
         }
     }
 
@@ -57,9 +60,13 @@ impl GrapevineAccount {
      * Increment nonce by 1 for normal actions
      *
      * @param save - if some, path to save the account to after incrementing nonce
+     * @returns - an error if saving fails or the nonce is already at u64::MAX
      */
     pub fn increment_nonce(&mut self, save: Option<PathBuf>) -> Result<(), std::io::Error> {
-        self.nonce += 1;
+        self.nonce = self
+            .nonce
+            .increment()
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
         if save.is_some() {
             return self.save(save.unwrap());
         }
@@ -72,7 +79,7 @@ impl GrapevineAccount {
      * @param nonce - the new nonce to set for the account\
      * @param save - if some, path to save the account to after incrementing nonce
      */
-    pub fn set_nonce(&mut self, nonce: u64, save: Option<PathBuf>) -> Result<(), std::io::Error> {
+    pub fn set_nonce(&mut self, nonce: Nonce, save: Option<PathBuf>) -> Result<(), std::io::Error> {
         self.nonce = nonce;
         if save.is_some() {
             return self.save(save.unwrap());
@@ -96,9 +103,12 @@ impl GrapevineAccount {
      * Decrypt an encrypted auth secret that should be encrypted with this account's public key
      *
      * @param message - the encrypted auth secret
-     * @returns - the decrypted auth secret
+     * @returns - the decrypted auth secret, or an error if the ciphertext fails authentication
      */
-    pub fn decrypt_auth_secret(&self, message: AuthSecretEncrypted) -> AuthSecret {
+    pub fn decrypt_auth_secret(
+        &self,
+        message: AuthSecretEncrypted,
+    ) -> Result<AuthSecret, aes_gcm::Error> {
         message.decrypt(self.private_key())
     }
 
@@ -106,41 +116,96 @@ impl GrapevineAccount {
 
     /**
      * Produce a signature over the username of this account
+     * @notice panics if the username cannot be encoded as a field element (see username_bytes)
      *
      * @returns - the signature over the username
      */
     pub fn sign_username(&self) -> Signature {
-        let message = BigInt::from_bytes_le(
-            Sign::Plus,
-            &convert_username_to_fr(&self.username).unwrap()[..],
-        );
+        let username =
+            username_bytes(&self.username).expect("Username must fit in a field element");
+        let message = BigInt::from_bytes_le(Sign::Plus, &username[..]);
+        self.private_key().sign(message).unwrap()
+    }
+
+    /**
+     * Produce a signature over the sha3 hash H|username, challenge| of this account
+     *
+     * @notice panics if the username cannot be encoded as a field element
+     *
+     * @param challenge - the user creation challenge issued by the server
+     * @returns - the signature authorizing the creation of this account
+     */
+    pub fn sign_creation(&self, challenge: &[u8; 32]) -> Signature {
+        let hash =
+            creation_hash(&self.username, challenge).expect("Username must fit in a field element");
+        let message = BigInt::from_bytes_le(Sign::Plus, &hash[..]);
         self.private_key().sign(message).unwrap()
     }
 
     /**
      * Produce a signature over the sha256 hash H|username, nonce| of this account
+     * @notice panics if the username cannot be encoded as a field element
      *
      * @returns - the signature authorizing arbitrary gated http actions
      */
     pub fn sign_nonce(&self) -> Signature {
-        let message =
-            BigInt::from_bytes_le(Sign::Plus, &nonce_hash(&self.username, self.nonce)[..]);
+        let hash =
+            nonce_hash(&self.username, self.nonce).expect("Username must fit in a field element");
+        let message = BigInt::from_bytes_le(Sign::Plus, &hash[..]);
+        self.private_key().sign(message).unwrap()
+    }
+
+    /**
+     * Produce a signature over the sha3 hash H|message| for verification by a third party
+     *
+     * @param message - the message to sign, such as a challenge issued by another service
+     * @returns - the signature that /verify/signature checks against this account's pubkey
+     */
+    pub fn sign_message(&self, message: &[u8]) -> Signature {
+        let message = BigInt::from_bytes_le(Sign::Plus, &message_hash(message)[..]);
         self.private_key().sign(message).unwrap()
     }
 
+    /// HTTP HEADER CONSTRUCTORS ///
+
+    /**
+     * Produce the header authenticating a request as this account at its current nonce
+     * @notice the nonce must be incremented after each authenticated request
+     *
+     * @returns - the (name, value) of the X-Authorization header carrying the signed nonce
+     */
+    pub fn auth_header(&self) -> (String, String) {
+        (
+            String::from(AUTHORIZATION_HEADER),
+            hex::encode(self.sign_nonce().compress()),
+        )
+    }
+
+    /**
+     * Produce the header naming this account on an authenticated request
+     *
+     * @returns - the (name, value) of the X-Username header sent alongside auth_header
+     */
+    pub fn username_header(&self) -> (String, String) {
+        (String::from(USERNAME_HEADER), self.username.clone())
+    }
+
     /// HTTP REQUEST BODY CONSTRUCTORS ///
 
     /**
      * Create the http request body for creating a new user in the Grapevine service
      *
+     * @param challenge - the user creation challenge issued by the server
      * @returns - the CreateUserRequest authorizing a new user to be added to Grapevine service
      */
-    pub fn create_user_request(&self) -> CreateUserRequest {
+    pub fn create_user_request(&self, challenge: [u8; 32]) -> CreateUserRequest {
         // return the Create User http request struct
         CreateUserRequest {
             username: self.username.clone(),
             pubkey: self.pubkey().compress(),
-            signature: self.sign_username().compress(),
+            challenge,
+            signature: self.sign_creation(&challenge).compress(),
+            invite: None,
         }
     }
 
@@ -163,6 +228,23 @@ impl GrapevineAccount {
             to: username.clone(),
             ephemeral_key: encrypted_auth_secret.ephemeral_key,
             ciphertext: encrypted_auth_secret.ciphertext,
+            nonce: encrypted_auth_secret.nonce,
+        }
+    }
+
+    /**
+     * Create the http request body for replacing the auth secret shared in an existing relationship
+     *
+     * @param pubkey - the public key of the relationship recipient
+     * @returns - the UpdateRelationshipRequest containing the newly encrypted auth secret
+     */
+    pub fn update_relationship_request(&self, pubkey: &Point) -> UpdateRelationshipRequest {
+        // encrypt the auth secret with the target pubkey under a fresh ephemeral key
+        let encrypted_auth_secret = self.encrypt_auth_secret(pubkey.clone());
+        UpdateRelationshipRequest {
+            ephemeral_key: encrypted_auth_secret.ephemeral_key,
+            ciphertext: encrypted_auth_secret.ciphertext,
+            nonce: encrypted_auth_secret.nonce,
         }
     }
 
@@ -223,7 +305,7 @@ impl GrapevineAccount {
     }
 
     /** Return the current nonce for this account */
-    pub fn nonce(&self) -> u64 {
+    pub fn nonce(&self) -> Nonce {
         self.nonce
     }
 
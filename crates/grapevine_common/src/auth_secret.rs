@@ -1,9 +1,8 @@
-use crate::crypto::gen_aes_key;
+use crate::crypto::{decrypt, encrypt, gen_aes_key};
 use crate::Fr;
-use aes::cipher::{block_padding::Pkcs7, BlockDecryptMut, BlockEncryptMut, KeyIvInit};
+use aes::cipher::{block_padding::Pkcs7, BlockDecryptMut, KeyIvInit};
 use babyjubjub_rs::{Point, PrivateKey};
 use serde::{Deserialize, Serialize};
-type Aes128CbcEnc = cbc::Encryptor<aes::Aes128>;
 type Aes128CbcDec = cbc::Decryptor<aes::Aes128>;
 
 /**
@@ -16,6 +15,9 @@ pub struct AuthSecretEncrypted {
     pub ephemeral_key: [u8; 32],
     #[serde(with = "serde_bytes")]
     pub ciphertext: [u8; 48],
+    // aes-gcm nonce; None for auth secrets encrypted with legacy aes-cbc
+    #[serde(default)]
+    pub nonce: Option<[u8; 12]>,
 }
 
 /**
@@ -40,11 +42,12 @@ pub trait AuthSecretEncryptedUser {
 
     /**
      * Decrypts an encrypted AuthSecret
+     * @notice falls back to legacy aes-cbc-128 if no aes-gcm nonce is present
      *
      * @param recipient - the private key of the recipient of the auth secret
-     * @returns - the decrypted auth secret
+     * @returns - the decrypted auth secret, or an error if the ciphertext fails authentication
      */
-    fn decrypt(&self, recipient: PrivateKey) -> AuthSecret;
+    fn decrypt(&self, recipient: PrivateKey) -> Result<AuthSecret, aes_gcm::Error>;
 }
 
 impl AuthSecretEncryptedUser for AuthSecretEncrypted {
@@ -52,43 +55,43 @@ impl AuthSecretEncryptedUser for AuthSecretEncrypted {
         // generate a new ephemeral keypair
         let ephm_sk = babyjubjub_rs::new_key();
         let ephm_pk = ephm_sk.public().compress();
-        // compute the aes-cbc-128 key
-        let (aes_key, aes_iv) = gen_aes_key(ephm_sk, recipient.clone());
-        // encrypt the auth secret
-        let plaintext = auth_secret.to_bytes();
-        let mut buf = [0u8; 48];
-        buf[..plaintext.len()].copy_from_slice(&plaintext);
-        let ciphertext: [u8; 48] = Aes128CbcEnc::new(aes_key[..].into(), aes_iv[..].into())
-            .encrypt_padded_mut::<Pkcs7>(&mut buf, plaintext.len())
-            .unwrap()
-            .try_into()
-            .unwrap();
+        // compute the aes-gcm-128 key
+        let (aes_key, _) = gen_aes_key(ephm_sk, recipient.clone());
+        // encrypt the auth secret under a fresh nonce
+        let plaintext: [u8; 32] = auth_secret.to_bytes();
+        let (nonce, ciphertext) = encrypt(&aes_key, &plaintext);
         // return the encrypted auth secret
         Self {
             username,
             recipient: recipient.compress(),
             ephemeral_key: ephm_pk,
-            ciphertext: ciphertext,
+            ciphertext,
+            nonce: Some(nonce),
         }
     }
 
-    fn decrypt(&self, recipient: PrivateKey) -> AuthSecret {
-        // compute the aes-cbc-128 key
+    fn decrypt(&self, recipient: PrivateKey) -> Result<AuthSecret, aes_gcm::Error> {
+        // compute the aes-128 key
         let ephm_pk = babyjubjub_rs::decompress_point(self.ephemeral_key).unwrap();
         let (aes_key, aes_iv) = gen_aes_key(recipient, ephm_pk);
         // decrypt the auth secret
-        let mut buf = self.ciphertext;
-        let ptr: [u8; 32] = Aes128CbcDec::new(aes_key[..].into(), aes_iv[..].into())
-            .decrypt_padded_mut::<Pkcs7>(&mut buf)
-            .unwrap()
-            .try_into()
-            .unwrap();
+        let ptr: [u8; 32] = match self.nonce {
+            Some(nonce) => decrypt(&aes_key, &nonce, &self.ciphertext)?,
+            None => {
+                let mut buf = self.ciphertext;
+                Aes128CbcDec::new(aes_key[..].into(), aes_iv[..].into())
+                    .decrypt_padded_mut::<Pkcs7>(&mut buf)
+                    .map_err(|_| aes_gcm::Error)?
+                    .try_into()
+                    .map_err(|_| aes_gcm::Error)?
+            }
+        };
         // convert the auth secret into an Fr
         let auth_secret = Fr::from_bytes(&ptr).unwrap();
-        AuthSecret {
+        Ok(AuthSecret {
             username: self.username.clone(),
             auth_secret,
-        }
+        })
     }
 }
 
@@ -106,7 +109,7 @@ mod test {
         // create encrypted auth secret
         let encrypted_auth_secret = AuthSecretEncrypted::new(username, auth_secret, recipient_pk);
         // decrypt the auth secret
-        let decrypted_auth_secret = encrypted_auth_secret.decrypt(recipient_sk);
+        let decrypted_auth_secret = encrypted_auth_secret.decrypt(recipient_sk).unwrap();
         // check that the auth secret is the same
         assert!(decrypted_auth_secret.auth_secret.eq(&auth_secret));
         println!("auth_secret_1 {:?}", auth_secret);
@@ -126,8 +129,49 @@ mod test {
         let json = serde_json::to_string(&encrypted_auth_secret).unwrap();
         // deserialize from json
         let deserialized = serde_json::from_str::<AuthSecretEncrypted>(&json).unwrap();
-        let decrypted_auth_secret = deserialized.decrypt(recipient_sk);
+        let decrypted_auth_secret = deserialized.decrypt(recipient_sk).unwrap();
         // check that the auth secret is the same
         assert!(decrypted_auth_secret.auth_secret.eq(&auth_secret));
     }
+
+    #[test]
+    fn tampered_ciphertext_test() {
+        // setup
+        let auth_secret = random_fr();
+        let username = String::from("JP4G");
+        let recipient_sk = babyjubjub_rs::new_key();
+        let recipient_pk = recipient_sk.public();
+        // create encrypted auth secret
+        let mut encrypted_auth_secret =
+            AuthSecretEncrypted::new(username, auth_secret, recipient_pk);
+        // flip a bit in the ciphertext
+        encrypted_auth_secret.ciphertext[0] ^= 1;
+        // check that the gcm tag check rejects the ciphertext
+        assert!(encrypted_auth_secret.decrypt(recipient_sk).is_err());
+    }
+
+    #[test]
+    fn unique_nonce_test() {
+        // setup
+        let auth_secret = random_fr();
+        let recipient_pk = babyjubjub_rs::new_key().public();
+        // encrypt the same auth secret to the same recipient twice
+        let ephm_sk = babyjubjub_rs::new_key();
+        let (aes_key, _) = gen_aes_key(ephm_sk, recipient_pk);
+        let plaintext: [u8; 32] = auth_secret.to_bytes();
+        let (nonce_1, ciphertext_1) = encrypt(&aes_key, &plaintext);
+        let (nonce_2, ciphertext_2) = encrypt(&aes_key, &plaintext);
+        // check that the nonces and ciphertexts differ
+        assert_ne!(nonce_1, nonce_2);
+        assert_ne!(ciphertext_1, ciphertext_2);
+        // check that both still decrypt to the same auth secret
+        assert_eq!(
+            decrypt(&aes_key, &nonce_1, &ciphertext_1).unwrap(),
+            plaintext
+        );
+        assert_eq!(
+            decrypt(&aes_key, &nonce_2, &ciphertext_2).unwrap(),
+            plaintext
+        );
+    }
 }
@@ -1,15 +1,23 @@
-use crate::{compat::ff_ce_to_le_bytes, utils::convert_username_to_fr};
-use babyjubjub_rs::{Point, PrivateKey};
-use num_bigint::{RandBigInt, ToBigInt};
+use crate::{
+    compat::ff_ce_to_le_bytes, errors::GrapevineServerError, http::responses::SignedClaim,
+    models::proof::ProvingData, nonce::Nonce, utils::convert_username_to_fr,
+};
+use aes_gcm::{
+    aead::{AeadInPlace, KeyInit},
+    Aes128Gcm, Nonce, Tag,
+};
+use babyjubjub_rs::{decompress_point, decompress_signature, verify, Point, PrivateKey};
+use num_bigint::{BigInt, RandBigInt, Sign, ToBigInt};
+use rand::RngCore;
 use sha256::digest;
 use sha3::{Digest, Sha3_256};
 
 /**
- * Computes an AES-CBC-128 Key from a Baby Jub Jub shared secret
+ * Computes an AES-128 Key from a Baby Jub Jub shared secret
  *
  * @param sk - the private key in the ecdh shared secret
  * @param pk - the public key in the ecdh shared secret (ephemeral in practice)
- * @return - a tuple (AES Key, AES Iv) used to encrypt/ decrypt aes-cbc-128
+ * @return - a tuple (AES Key, AES Iv) used to encrypt/ decrypt aes-128 (iv only used by legacy cbc)
  */
 pub fn gen_aes_key(sk: PrivateKey, pk: Point) -> ([u8; 16], [u8; 16]) {
     // compute ecdh shared secret
@@ -29,6 +37,55 @@ pub fn gen_aes_key(sk: PrivateKey, pk: Point) -> ([u8; 16], [u8; 16]) {
     (aes_key, aes_iv)
 }
 
+/**
+ * Encrypts a 32 byte secret with AES-GCM-128 under a freshly sampled random nonce
+ * @notice the nonce is always generated here so callers cannot reuse one under the same key
+ *
+ * @param key - the aes key derived from the ecdh shared secret
+ * @param plaintext - the 32 byte secret to encrypt
+ * @return - a tuple (nonce, ciphertext || tag) to be stored alongside each other
+ */
+pub fn encrypt(key: &[u8; 16], plaintext: &[u8; 32]) -> ([u8; 12], [u8; 48]) {
+    // sample a unique nonce for this encryption
+    let mut nonce = [0u8; 12];
+    rand::thread_rng().fill_bytes(&mut nonce);
+    // encrypt the plaintext in place and compute the authentication tag
+    let cipher = Aes128Gcm::new(key[..].into());
+    let mut buffer = *plaintext;
+    let tag = cipher
+        .encrypt_in_place_detached(Nonce::from_slice(&nonce), b"", &mut buffer)
+        .unwrap();
+    // append the tag to the ciphertext
+    let mut ciphertext = [0u8; 48];
+    ciphertext[..32].copy_from_slice(&buffer);
+    ciphertext[32..].copy_from_slice(&tag);
+    (nonce, ciphertext)
+}
+
+/**
+ * Decrypts and authenticates a 32 byte secret encrypted with AES-GCM-128
+ *
+ * @param key - the aes key derived from the ecdh shared secret
+ * @param nonce - the nonce the secret was encrypted under
+ * @param ciphertext - the ciphertext with the 16 byte authentication tag appended
+ * @return - the decrypted secret, or an error if the authentication tag does not match
+ */
+pub fn decrypt(
+    key: &[u8; 16],
+    nonce: &[u8; 12],
+    ciphertext: &[u8; 48],
+) -> Result<[u8; 32], aes_gcm::Error> {
+    let cipher = Aes128Gcm::new(key[..].into());
+    let mut buffer: [u8; 32] = ciphertext[..32].try_into().unwrap();
+    cipher.decrypt_in_place_detached(
+        Nonce::from_slice(nonce),
+        b"",
+        &mut buffer,
+        Tag::from_slice(&ciphertext[32..]),
+    )?;
+    Ok(buffer)
+}
+
 /**
  * Generates a new private key as a 32 byte array
  *
@@ -46,12 +103,13 @@ pub fn new_private_key() -> [u8; 32] {
  *
  * @param username - the username to hash
  * @param nonce - the nonce to hash
- * @return - the sha256 hash of the username and nonce
+ * @return - the sha256 hash of the username and nonce, or UsernameEncodingError if the username
+ *           cannot be encoded as a field element
  */
-pub fn nonce_hash(username: &String, nonce: u64) -> [u8; 32] {
+pub fn nonce_hash(username: &String, nonce: Nonce) -> Result<[u8; 32], GrapevineServerError> {
     let mut hasher = Sha3_256::new();
     // add username to hash buffer
-    let username_bytes = convert_username_to_fr(username).unwrap();
+    let username_bytes = username_bytes(username)?;
     hasher.update(username_bytes);
     // add nonce to hash buffer
     let nonce_bytes = nonce.to_le_bytes();
@@ -61,5 +119,189 @@ pub fn nonce_hash(username: &String, nonce: u64) -> [u8; 32] {
     // 0 the last byte to ensure it always falls within the prime field Fr
     hash[31] = 0;
 
+    Ok(hash)
+}
+
+/**
+ * Generates a random challenge a server issues to be signed during user creation
+ *
+ * @returns - the new 32 byte challenge
+ */
+pub fn new_challenge() -> [u8; 32] {
+    let mut challenge = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut challenge);
+    challenge
+}
+
+/**
+ * Computes the sha3 hash H |username, challenge| with last byte zeroed
+ *
+ * @param username - the username being registered
+ * @param challenge - the server issued challenge for this registration
+ * @return - the sha3 hash of the username and challenge, or UsernameEncodingError if the
+ *           username cannot be encoded as a field element
+ */
+pub fn creation_hash(
+    username: &String,
+    challenge: &[u8; 32],
+) -> Result<[u8; 32], GrapevineServerError> {
+    let mut hasher = Sha3_256::new();
+    // add username to hash buffer
+    let username_bytes = username_bytes(username)?;
+    hasher.update(username_bytes);
+    // add challenge to hash buffer
+    hasher.update(challenge);
+    // compute sha3 hash
+    let mut hash: [u8; 32] = hasher.finalize().into();
+    // 0 the last byte to ensure it always falls within the prime field Fr
+    hash[31] = 0;
+
+    Ok(hash)
+}
+
+/**
+ * Encodes a username as a field element for hashing or signing
+ *
+ * @param username - the username to encode
+ * @return - the field element bytes, or UsernameEncodingError if the username does not fit
+ */
+pub fn username_bytes(username: &String) -> Result<[u8; 32], GrapevineServerError> {
+    convert_username_to_fr(username)
+        .map_err(|_| GrapevineServerError::UsernameEncodingError(username.clone()))
+}
+
+/**
+ * Computes the sha3 hash H |message| with last byte zeroed
+ * @dev lets users sign arbitrary messages, such as challenges issued by third party services
+ *
+ * @param message - the message to hash
+ * @return - the sha3 hash of the message
+ */
+pub fn message_hash(message: &[u8]) -> [u8; 32] {
+    let mut hash: [u8; 32] = Sha3_256::digest(message).into();
+    // 0 the last byte to ensure it always falls within the prime field Fr
+    hash[31] = 0;
+
+    hash
+}
+
+/**
+ * Computes the sha3 hash H |degree, proof, username, ephemeral_key, ciphertext, nonce| with last
+ * byte zeroed
+ * @dev variable length fields are length prefixed so field boundaries cannot be shifted
+ *
+ * @param data - the proving data returned by the server
+ * @return - the sha3 hash of the proving data that the server signs
+ */
+pub fn proving_data_hash(data: &ProvingData) -> [u8; 32] {
+    let mut hasher = Sha3_256::new();
+    // add degree to hash buffer
+    hasher.update([data.degree]);
+    // add length prefixed proof and username to hash buffer
+    hasher.update((data.proof.len() as u64).to_le_bytes());
+    hasher.update(&data.proof);
+    hasher.update((data.username.len() as u64).to_le_bytes());
+    hasher.update(data.username.as_bytes());
+    // add encrypted auth secret to hash buffer
+    hasher.update(data.ephemeral_key);
+    hasher.update(data.ciphertext);
+    match data.nonce {
+        Some(nonce) => {
+            hasher.update([1u8]);
+            hasher.update(nonce);
+        }
+        None => hasher.update([0u8]),
+    }
+    // compute sha3 hash
+    let mut hash: [u8; 32] = hasher.finalize().into();
+    // 0 the last byte to ensure it always falls within the prime field Fr
+    hash[31] = 0;
+
+    hash
+}
+
+/**
+ * Checks the server's signature over proving data returned by /proof/params/<oid>
+ *
+ * @param data - the proving data returned by the server
+ * @param signature - the compressed signature from the X-Grapevine-Signature header
+ * @param pubkey - the compressed pubkey published by the server at /server/pubkey
+ * @return - true if the signature verifies, false if it does not or either input is malformed
+ */
+pub fn verify_proving_data(data: &ProvingData, signature: &[u8; 64], pubkey: &[u8; 32]) -> bool {
+    verify_hash(&proving_data_hash(data), signature, pubkey)
+}
+
+/**
+ * Computes the sha3 hash H |username, phrase_hash, max_degree, within, issued_at| with last byte
+ * zeroed
+ * @dev the username is length prefixed so it cannot run into the phrase hash
+ *
+ * @param username - the user the claim is about
+ * @param phrase_hash - the poseidon hash of the phrase
+ * @param max_degree - the highest degree of separation the claim is about
+ * @param within - whether the user holds a proof at or below max_degree
+ * @param issued_at - when the claim was answered in milliseconds since the unix epoch
+ * @return - the sha3 hash of the claim that the server signs
+ */
+pub fn within_claim_hash(
+    username: &str,
+    phrase_hash: &[u8; 32],
+    max_degree: u8,
+    within: bool,
+    issued_at: i64,
+) -> [u8; 32] {
+    let mut hasher = Sha3_256::new();
+    // add length prefixed username to hash buffer
+    hasher.update((username.len() as u64).to_le_bytes());
+    hasher.update(username.as_bytes());
+    // add the claim and its answer to hash buffer
+    hasher.update(phrase_hash);
+    hasher.update([max_degree, within as u8]);
+    hasher.update(issued_at.to_le_bytes());
+    // compute sha3 hash
+    let mut hash: [u8; 32] = hasher.finalize().into();
+    // 0 the last byte to ensure it always falls within the prime field Fr
+    hash[31] = 0;
+
     hash
 }
+
+/**
+ * Checks the server's signature over an answer to whether a user is within a degree of a phrase
+ *
+ * @param claim - the signed claim returned by /user/<username>/within/<phrase_hash>/<n>
+ * @param pubkey - the compressed pubkey published by the server at /server/pubkey
+ * @return - true if the signature verifies, false if it does not or either input is malformed
+ */
+pub fn verify_signed_claim(claim: &SignedClaim, pubkey: &[u8; 32]) -> bool {
+    let hash = within_claim_hash(
+        &claim.username,
+        &claim.phrase_hash,
+        claim.max_degree,
+        claim.within,
+        claim.issued_at,
+    );
+    verify_hash(&hash, &claim.signature, pubkey)
+}
+
+/**
+ * Checks a compressed signature over a field-sized hash against a compressed pubkey
+ *
+ * @param hash - the signed hash (last byte zeroed)
+ * @param signature - the compressed signature
+ * @param pubkey - the compressed pubkey of the signer
+ * @return - true if the signature verifies, false if it does not or either input is malformed
+ */
+fn verify_hash(hash: &[u8; 32], signature: &[u8; 64], pubkey: &[u8; 32]) -> bool {
+    let pubkey = match decompress_point(*pubkey) {
+        Ok(pubkey) => pubkey,
+        Err(_) => return false,
+    };
+    let signature = match decompress_signature(signature) {
+        Ok(signature) => signature,
+        Err(_) => return false,
+    };
+    let message = BigInt::from_bytes_le(Sign::Plus, hash);
+    verify(pubkey, signature, message)
+}
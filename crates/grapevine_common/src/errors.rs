@@ -1,21 +1,66 @@
+use crate::MAX_USERNAME_CHARS;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
 pub enum GrapevineServerError {
     Signature(String),
     UsernameExists(String),
     UserNotFound(String),
     UsernameTooLong(String),
     UsernameNotAscii(String),
+    UsernameEncodingError(String),
     PubkeyExists(String),
     UserExists(String),
     RelationshipSenderIsTarget,
+    RelationshipExists,
+    RelationshipNotFound(String, String),
+    RelationshipNotSender(String),
+    RelationshipNotParticipant,
     NonceMismatch(u64, u64),
     MongoError(String),
     HeaderError(String),
     InternalError,
     SerdeError(String),
-    DegreeProofVerificationFailed
+    DegreeProofVerificationFailed,
+    ProofDecompressionFailed(String),
+    EmptyProof(usize),
+    PhraseAlreadyOriginated(String),
+    InvalidChallenge,
+    BatchTooLarge(usize),
+    UploadNotFound(String),
+    UploadIncomplete(u32),
+    UploadTooLarge(usize),
+    RateLimited(u64),
+    VerificationTimeout(u64),
+    VerificationBusy(u64),
+    RegistrationClosed,
+    AdminUnauthorized,
+    ProofNotFound(String),
+    ProofNotEntitled(String),
+    RelationshipLimitReached(usize),
+    NoAvailableProof(String),
+    ParamsVersionMismatch(String),
+    InvalidDegree(u8),
+    DegreeMismatch(u8),
+    UserGone(String),
+    ChainInvalid(String),
+    SessionInvalid,
+    SessionNotFound(String),
+    SessionNotPermitted,
+    PhraseNotFound(String),
+    RouteNotFound(String),
+    MethodNotAllowed(String),
+    PhraseFrozen(String),
+    ClaimNotPermitted(String),
+    StaleProof(String),
+    ProfileNotPermitted(String),
+    RelationshipConfirmed(String),
+    AvailableNotPermitted(String),
+    PhraseAlreadyKnown(String),
+    ProofReplayed(String),
+    UploadLimitReached(usize),
+    UploadChunkLimit(usize),
 }
 
 impl std::fmt::Display for GrapevineServerError {
@@ -28,10 +73,19 @@ impl std::fmt::Display for GrapevineServerError {
             GrapevineServerError::UserNotFound(msg) => {
                 write!(f, "Username {} does not exist", msg)
             }
-            GrapevineServerError::UsernameTooLong(msg) => write!(f, "Username {} is too long", msg),
+            GrapevineServerError::UsernameTooLong(msg) => write!(
+                f,
+                "Username {} is {} characters, exceeding the limit of {}",
+                msg,
+                msg.chars().count(),
+                MAX_USERNAME_CHARS
+            ),
             GrapevineServerError::UsernameNotAscii(msg) => {
                 write!(f, "Username {} is not ascii", msg)
             }
+            GrapevineServerError::UsernameEncodingError(msg) => {
+                write!(f, "Username {} cannot be encoded as a field element", msg)
+            }
             GrapevineServerError::PubkeyExists(msg) => {
                 write!(f, "Pubkey {} already used by another account", msg)
             }
@@ -41,6 +95,15 @@ impl std::fmt::Display for GrapevineServerError {
             GrapevineServerError::RelationshipSenderIsTarget => {
                 write!(f, "Relationship sender and target are the same")
             },
+            GrapevineServerError::RelationshipExists => {
+                write!(f, "Relationship between sender and target already exists")
+            }
+            GrapevineServerError::RelationshipNotFound(sender, recipient) => {
+                write!(f, "No relationship from {} to {} exists", sender, recipient)
+            }
+            GrapevineServerError::RelationshipNotSender(msg) => {
+                write!(f, "Only the sender can modify the relationship with {}", msg)
+            }
             &GrapevineServerError::NonceMismatch(expected, actual) => write!(
                 f,
                 "Nonce mismatch: expected {}, got {}. Retry this call",
@@ -51,8 +114,272 @@ impl std::fmt::Display for GrapevineServerError {
             GrapevineServerError::InternalError => write!(f, "Unknown internal server error"),
             GrapevineServerError::SerdeError(msg) => write!(f, "Error deserializing {}", msg),
             GrapevineServerError::DegreeProofVerificationFailed => write!(f, "Failed to verify degree proof"),
+            GrapevineServerError::ProofDecompressionFailed(msg) => {
+                write!(f, "Failed to decompress proof: {}", msg)
+            }
+            GrapevineServerError::EmptyProof(size) => {
+                write!(f, "Proof of {} bytes is too short to be a compressed proof", size)
+            }
+            GrapevineServerError::PhraseAlreadyOriginated(msg) => {
+                write!(f, "Phrase with hash {} has already been created", msg)
+            }
+            GrapevineServerError::InvalidChallenge => {
+                write!(f, "User creation challenge is invalid or has expired")
+            }
+            GrapevineServerError::BatchTooLarge(size) => {
+                write!(f, "Batch of {} items exceeds the maximum batch size", size)
+            }
+            GrapevineServerError::UploadNotFound(id) => {
+                write!(f, "No upload in progress with id {}", id)
+            }
+            GrapevineServerError::UploadIncomplete(index) => {
+                write!(f, "Upload is missing chunk {}", index)
+            }
+            GrapevineServerError::UploadTooLarge(size) => {
+                write!(f, "Upload of {} bytes exceeds the maximum proof size", size)
+            }
+            GrapevineServerError::RateLimited(seconds) => {
+                write!(f, "Too many requests, retry in {} seconds", seconds)
+            }
+            GrapevineServerError::VerificationTimeout(millis) => {
+                write!(f, "Proof verification did not finish within {} ms", millis)
+            }
+            GrapevineServerError::VerificationBusy(millis) => {
+                write!(f, "No proof verifier was free within {} ms, retry later", millis)
+            }
+            GrapevineServerError::RelationshipNotParticipant => {
+                write!(f, "Only a participant can query a relationship")
+            }
+            GrapevineServerError::RegistrationClosed => {
+                write!(f, "Registration is closed, a valid invite is required")
+            }
+            GrapevineServerError::AdminUnauthorized => {
+                write!(f, "Missing or invalid admin key")
+            }
+            GrapevineServerError::ProofNotFound(oid) => {
+                write!(f, "No proof found with oid {}", oid)
+            }
+            GrapevineServerError::ProofNotEntitled(oid) => {
+                write!(f, "No relationship with the creator of proof {}", oid)
+            }
+            GrapevineServerError::RelationshipLimitReached(max) => {
+                write!(f, "Users cannot have more than {} relationships", max)
+            }
+            GrapevineServerError::NoAvailableProof(phrase_hash) => {
+                write!(f, "No available proof to build on for phrase hash {}", phrase_hash)
+            }
+            GrapevineServerError::ParamsVersionMismatch(oid) => write!(
+                f,
+                "Proof {} was made with outdated public params and must be re-proven with the current artifacts",
+                oid
+            ),
+            GrapevineServerError::InvalidDegree(degree) => {
+                write!(f, "Degree {} is not a valid degree of separation", degree)
+            }
+            GrapevineServerError::DegreeMismatch(degree) => {
+                write!(f, "Proof does not prove the claimed degree {}", degree)
+            }
+            GrapevineServerError::UserGone(username) => {
+                write!(f, "User {} has been deleted or deactivated", username)
+            }
+            GrapevineServerError::ChainInvalid(msg) => write!(f, "Invalid proof chain: {}", msg),
+            GrapevineServerError::SessionInvalid => {
+                write!(f, "Session token is invalid, expired, or revoked")
+            }
+            GrapevineServerError::SessionNotFound(id) => {
+                write!(f, "No active session with id {}", id)
+            }
+            GrapevineServerError::SessionNotPermitted => {
+                write!(f, "Sessions can only be managed by requests signed with the account key")
+            }
+            GrapevineServerError::PhraseNotFound(phrase_hash) => {
+                write!(f, "No degree 1 proof exists for phrase hash {}", phrase_hash)
+            }
+            GrapevineServerError::RouteNotFound(target) => write!(f, "No route matches {}", target),
+            GrapevineServerError::MethodNotAllowed(target) => {
+                write!(f, "Method not allowed for {}", target)
+            }
+            GrapevineServerError::PhraseFrozen(phrase_hash) => {
+                write!(f, "Phrase hash {} is frozen and cannot be proven", phrase_hash)
+            }
+            GrapevineServerError::ClaimNotPermitted(username) => {
+                write!(f, "Signed claims about {} can only be requested by {}", username, username)
+            }
+            GrapevineServerError::StaleProof(oid) => {
+                write!(f, "Proof {} has already been continued by this user", oid)
+            }
+            GrapevineServerError::ProfileNotPermitted(username) => {
+                write!(f, "The full profile of {} can only be requested by {}", username, username)
+            }
+            GrapevineServerError::RelationshipConfirmed(username) => {
+                write!(f, "Relationship with {} is confirmed and must be deleted instead", username)
+            }
+            GrapevineServerError::AvailableNotPermitted(username) => {
+                write!(f, "Available proofs of {} can only be counted by {}", username, username)
+            }
+            GrapevineServerError::PhraseAlreadyKnown(phrase_hash) => {
+                write!(f, "User already has a proof for the phrase with hash {}", phrase_hash)
+            }
+            GrapevineServerError::ProofReplayed(auth_hash) => {
+                write!(f, "A proof with auth hash {} has already been submitted", auth_hash)
+            }
+            GrapevineServerError::UploadLimitReached(limit) => {
+                write!(f, "Limit of {} uploads in progress reached", limit)
+            }
+            GrapevineServerError::UploadChunkLimit(limit) => {
+                write!(f, "Upload exceeds the limit of {} chunks", limit)
+            }
         }
     }
 }
 
 impl std::error::Error for GrapevineServerError {}
+
+/**
+ * A failed request to the grapevine server, parsed from the response so clients can match on the
+ * error instead of comparing response bodies
+ * @dev error responses carry a json GrapevineServerError, except for the plain text bodies of
+ *      not found, payload too large, and not implemented responses
+ */
+#[derive(Clone, Debug)]
+pub enum GrapevineClientError {
+    // the status and structured error the server responded with
+    Server(u16, GrapevineServerError),
+    // the status and plain text body of a response without a structured error
+    Message(u16, String),
+}
+
+impl GrapevineClientError {
+    /**
+     * Parse the body of an error response
+     *
+     * @param status - the http status code of the response
+     * @param body - the raw response body
+     * @returns - the structured error if the body is a json GrapevineServerError, or the body as
+     *            text otherwise
+     */
+    pub fn from_body(status: u16, body: &[u8]) -> Self {
+        match serde_json::from_slice::<GrapevineServerError>(body) {
+            Ok(err) => GrapevineClientError::Server(status, err),
+            Err(_) => GrapevineClientError::Message(status, String::from_utf8_lossy(body).into()),
+        }
+    }
+
+    /**
+     * Read and parse an error response from the server
+     *
+     * @param res - the response to a request that did not succeed
+     * @returns - the parsed error (with the read error as its message if the body was unreadable)
+     */
+    pub async fn from_response(res: reqwest::Response) -> Self {
+        let status = res.status().as_u16();
+        match res.bytes().await {
+            Ok(body) => Self::from_body(status, &body),
+            Err(e) => GrapevineClientError::Message(status, e.to_string()),
+        }
+    }
+
+    /**
+     * Get the http status the server responded with
+     *
+     * @returns - the status code of the response
+     */
+    pub fn status(&self) -> u16 {
+        match self {
+            GrapevineClientError::Server(status, _) | GrapevineClientError::Message(status, _) => {
+                *status
+            }
+        }
+    }
+
+    /**
+     * Get the structured error the server responded with
+     *
+     * @returns - the server error, or None if the response had a plain text body
+     */
+    pub fn server_error(&self) -> Option<&GrapevineServerError> {
+        match self {
+            GrapevineClientError::Server(_, err) => Some(err),
+            GrapevineClientError::Message(_, _) => None,
+        }
+    }
+}
+
+impl std::fmt::Display for GrapevineClientError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            GrapevineClientError::Server(status, err) => {
+                write!(f, "Server error {}: {}", status, err)
+            }
+            GrapevineClientError::Message(status, msg) => {
+                write!(f, "Server error {}: {}", status, msg)
+            }
+        }
+    }
+}
+
+impl std::error::Error for GrapevineClientError {}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum NonceError {
+    Malformed(String),
+    Overflow,
+}
+
+impl std::fmt::Display for NonceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            NonceError::Malformed(nonce) => {
+                write!(f, "Nonce \"{}\" is not an unsigned integer", nonce)
+            }
+            NonceError::Overflow => write!(f, "Nonce cannot be incremented past u64::MAX"),
+        }
+    }
+}
+
+impl std::error::Error for NonceError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_client_error_from_body() {
+        // each structured error body parses into its variant
+        let errors = vec![
+            (400, GrapevineServerError::Signature(String::from("msg"))),
+            (400, GrapevineServerError::DegreeProofVerificationFailed),
+            (401, GrapevineServerError::NonceMismatch(1, 2)),
+            (403, GrapevineServerError::PhraseFrozen(String::from("0x00"))),
+            (404, GrapevineServerError::RouteNotFound(String::from("GET /"))),
+            (405, GrapevineServerError::MethodNotAllowed(String::from("GET /"))),
+            (409, GrapevineServerError::UsernameExists(String::from("user"))),
+            (410, GrapevineServerError::UserGone(String::from("user"))),
+            (429, GrapevineServerError::RateLimited(1000)),
+            (500, GrapevineServerError::InternalError),
+            (503, GrapevineServerError::VerificationBusy(1000)),
+            (504, GrapevineServerError::VerificationTimeout(1000)),
+        ];
+        for (status, err) in errors {
+            let body = serde_json::to_vec(&err).unwrap();
+            let parsed = GrapevineClientError::from_body(status, &body);
+            assert_eq!(parsed.status(), status);
+            // compare the encoded variants as GrapevineServerError has no PartialEq
+            let parsed = serde_json::to_vec(parsed.server_error().unwrap()).unwrap();
+            assert_eq!(parsed, body);
+        }
+        let body = serde_json::to_vec(&GrapevineServerError::NonceMismatch(1, 2)).unwrap();
+        assert!(matches!(
+            GrapevineClientError::from_body(401, &body),
+            GrapevineClientError::Server(401, GrapevineServerError::NonceMismatch(1, 2))
+        ));
+
+        // plain text bodies are kept as messages
+        let parsed = GrapevineClientError::from_body(404, b"User user does not exist");
+        assert!(parsed.server_error().is_none());
+        match parsed {
+            GrapevineClientError::Message(404, msg) => assert_eq!(msg, "User user does not exist"),
+            _ => panic!("expected a message"),
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+/**
+ * Serialize a 32 byte hash as a 64 character lowercase hex string
+ *
+ * @param hash - the hash to serialize
+ * @param serializer - the serde serializer
+ */
+pub fn serialize<S: Serializer>(hash: &[u8; 32], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&hex::encode(hash))
+}
+
+/**
+ * Deserialize a 32 byte hash from a 64 character hex string
+ *
+ * @param deserializer - the serde deserializer
+ * @returns - the decoded hash, or an error if the string is not a hex encoded 32 byte hash
+ */
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[u8; 32], D::Error> {
+    let encoded = String::deserialize(deserializer)?;
+    decode(&encoded).map_err(D::Error::custom)
+}
+
+fn decode(encoded: &str) -> Result<[u8; 32], String> {
+    let bytes = hex::decode(encoded).map_err(|e| e.to_string())?;
+    bytes
+        .try_into()
+        .map_err(|_| String::from("expected a hex encoded 32 byte hash"))
+}
+
+/** Hex encoding for an optional 32 byte hash */
+pub mod option {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        hash: &Option<[u8; 32]>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        hash.map(hex::encode).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<[u8; 32]>, D::Error> {
+        match Option::<String>::deserialize(deserializer)? {
+            Some(encoded) => decode(&encoded).map(Some).map_err(D::Error::custom),
+            None => Ok(None),
+        }
+    }
+}
+
+/** Hex encoding for a list of 32 byte hashes */
+pub mod vec {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(hashes: &[[u8; 32]], serializer: S) -> Result<S::Ok, S::Error> {
+        let encoded: Vec<String> = hashes.iter().map(hex::encode).collect();
+        encoded.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<[u8; 32]>, D::Error> {
+        Vec::<String>::deserialize(deserializer)?
+            .iter()
+            .map(|encoded| decode(encoded).map_err(D::Error::custom))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::http::responses::DegreeData;
+
+    #[test]
+    fn test_degree_data_phrase_hash_hex() {
+        let mut phrase_hash = [0u8; 32];
+        phrase_hash[0] = 0xab;
+        phrase_hash[31] = 0x01;
+        let degree = DegreeData {
+            degree: 2,
+            relation: Some(String::from("alice")),
+            phrase_hash,
+        };
+        // the hash is a lowercase hex string rather than an array of numbers
+        let json = serde_json::to_value(&degree).unwrap();
+        let encoded = json["phrase_hash"].as_str().unwrap();
+        assert_eq!(encoded.len(), 64);
+        assert_eq!(encoded, format!("ab{}01", "00".repeat(30)));
+        // and decodes back to the same bytes
+        let decoded = serde_json::from_value::<DegreeData>(json).unwrap();
+        assert_eq!(decoded.phrase_hash, phrase_hash);
+        // strings that are not a 32 byte hash are rejected
+        let short = serde_json::json!({ "degree": 2, "relation": null, "phrase_hash": "abcd" });
+        assert!(serde_json::from_value::<DegreeData>(short).is_err());
+    }
+}
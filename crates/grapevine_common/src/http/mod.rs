@@ -1,2 +1,13 @@
+/**
+ * Serde helpers that encode 32 byte hashes in api responses as lowercase hex strings
+ * @dev use with #[serde(with = "hex_hash")], or hex_hash::option / hex_hash::vec for wrapped
+ *      hashes. Only the api forms use these, so hashes stored in the database are unchanged
+ */
+pub mod hex_hash;
 pub mod requests;
-pub mod responses;
\ No newline at end of file
+pub mod responses;
+
+/** The header naming the user an authenticated request is made by */
+pub const USERNAME_HEADER: &str = "X-Username";
+/** The header carrying the hex encoded signature over H|username, nonce| */
+pub const AUTHORIZATION_HEADER: &str = "X-Authorization";
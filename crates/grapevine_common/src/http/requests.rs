@@ -1,46 +1,106 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
 pub struct CreateUserRequest {
     pub username: String,
     pub pubkey: [u8; 32],
+    pub challenge: [u8; 32],
     #[serde(with = "serde_bytes")]
+    #[schemars(with = "Vec<u8>")]
     pub signature: [u8; 64],
+    #[serde(default)]
+    pub invite: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
 pub struct NewPhraseRequest {
     pub proof: Vec<u8>,
 
 }
-#[derive(Serialize, Deserialize, Debug, Clone)]
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+pub struct NewPhraseBatchRequest {
+    pub proofs: Vec<Vec<u8>>,
+}
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+pub struct VerifyProofRequest {
+    pub proof: Vec<u8>,
+    pub degree: u8,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+pub struct VerifyClaimRequest {
+    pub username: String,
+    #[serde(with = "serde_bytes")]
+    #[schemars(with = "[u8; 32]")]
+    pub phrase_hash: [u8; 32],
+    pub max_degree: u8,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+pub struct VerifySignatureRequest {
+    pub username: String,
+    #[serde(with = "serde_bytes")]
+    #[schemars(with = "Vec<u8>")]
+    pub message: Vec<u8>,
+    #[serde(with = "serde_bytes")]
+    #[schemars(with = "Vec<u8>")]
+    pub signature: [u8; 64],
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
 pub struct GetNonceRequest {
     pub username: String,
     #[serde(with = "serde_bytes")]
+    #[schemars(with = "Vec<u8>")]
     pub signature: [u8; 64],
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
 pub struct TestProofCompressionRequest {
     pub proof: Vec<u8>,
     pub username: String,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
 pub struct NewRelationshipRequest {
     pub to: String,
     #[serde(with = "serde_bytes")]
+    #[schemars(with = "[u8; 32]")]
+    pub ephemeral_key: [u8; 32],
+    #[serde(with = "serde_bytes")]
+    #[schemars(with = "Vec<u8>")]
+    pub ciphertext: [u8; 48],
+    #[serde(default)]
+    pub nonce: Option<[u8; 12]>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+pub struct UpdateRelationshipRequest {
+    #[serde(with = "serde_bytes")]
+    #[schemars(with = "[u8; 32]")]
     pub ephemeral_key: [u8; 32],
     #[serde(with = "serde_bytes")]
+    #[schemars(with = "Vec<u8>")]
     pub ciphertext: [u8; 48],
+    #[serde(default)]
+    pub nonce: Option<[u8; 12]>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
 pub struct DegreeProofRequest {
     pub proof: Vec<u8>,
     pub previous: String,
     pub degree: u8,
 }
 
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+pub struct AutoParamsRequest {
+    #[serde(with = "serde_bytes")]
+    #[schemars(with = "[u8; 32]")]
+    pub phrase_hash: [u8; 32],
+}
+
 pub struct RequestDriver {
     pub url: String,
 }
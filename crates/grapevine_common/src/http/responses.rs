@@ -1,9 +1,271 @@
+use crate::errors::GrapevineServerError;
+use crate::http::hex_hash;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
 pub struct DegreeData {
     pub degree: u8,
     pub relation: Option<String>,
+    #[serde(with = "hex_hash")]
+    #[schemars(with = "String")]
+    pub phrase_hash: [u8; 32],
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct GlobalStats {
+    pub users: u64,
+    pub phrases: u64,
+    pub proofs: u64,
+    pub relationships: u64,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct TopConnector {
+    pub username: String,
+    pub relationships: u64,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+pub struct BatchRelationshipResult {
+    pub to: String,
+    pub status: u16,
+    pub error: Option<GrapevineServerError>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct CircuitInfo {
+    pub r1cs_hash: String,
+    pub public_params_hash: String,
+    pub version: String,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct ParamsMigration {
+    pub version: String,
+    pub migrated: u64,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct GraphEdge {
+    pub from: String,
+    pub to: String,
+    pub degree: u8,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+pub struct ConnectionGraph {
+    pub nodes: Vec<String>,
+    pub edges: Vec<GraphEdge>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct ProofChild {
+    pub oid: String,
+    pub username: String,
+    pub degree: u8,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct ChainProof {
+    pub oid: String,
+    pub degree: u8,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct ProofMetadata {
+    pub oid: String,
+    pub degree: u8,
+    #[serde(with = "hex_hash")]
+    #[schemars(with = "String")]
+    pub phrase_hash: [u8; 32],
+    pub username: Option<String>, // None if the proof anonymously originated its phrase
+    pub created_at: i64,          // milliseconds since the unix epoch
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct SignedClaim {
+    pub username: String,
+    #[serde(with = "hex_hash")]
+    #[schemars(with = "String")]
+    pub phrase_hash: [u8; 32],
+    pub max_degree: u8,
+    pub within: bool,   // whether the user holds a proof at or below max_degree
+    pub issued_at: i64, // milliseconds since the unix epoch
+    #[serde(with = "serde_bytes")]
+    #[schemars(with = "Vec<u8>")]
+    pub signature: [u8; 64], // the server's signature over crypto::within_claim_hash
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct PhraseOriginator {
+    pub username: Option<String>, // None if the phrase was originated anonymously
+    pub oid: String,              // the oid of the degree 1 proof that created the phrase
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct ProofVerification {
+    pub verified: bool,
+    #[serde(default, with = "hex_hash::option")]
+    #[schemars(with = "Option<String>")]
+    pub phrase_hash: Option<[u8; 32]>,
+    #[serde(default, with = "hex_hash::option")]
+    #[schemars(with = "Option<String>")]
+    pub auth_hash: Option<[u8; 32]>,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RelationshipDirection {
+    Sent,
+    Received,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+pub struct RelationshipData {
+    pub username: String,
+    pub direction: RelationshipDirection,
+    pub confirmed: bool,
     #[serde(with = "serde_bytes")]
+    #[schemars(with = "[u8; 32]")]
+    pub ephemeral_key: [u8; 32],
+    #[serde(with = "serde_bytes")]
+    #[schemars(with = "Vec<u8>")]
+    pub ciphertext: [u8; 48],
+    pub nonce: Option<[u8; 12]>,
+}
+
+/** A relationship the caller participates in, without its encrypted auth secret */
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct RelationshipSummary {
+    pub username: String, // the counterparty of the caller
+    pub direction: RelationshipDirection,
+    pub confirmed: bool, // the counterparty has a relationship in the other direction too
+}
+
+/** Checks on the stored encryption of a relationship's auth secret that reveal nothing secret */
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct RelationshipDiagnostics {
+    pub username: String,
+    pub direction: RelationshipDirection,
+    pub ephemeral_key_valid: bool, // ephemeral_key decompresses to a point on the curve
+    pub ciphertext_length: usize,  // number of stored ciphertext bytes (0 if missing)
+    pub legacy: bool,              // no aes-gcm nonce is stored (encrypted with aes-cbc)
+    pub format_intact: bool,       // nonce and ciphertext are the lengths the scheme produces
+}
+
+/** A proof the caller can build a new degree proof from */
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct AvailableProof {
+    pub oid: String,
+    pub degree: u8, // the degree the caller would prove by building on this proof
+}
+
+/** Everything a client needs on app open, without any proof bytes */
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+pub struct Dashboard {
+    #[serde(with = "hex_hash::vec")]
+    #[schemars(with = "Vec<String>")]
+    pub phrases: Vec<[u8; 32]>, // the phrase hashes the caller created
+    pub degrees: Vec<DegreeData>,
+    pub available: Vec<AvailableProof>,
+    pub pending: Vec<String>, // senders of relationships the caller has not sent back
+}
+
+/** A newly issued session token */
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+pub struct SessionToken {
+    pub id: String,
+    pub token: String, // sent in the X-Session header to authenticate without signing a nonce
+    pub expires_at: i64, // milliseconds since the unix epoch
+}
+
+/** An active session, without the token that authenticates it */
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct SessionData {
+    pub id: String,
+    pub created_at: i64, // milliseconds since the unix epoch
+    pub expires_at: i64, // milliseconds since the unix epoch
+}
+
+/** An auth secret a sender encrypted to the caller, as stored in their relationship */
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+pub struct ReceivedAuthSecret {
+    pub sender: String,
+    #[serde(with = "serde_bytes")]
+    #[schemars(with = "[u8; 32]")]
+    pub ephemeral_key: [u8; 32],
+    #[serde(with = "serde_bytes")]
+    #[schemars(with = "Vec<u8>")]
+    pub ciphertext: [u8; 48],
+    pub nonce: Option<[u8; 12]>,
+}
+
+/** A proof chain exported from one Grapevine server that can be imported into another */
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+pub struct ChainExport {
+    pub users: Vec<ExportedUser>,
+    pub relationships: Vec<ExportedRelationship>,
+    pub proofs: Vec<ExportedProof>, // ordered so every proof follows the proof it builds on
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+pub struct ExportedUser {
+    pub username: String,
+    #[serde(with = "serde_bytes")]
+    #[schemars(with = "[u8; 32]")]
+    pub pubkey: [u8; 32],
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+pub struct ExportedRelationship {
+    pub sender: String,
+    pub recipient: String,
+    #[serde(with = "serde_bytes")]
+    #[schemars(with = "[u8; 32]")]
+    pub ephemeral_key: [u8; 32],
+    #[serde(with = "serde_bytes")]
+    #[schemars(with = "Vec<u8>")]
+    pub ciphertext: [u8; 48],
+    pub nonce: Option<[u8; 12]>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone)]
+pub struct ExportedProof {
+    pub oid: String, // the oid on the exporting server, used to link proofs within the chain
+    pub username: String,
+    pub degree: u8,
+    #[serde(with = "hex_hash")]
+    #[schemars(with = "String")]
+    pub phrase_hash: [u8; 32],
+    #[serde(with = "hex_hash")]
+    #[schemars(with = "String")]
+    pub auth_hash: [u8; 32],
+    #[serde(with = "serde_bytes")]
+    #[schemars(with = "Vec<u8>")]
+    pub proof: Vec<u8>,
+    pub preceding: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct ChainImport {
+    pub proofs: Vec<String>, // the new oid of each imported proof, in the order of the export
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct AuthHashReuse {
+    #[serde(with = "hex_hash")]
+    #[schemars(with = "String")]
+    pub auth_hash: [u8; 32],
+    pub origins: Vec<PhraseOrigin>, // every degree 1 proof with the auth hash, ordered by oid
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct PhraseOrigin {
+    pub oid: String,              // the oid of the degree 1 proof
+    pub username: Option<String>, // None if the user who made the proof no longer exists
+    #[serde(with = "hex_hash")]
+    #[schemars(with = "String")]
     pub phrase_hash: [u8; 32],
-}
\ No newline at end of file
+}
@@ -6,6 +6,7 @@ pub mod auth_secret;
 pub mod compat;
 pub mod crypto;
 pub mod http;
+pub mod nonce;
 pub mod utils;
 pub mod models;
 pub mod errors;
@@ -18,5 +19,11 @@ pub type Params = PublicParams<G1, G2, C1<G1>, C2<G2>>;
 pub type NovaProof = RecursiveSNARK<G1, G2, CircomCircuit<Fr>, TrivialTestCircuit<Fq>>;
 
 pub const SECRET_FIELD_LENGTH: usize = 6;
-pub const MAX_SECRET_CHARS: usize = 180;
+// the most bytes of phrase the circuit packs into its SECRET_FIELD_LENGTH field elements
+pub const MAX_PHRASE_BYTES: usize = 180;
 pub const MAX_USERNAME_CHARS: usize = 30;
+pub const MAX_RELATIONSHIP_BATCH: usize = 50;
+pub const MAX_GRAPH_DEPTH: u8 = 4;
+pub const MAX_PHRASE_BATCH: usize = 5;
+pub const MAX_CHAIN_IMPORT: usize = 100;
+pub const MAX_TOP_CONNECTORS: u32 = 100;
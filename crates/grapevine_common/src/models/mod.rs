@@ -1,3 +1,4 @@
 pub mod user;
 pub mod proof;
-pub mod relationship;
\ No newline at end of file
+pub mod relationship;
+pub mod session;
\ No newline at end of file
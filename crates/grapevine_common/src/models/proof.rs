@@ -1,4 +1,5 @@
-use bson::oid::ObjectId;
+use bson::{oid::ObjectId, DateTime};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -14,16 +15,26 @@ pub struct DegreeProof {
     pub proof: Option<Vec<u8>>, // compressed proof
     pub preceding: Option<ObjectId>, // the proof that this proof is built on (null if first)
     pub proceeding: Option<Vec<ObjectId>>, // proofs that are built on top of this proof
+    pub proof_size: Option<u64>,     // size of the proof once decompressed
+    pub params_version: Option<String>, // hash of the public params the proof was made with
+    #[serde(default)]
+    pub created_at: Option<DateTime>, // None for proofs added before creation times were stored
+    #[serde(default)]
+    pub anonymous: Option<bool>, // Some(true) if a degree 1 proof withholds its creator
 }
 
 // all data needed from server to prove a degree of separation
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, PartialEq)]
 pub struct ProvingData {
     pub degree: u8, // multiply by 2 to get iterations
     pub proof: Vec<u8>,
     pub username: String,
     #[serde(with = "serde_bytes")]
+    #[schemars(with = "[u8; 32]")]
     pub ephemeral_key: [u8; 32],
     #[serde(with = "serde_bytes")]
+    #[schemars(with = "Vec<u8>")]
     pub ciphertext: [u8; 48],
+    #[serde(default)]
+    pub nonce: Option<[u8; 12]>,
 }
@@ -1,4 +1,4 @@
-use bson::oid::ObjectId;
+use bson::{oid::ObjectId, DateTime};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -11,4 +11,7 @@ pub struct Relationship {
     pub ephemeral_key: Option<[u8; 32]>,
     #[serde(with = "serde_bytes")]
     pub ciphertext: Option<[u8; 48]>,
+    #[serde(default, with = "serde_bytes")]
+    pub nonce: Option<[u8; 12]>, // aes-gcm nonce; None for legacy aes-cbc ciphertexts
+    pub created_at: Option<DateTime>,
 }
\ No newline at end of file
@@ -0,0 +1,10 @@
+use bson::DateTime;
+use serde::{Deserialize, Serialize};
+
+/** A session token issued to a user, which authenticates requests until it expires or is revoked */
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Session {
+    pub id: String,
+    pub created_at: DateTime,
+    pub expires_at: DateTime,
+}
@@ -1,4 +1,6 @@
-use bson::oid::ObjectId;
+use crate::models::session::Session;
+use crate::nonce::Nonce;
+use bson::{oid::ObjectId, DateTime};
 use serde::{Deserialize, Serialize};
 
 // All fields optional to allow projections
@@ -6,10 +8,14 @@ use serde::{Deserialize, Serialize};
 pub struct User {
     #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
     pub id: Option<ObjectId>,
-    pub nonce: Option<u64>,
+    pub nonce: Option<Nonce>,
     pub username: Option<String>,
     #[serde(with = "serde_bytes")]
     pub pubkey: Option<[u8; 32]>,
     pub relationships: Option<Vec<ObjectId>>, // references to connections (includes reference to connected user + their auth secret)
     pub degree_proofs: Option<Vec<ObjectId>>, // references to degree proofs by this user
+    pub last_active: Option<DateTime>, // last authenticated request (see LAST_ACTIVE_DEBOUNCE)
+    pub deactivated: Option<bool>, // deactivated users can no longer authenticate
+    #[serde(default)]
+    pub sessions: Option<Vec<Session>>, // session tokens that have been issued and not revoked
 }
@@ -0,0 +1,105 @@
+use crate::errors::NonceError;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/**
+ * The per-user counter signed over to authenticate gated http actions
+ * @notice serializes as a bare integer so stored users and saved accounts are unchanged
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+#[serde(transparent)]
+pub struct Nonce(pub u64);
+
+impl Nonce {
+    /**
+     * Get the nonce that follows this one
+     *
+     * @returns - the incremented nonce, or an error if the nonce is already at u64::MAX
+     */
+    pub fn increment(&self) -> Result<Nonce, NonceError> {
+        match self.0.checked_add(1) {
+            Some(nonce) => Ok(Nonce(nonce)),
+            None => Err(NonceError::Overflow),
+        }
+    }
+
+    /** Return the little endian bytes of the nonce used when hashing it */
+    pub fn to_le_bytes(&self) -> [u8; 8] {
+        self.0.to_le_bytes()
+    }
+}
+
+impl FromStr for Nonce {
+    type Err = NonceError;
+
+    /**
+     * Parse a nonce from its decimal string form
+     * @notice rejects negative and non-numeric input rather than wrapping
+     */
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().parse::<u64>() {
+            Ok(nonce) => Ok(Nonce(nonce)),
+            Err(_) => Err(NonceError::Malformed(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for Nonce {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<u64> for Nonce {
+    fn from(nonce: u64) -> Self {
+        Nonce(nonce)
+    }
+}
+
+impl From<Nonce> for u64 {
+    fn from(nonce: Nonce) -> Self {
+        nonce.0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_increment() {
+        assert_eq!(Nonce(0).increment().unwrap(), Nonce(1));
+        assert_eq!(Nonce(u64::MAX - 1).increment().unwrap(), Nonce(u64::MAX));
+    }
+
+    #[test]
+    fn test_increment_overflow() {
+        let res = Nonce(u64::MAX).increment();
+        assert!(matches!(res, Err(NonceError::Overflow)));
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("42".parse::<Nonce>().unwrap(), Nonce(42));
+        assert_eq!(Nonce(42).to_string(), "42");
+        let max = u64::MAX.to_string();
+        assert_eq!(max.parse::<Nonce>().unwrap(), Nonce(u64::MAX));
+    }
+
+    #[test]
+    fn test_parse_malformed() {
+        for input in ["", "abc", "-1", "1.5", "18446744073709551616"] {
+            let res = input.parse::<Nonce>();
+            assert!(matches!(res, Err(NonceError::Malformed(_))), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_serde() {
+        let json = serde_json::to_string(&Nonce(7)).unwrap();
+        assert_eq!(json, "7");
+        assert_eq!(serde_json::from_str::<Nonce>("7").unwrap(), Nonce(7));
+        assert!(serde_json::from_str::<Nonce>("-7").is_err());
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{Fr, MAX_SECRET_CHARS, MAX_USERNAME_CHARS, SECRET_FIELD_LENGTH};
+use crate::{Fr, MAX_PHRASE_BYTES, MAX_USERNAME_CHARS, SECRET_FIELD_LENGTH};
 use std::error::Error;
 
 /**
@@ -21,8 +21,8 @@ pub fn convert_phrase_to_fr(
     phrase: &String,
 ) -> Result<[[u8; 32]; SECRET_FIELD_LENGTH], Box<dyn Error>> {
     // check
-    if phrase.len() > MAX_SECRET_CHARS {
-        return Err("Phrase must be <= 180 characters".into());
+    if phrase.len() > MAX_PHRASE_BYTES {
+        return Err(format!("Phrase must be <= {} bytes", MAX_PHRASE_BYTES).into());
     }
 
     let mut chunks: [[u8; 32]; SECRET_FIELD_LENGTH] = Default::default();
@@ -59,3 +59,99 @@ pub fn convert_username_to_fr(username: &String) -> Result<[u8; 32], Box<dyn Err
     //    Ok(format!("0x{}", hex::encode(bytes)))
     Ok(bytes)
 }
+
+/**
+ * Hex encodes a field element such as a phrase or auth hash
+ *
+ * @param fr - the field element to encode
+ * @return - the little endian bytes of the field element as a hex string
+ */
+pub fn fr_to_hex(fr: &Fr) -> String {
+    hex::encode(fr.to_bytes())
+}
+
+/**
+ * Decodes a hex string into a field element
+ * @dev values >= the field modulus are rejected rather than reduced so every element has exactly
+ *      one accepted encoding
+ *
+ * @param value - the hex encoded little endian bytes of the field element (optionally 0x prefixed)
+ * @return - the field element, or an error if the value is not a canonical 32 byte field element
+ */
+pub fn fr_from_hex(value: &str) -> Result<Fr, Box<dyn Error>> {
+    let value = value.strip_prefix("0x").unwrap_or(value);
+    let bytes: [u8; 32] = match hex::decode(value)?.try_into() {
+        Ok(bytes) => bytes,
+        Err(_) => return Err("Field element must be 32 bytes".into()),
+    };
+    Option::from(Fr::from_bytes(&bytes))
+        .ok_or_else(|| "Value is not a canonical field element".into())
+}
+
+/**
+ * Normalizes a username to the canonical form two usernames are compared in for collisions
+ * @dev usernames are ascii, so case folding is ascii lowercasing
+ *
+ * @param username - the username to normalize
+ * @return - the canonical username
+ */
+pub fn normalize_username(username: &str) -> String {
+    username.to_ascii_lowercase()
+}
+
+/**
+ * Re-encodes a compressed baby jubjub pubkey in the canonical compressed form of its point
+ * @dev the sign bit of x is meaningless when x = 0, so some points have more than one encoding
+ *
+ * @param pubkey - the compressed pubkey
+ * @return - the canonical compressed pubkey, or None if the bytes do not encode a point
+ */
+pub fn canonical_pubkey(pubkey: &[u8; 32]) -> Option<[u8; 32]> {
+    babyjubjub_rs::decompress_point(*pubkey)
+        .ok()
+        .map(|point| point.compress())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn fr_hex_round_trip_test() {
+        let fr = random_fr();
+        let encoded = fr_to_hex(&fr);
+        assert_eq!(encoded.len(), 64);
+        assert_eq!(fr_from_hex(&encoded).unwrap(), fr);
+        assert_eq!(fr_from_hex(&format!("0x{}", encoded)).unwrap(), fr);
+    }
+
+    #[test]
+    fn fr_hex_rejects_non_canonical_test() {
+        // 2^256 - 1 is larger than the bn254 scalar field modulus
+        assert!(fr_from_hex(&"ff".repeat(32)).is_err());
+        // wrong length and invalid hex are rejected
+        assert!(fr_from_hex(&"00".repeat(31)).is_err());
+        assert!(fr_from_hex("not hex").is_err());
+    }
+
+    #[test]
+    fn normalize_username_test() {
+        assert_eq!(normalize_username("Alice"), normalize_username("alice"));
+        assert_ne!(normalize_username("alice"), normalize_username("alice_"));
+    }
+
+    #[test]
+    fn canonical_pubkey_test() {
+        // a real pubkey is already canonical
+        let pubkey = babyjubjub_rs::new_key().public().compress();
+        assert_eq!(canonical_pubkey(&pubkey), Some(pubkey));
+        // the identity point with and without the sign bit set is the same point
+        let mut identity = [0u8; 32];
+        identity[0] = 1;
+        let mut reencoded = identity;
+        reencoded[31] |= 0x80;
+        assert_eq!(canonical_pubkey(&reencoded), Some(identity));
+        // y outside of the field does not encode a point
+        assert_eq!(canonical_pubkey(&[0xff; 32]), None);
+    }
+}
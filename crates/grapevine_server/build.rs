@@ -8,4 +8,13 @@ fn main() {
     println!("cargo:rustc-env=MONGODB_URI={}", mongodb_uri);
     let database_name = env::var("DATABASE_NAME").unwrap_or("grapevine".to_string());
     println!("cargo:rustc-env=DATABASE_NAME={}", database_name);
-}
\ No newline at end of file
+    let max_proof_size = env::var("MAX_PROOF_SIZE").unwrap_or("2097152".to_string());
+    println!("cargo:rustc-env=MAX_PROOF_SIZE={}", max_proof_size);
+    let verification_timeout = env::var("VERIFICATION_TIMEOUT_MS").unwrap_or("30000".to_string());
+    println!(
+        "cargo:rustc-env=VERIFICATION_TIMEOUT_MS={}",
+        verification_timeout
+    );
+    let max_relationships = env::var("MAX_RELATIONSHIPS").unwrap_or("10000".to_string());
+    println!("cargo:rustc-env=MAX_RELATIONSHIPS={}", max_relationships);
+}
@@ -0,0 +1,120 @@
+use rocket::figment::Figment;
+use rocket::Config;
+use std::net::{IpAddr, Ipv4Addr};
+
+/** Server configuration for the address and port rocket listens on */
+pub struct BindConfig {
+    // the interface to bind to (rocket's default of 127.0.0.1 if None)
+    pub address: Option<IpAddr>,
+    // the port to listen on (rocket's default of 8000 if None)
+    pub port: Option<u16>,
+}
+
+impl BindConfig {
+    /**
+     * Read the bind config from the environment
+     *
+     * @returns - the config from GRAPEVINE_ADDRESS (i.e. 0.0.0.0 in a container) and
+     *            GRAPEVINE_PORT, each falling back to rocket's default if unset
+     */
+    pub fn from_env() -> Self {
+        BindConfig {
+            address: address_var("GRAPEVINE_ADDRESS"),
+            port: port_var("GRAPEVINE_PORT"),
+        }
+    }
+
+    /**
+     * Read the bind config of the separate admin listener from the environment
+     * @notice admin routes are mounted on the public listener if ADMIN_PORT is unset
+     *
+     * @returns - the config from ADMIN_ADDRESS (default 127.0.0.1) and ADMIN_PORT, or None if
+     *            ADMIN_PORT is unset
+     */
+    pub fn admin_from_env() -> Option<Self> {
+        let port = port_var("ADMIN_PORT")?;
+        let address = address_var("ADMIN_ADDRESS").unwrap_or(IpAddr::V4(Ipv4Addr::LOCALHOST));
+        Some(BindConfig {
+            address: Some(address),
+            port: Some(port),
+        })
+    }
+
+    /**
+     * Build the rocket config figment with the bind address and port applied
+     * @dev starts from rocket's default figment so ROCKET_* variables still apply where the bind
+     *      config is unset
+     *
+     * @returns - the figment to launch rocket with
+     */
+    pub fn figment(&self) -> Figment {
+        let mut figment = Config::figment();
+        if let Some(address) = self.address {
+            figment = figment.merge((Config::ADDRESS, address));
+        }
+        if let Some(port) = self.port {
+            figment = figment.merge((Config::PORT, port));
+        }
+        figment
+    }
+}
+
+fn address_var(name: &str) -> Option<IpAddr> {
+    std::env::var(name).ok().map(|address| {
+        address
+            .parse::<IpAddr>()
+            .unwrap_or_else(|_| panic!("{} must be an ip address", name))
+    })
+}
+
+fn port_var(name: &str) -> Option<u16> {
+    std::env::var(name).ok().map(|port| {
+        port.parse::<u16>()
+            .unwrap_or_else(|_| panic!("{} must be a port number", name))
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_bind_config_from_env() {
+        // both steps share one test since they read the same process-wide variables
+        std::env::set_var("GRAPEVINE_ADDRESS", "0.0.0.0");
+        std::env::set_var("GRAPEVINE_PORT", "8080");
+        let config = Config::from(BindConfig::from_env().figment());
+        assert_eq!(config.address, IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+        assert_eq!(config.port, 8080);
+
+        // unset variables keep rocket's defaults
+        std::env::remove_var("GRAPEVINE_ADDRESS");
+        std::env::remove_var("GRAPEVINE_PORT");
+        let bind = BindConfig::from_env();
+        assert!(bind.address.is_none() && bind.port.is_none());
+        let config = Config::from(bind.figment());
+        assert_eq!(config.address, Config::default().address);
+        assert_eq!(config.port, Config::default().port);
+    }
+
+    #[test]
+    fn test_admin_bind_config_from_env() {
+        // no separate admin listener unless a port is given
+        std::env::remove_var("ADMIN_PORT");
+        std::env::remove_var("ADMIN_ADDRESS");
+        assert!(BindConfig::admin_from_env().is_none());
+
+        // the admin listener defaults to loopback
+        std::env::set_var("ADMIN_PORT", "8081");
+        let config = Config::from(BindConfig::admin_from_env().unwrap().figment());
+        assert_eq!(config.address, IpAddr::V4(Ipv4Addr::LOCALHOST));
+        assert_eq!(config.port, 8081);
+
+        // an internal interface can be chosen instead
+        std::env::set_var("ADMIN_ADDRESS", "10.0.0.2");
+        let config = Config::from(BindConfig::admin_from_env().unwrap().figment());
+        assert_eq!(config.address, IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)));
+        std::env::remove_var("ADMIN_PORT");
+        std::env::remove_var("ADMIN_ADDRESS");
+    }
+}
@@ -1,9 +1,11 @@
+use grapevine_circuits::errors::GrapevineCircuitError;
 use grapevine_common::errors::GrapevineServerError;
 use rocket::{
-    http::{ContentType, Status},
+    http::{ContentType, MediaType, Status},
     request::Request,
     response::{self, Responder, Response},
     serde::json::Json,
+    Either, Route,
 };
 use serde::{Deserialize, Serialize};
 
@@ -11,22 +13,178 @@ use serde::{Deserialize, Serialize};
 
 #[derive(Responder)]
 pub enum GrapevineResponse {
+    #[response(status = 200)]
+    Ok(String),
     #[response(status = 201)]
     Created(String),
     #[response(status = 400)]
     BadRequest(ErrorMessage),
     #[response(status = 401)]
     Unauthorized(ErrorMessage),
+    #[response(status = 403)]
+    Forbidden(ErrorMessage),
     #[response(status = 404)]
     NotFound(String),
+    #[response(status = 405)]
+    MethodNotAllowed(ErrorMessage),
     #[response(status = 409)]
     Conflict(ErrorMessage),
+    #[response(status = 410)]
+    Gone(ErrorMessage),
     #[response(status = 413)]
     TooLarge(String),
+    #[response(status = 429)]
+    TooManyRequests(ErrorMessage),
     #[response(status = 500)]
     InternalError(ErrorMessage),
     #[response(status = 501)]
     NotImplemented(String),
+    #[response(status = 503)]
+    ServiceUnavailable(ErrorMessage),
+    #[response(status = 504)]
+    GatewayTimeout(ErrorMessage),
+}
+
+impl GrapevineResponse {
+    /**
+     * Get the http status this response will be sent with
+     *
+     * @returns - the status of the response variant
+     */
+    pub fn status(&self) -> Status {
+        match self {
+            GrapevineResponse::Ok(_) => Status::Ok,
+            GrapevineResponse::Created(_) => Status::Created,
+            GrapevineResponse::BadRequest(_) => Status::BadRequest,
+            GrapevineResponse::Unauthorized(_) => Status::Unauthorized,
+            GrapevineResponse::Forbidden(_) => Status::Forbidden,
+            GrapevineResponse::NotFound(_) => Status::NotFound,
+            GrapevineResponse::MethodNotAllowed(_) => Status::MethodNotAllowed,
+            GrapevineResponse::Conflict(_) => Status::Conflict,
+            GrapevineResponse::Gone(_) => Status::Gone,
+            GrapevineResponse::TooLarge(_) => Status::PayloadTooLarge,
+            GrapevineResponse::TooManyRequests(_) => Status::TooManyRequests,
+            GrapevineResponse::InternalError(_) => Status::InternalServerError,
+            GrapevineResponse::NotImplemented(_) => Status::NotImplemented,
+            GrapevineResponse::ServiceUnavailable(_) => Status::ServiceUnavailable,
+            GrapevineResponse::GatewayTimeout(_) => Status::GatewayTimeout,
+        }
+    }
+}
+
+/**
+ * Map each GrapevineServerError to its canonical response so routes can use `err.into()`
+ */
+impl From<GrapevineServerError> for GrapevineResponse {
+    fn from(err: GrapevineServerError) -> Self {
+        match err {
+            GrapevineServerError::Signature(_)
+            | GrapevineServerError::UsernameTooLong(_)
+            | GrapevineServerError::UsernameNotAscii(_)
+            | GrapevineServerError::UsernameEncodingError(_)
+            | GrapevineServerError::RelationshipSenderIsTarget
+            | GrapevineServerError::HeaderError(_)
+            | GrapevineServerError::SerdeError(_)
+            | GrapevineServerError::DegreeProofVerificationFailed
+            | GrapevineServerError::ProofDecompressionFailed(_)
+            | GrapevineServerError::EmptyProof(_)
+            | GrapevineServerError::UploadIncomplete(_)
+            | GrapevineServerError::InvalidDegree(_)
+            | GrapevineServerError::DegreeMismatch(_)
+            | GrapevineServerError::ChainInvalid(_) => {
+                GrapevineResponse::BadRequest(ErrorMessage(Some(err), None))
+            }
+            GrapevineServerError::NonceMismatch(_, _)
+            | GrapevineServerError::InvalidChallenge
+            | GrapevineServerError::AdminUnauthorized
+            | GrapevineServerError::SessionInvalid => {
+                GrapevineResponse::Unauthorized(ErrorMessage(Some(err), None))
+            }
+            GrapevineServerError::RelationshipNotSender(_)
+            | GrapevineServerError::RelationshipNotParticipant
+            | GrapevineServerError::RegistrationClosed
+            | GrapevineServerError::ProofNotEntitled(_)
+            | GrapevineServerError::RelationshipLimitReached(_)
+            | GrapevineServerError::SessionNotPermitted
+            | GrapevineServerError::PhraseFrozen(_)
+            | GrapevineServerError::ClaimNotPermitted(_)
+            | GrapevineServerError::ProfileNotPermitted(_)
+            | GrapevineServerError::AvailableNotPermitted(_) => {
+                GrapevineResponse::Forbidden(ErrorMessage(Some(err), None))
+            }
+            GrapevineServerError::UserNotFound(_)
+            | GrapevineServerError::RelationshipNotFound(_, _)
+            | GrapevineServerError::UploadNotFound(_)
+            | GrapevineServerError::ProofNotFound(_)
+            | GrapevineServerError::NoAvailableProof(_)
+            | GrapevineServerError::SessionNotFound(_)
+            | GrapevineServerError::PhraseNotFound(_)
+            | GrapevineServerError::RouteNotFound(_) => {
+                GrapevineResponse::NotFound(err.to_string())
+            }
+            GrapevineServerError::MethodNotAllowed(_) => {
+                GrapevineResponse::MethodNotAllowed(ErrorMessage(Some(err), None))
+            }
+            GrapevineServerError::UsernameExists(_)
+            | GrapevineServerError::PubkeyExists(_)
+            | GrapevineServerError::UserExists(_)
+            | GrapevineServerError::RelationshipExists
+            | GrapevineServerError::PhraseAlreadyOriginated(_)
+            | GrapevineServerError::ParamsVersionMismatch(_)
+            | GrapevineServerError::StaleProof(_)
+            | GrapevineServerError::RelationshipConfirmed(_)
+            | GrapevineServerError::PhraseAlreadyKnown(_)
+            | GrapevineServerError::ProofReplayed(_) => {
+                GrapevineResponse::Conflict(ErrorMessage(Some(err), None))
+            }
+            GrapevineServerError::UserGone(_) => {
+                GrapevineResponse::Gone(ErrorMessage(Some(err), None))
+            }
+            GrapevineServerError::BatchTooLarge(_)
+            | GrapevineServerError::UploadTooLarge(_)
+            | GrapevineServerError::UploadChunkLimit(_) => {
+                GrapevineResponse::TooLarge(err.to_string())
+            }
+            GrapevineServerError::RateLimited(_) | GrapevineServerError::UploadLimitReached(_) => {
+                GrapevineResponse::TooManyRequests(ErrorMessage(Some(err), None))
+            }
+            GrapevineServerError::MongoError(_) | GrapevineServerError::InternalError => {
+                GrapevineResponse::InternalError(ErrorMessage(Some(err), None))
+            }
+            GrapevineServerError::VerificationBusy(_) => {
+                GrapevineResponse::ServiceUnavailable(ErrorMessage(Some(err), None))
+            }
+            GrapevineServerError::VerificationTimeout(_) => {
+                GrapevineResponse::GatewayTimeout(ErrorMessage(Some(err), None))
+            }
+        }
+    }
+}
+
+/**
+ * Map each GrapevineCircuitError to a response so proof handling routes can use `err.into()`
+ * @dev failures caused by the submitted proof are the client's fault, while proof and witness
+ *      generation are never performed by the server so indicate a server side issue
+ */
+impl From<GrapevineCircuitError> for GrapevineResponse {
+    fn from(err: GrapevineCircuitError) -> Self {
+        let err = match err {
+            GrapevineCircuitError::Verification(_) => {
+                GrapevineServerError::DegreeProofVerificationFailed
+            }
+            GrapevineCircuitError::Decompression(msg) => {
+                GrapevineServerError::ProofDecompressionFailed(msg)
+            }
+            GrapevineCircuitError::Serialization(msg) => {
+                GrapevineServerError::SerdeError(format!("proof: {}", msg))
+            }
+            GrapevineCircuitError::ProofGeneration(_)
+            | GrapevineCircuitError::WitnessGeneration(_)
+            | GrapevineCircuitError::Artifact(_)
+            | GrapevineCircuitError::PhraseTooLong(_) => GrapevineServerError::InternalError,
+        };
+        err.into()
+    }
 }
 
 // #[catch(400)]
@@ -53,13 +211,87 @@ pub enum GrapevineResponse {
 //     }
 // }
 
-// #[catch(404)]
-// pub fn not_found(req: &Request) -> Response {
-//     match req.local_cache(|| ErrorMessage(None)) {
-//         ErrorMessage(Some(msg)) => Response::NotFound(msg.to_string()),
-//         ErrorMessage(None) => Response::NotFound("Asset not found".to_string()),
-//     }
-// }
+/**
+ * Respond with a json GrapevineServerError to requests that no route handled
+ * @dev rocket answers a request whose path only matches routes for other methods with 404, so the
+ *      mounted routes are checked to answer those with 405 instead
+ *
+ * @return status:
+ *            * 404 if no route matches the request path
+ *            * 405 if the request path only matches routes for other methods
+ */
+#[catch(404)]
+pub fn not_found(req: &Request) -> (Status, ErrorMessage) {
+    // a guard that failed with 404 (i.e. an unknown user) responds with its own error
+    let guard_error = req.local_cache(|| ErrorMessage(None, None));
+    if guard_error.0.is_some() {
+        return (Status::NotFound, guard_error.clone());
+    }
+    let path = req.uri().path().to_string();
+    let target = format!("{} {}", req.method(), path);
+    let (mut same_method, mut other_method) = (false, false);
+    for route in req.rocket().routes() {
+        if route_matches_path(route, &path) {
+            match route.method == req.method() {
+                true => same_method = true,
+                false => other_method = true,
+            }
+        }
+    }
+    match other_method && !same_method {
+        true => (
+            Status::MethodNotAllowed,
+            ErrorMessage(Some(GrapevineServerError::MethodNotAllowed(target)), None),
+        ),
+        false => (
+            Status::NotFound,
+            ErrorMessage(Some(GrapevineServerError::RouteNotFound(target)), None),
+        ),
+    }
+}
+
+/**
+ * Respond with the json GrapevineServerError a request guard failed with
+ * @dev rocket only passes the status of a failed guard to its catcher, so guards cache their
+ *      error on the request. Errors not raised by a guard keep their status with an empty body
+ */
+#[catch(default)]
+pub fn guard_error(status: Status, req: &Request) -> Either<(Status, ErrorMessage), (Status, ())> {
+    let guard_error = req.local_cache(|| ErrorMessage(None, None));
+    match guard_error.0.is_some() {
+        true => Either::Left((status, guard_error.clone())),
+        false => Either::Right((status, ())),
+    }
+}
+
+/**
+ * Check whether a request path matches the path of a mounted route, ignoring the query
+ *
+ * @param route - the mounted route
+ * @param path - the path of the request
+ * @returns - true if every static segment matches and every dynamic segment has a value
+ */
+fn route_matches_path(route: &Route, path: &str) -> bool {
+    let route_path = route.uri.path().to_string();
+    let mut route_segments = route_path.split('/').filter(|segment| !segment.is_empty());
+    let mut segments = path.split('/').filter(|segment| !segment.is_empty());
+    loop {
+        match (route_segments.next(), segments.next()) {
+            // a trailing <param..> segment matches the rest of the path
+            (Some(expected), _) if expected.starts_with('<') && expected.ends_with("..>") => {
+                return true
+            }
+            (Some(expected), Some(segment))
+                if expected == segment
+                    || (expected.starts_with('<') && expected.ends_with('>')) =>
+            {
+                continue
+            }
+            (None, None) => return true,
+            _ => return false,
+        }
+    }
+}
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ErrorMessage(pub Option<GrapevineServerError>, pub Option<u64>);
@@ -89,3 +321,286 @@ impl<'r> Responder<'r, 'static> for ErrorMessage {
         res.header(ContentType::JSON).ok()
     }
 }
+
+/**
+ * A response body serialized according to the request's Accept header
+ * @dev bincode if the client prefers application/octet-stream, otherwise json
+ */
+pub struct Negotiated<T: Serialize>(pub T);
+
+impl<'r, T: Serialize> Responder<'r, 'static> for Negotiated<T> {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'static> {
+        let binary = match req.accept() {
+            Some(accept) => accept.preferred().media_type() == &MediaType::Binary,
+            None => false,
+        };
+        match binary {
+            true => match bincode::serialize(&self.0) {
+                Ok(body) => Response::build_from(body.respond_to(req)?)
+                    .header(ContentType::Binary)
+                    .ok(),
+                Err(_) => Err(Status::InternalServerError),
+            },
+            false => Json(self.0).respond_to(req),
+        }
+    }
+}
+
+/**
+ * A response carrying a proof, reporting the decompressed proof size in X-Grapevine-Proof-Size
+ * @dev the header is omitted if the size of the proof is unknown
+ */
+pub struct ProofSized<R>(pub R, pub Option<u64>);
+
+impl<'r, R: Responder<'r, 'static>> Responder<'r, 'static> for ProofSized<R> {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'static> {
+        let mut res = self.0.respond_to(req)?;
+        if let Some(size) = self.1 {
+            res.set_raw_header("X-Grapevine-Proof-Size", size.to_string());
+        }
+        Ok(res)
+    }
+}
+
+/**
+ * A response signed by the server, carrying the hex encoded compressed signature in
+ * X-Grapevine-Signature
+ */
+pub struct Signed<R>(pub R, pub [u8; 64]);
+
+impl<'r, R: Responder<'r, 'static>> Responder<'r, 'static> for Signed<R> {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'static> {
+        let mut res = self.0.respond_to(req)?;
+        res.set_raw_header("X-Grapevine-Signature", hex::encode(self.1));
+        Ok(res)
+    }
+}
+
+/**
+ * A response to a conditional GET, carrying a strong ETag derived from the hash of its content
+ * @dev the json and bincode encodings of the same content are different representations, so
+ *      the tag of a bincode body is suffixed to keep them distinct
+ *
+ * @return status:
+ *            * 304 with no body if the request's If-None-Match lists the tag (or *)
+ *            * the status of the wrapped response otherwise
+ */
+pub struct Tagged<R>(pub R, pub [u8; 32]);
+
+impl<'r, R: Responder<'r, 'static>> Responder<'r, 'static> for Tagged<R> {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'static> {
+        let mut res = self.0.respond_to(req)?;
+        let representation = match res.content_type() {
+            Some(content_type) if content_type == ContentType::Binary => "-bin",
+            _ => "",
+        };
+        let etag = format!("\"{}{}\"", hex::encode(self.1), representation);
+        // if-none-match uses weak comparison, so a W/ prefix on the client's tag is ignored
+        let cached = req.headers().get("If-None-Match").any(|header| {
+            header
+                .split(',')
+                .map(|tag| tag.trim())
+                .any(|tag| tag == "*" || tag.strip_prefix("W/").unwrap_or(tag) == etag)
+        });
+        if cached {
+            return Response::build()
+                .status(Status::NotModified)
+                .raw_header("ETag", etag)
+                .ok();
+        }
+        res.set_raw_header("ETag", etag);
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_bad_request_mapping() {
+        let errors = vec![
+            GrapevineServerError::Signature(String::from("sig")),
+            GrapevineServerError::UsernameTooLong(String::from("user")),
+            GrapevineServerError::UsernameNotAscii(String::from("user")),
+            GrapevineServerError::UsernameEncodingError(String::from("user")),
+            GrapevineServerError::RelationshipSenderIsTarget,
+            GrapevineServerError::HeaderError(String::from("header")),
+            GrapevineServerError::SerdeError(String::from("request")),
+            GrapevineServerError::DegreeProofVerificationFailed,
+            GrapevineServerError::UploadIncomplete(1),
+            GrapevineServerError::ProofDecompressionFailed(String::from("gzip")),
+            GrapevineServerError::EmptyProof(0),
+            GrapevineServerError::InvalidDegree(0),
+            GrapevineServerError::DegreeMismatch(2),
+            GrapevineServerError::ChainInvalid(String::from("link")),
+        ];
+        for err in errors {
+            let res: GrapevineResponse = err.into();
+            assert!(matches!(res, GrapevineResponse::BadRequest(_)));
+        }
+    }
+
+    #[test]
+    fn test_unauthorized_mapping() {
+        let res: GrapevineResponse = GrapevineServerError::NonceMismatch(1, 0).into();
+        assert!(matches!(res, GrapevineResponse::Unauthorized(_)));
+        let res: GrapevineResponse = GrapevineServerError::InvalidChallenge.into();
+        assert!(matches!(res, GrapevineResponse::Unauthorized(_)));
+        let res: GrapevineResponse = GrapevineServerError::SessionInvalid.into();
+        assert!(matches!(res, GrapevineResponse::Unauthorized(_)));
+    }
+
+    #[test]
+    fn test_forbidden_mapping() {
+        let res: GrapevineResponse =
+            GrapevineServerError::RelationshipNotSender(String::from("user")).into();
+        assert!(matches!(res, GrapevineResponse::Forbidden(_)));
+        let res: GrapevineResponse =
+            GrapevineServerError::ProofNotEntitled(String::from("oid")).into();
+        assert!(matches!(res, GrapevineResponse::Forbidden(_)));
+        let res: GrapevineResponse = GrapevineServerError::SessionNotPermitted.into();
+        assert!(matches!(res, GrapevineResponse::Forbidden(_)));
+        let res: GrapevineResponse =
+            GrapevineServerError::PhraseFrozen(String::from("0x00")).into();
+        assert!(matches!(res, GrapevineResponse::Forbidden(_)));
+        let res: GrapevineResponse =
+            GrapevineServerError::ClaimNotPermitted(String::from("user")).into();
+        assert!(matches!(res, GrapevineResponse::Forbidden(_)));
+        let res: GrapevineResponse =
+            GrapevineServerError::ProfileNotPermitted(String::from("user")).into();
+        assert!(matches!(res, GrapevineResponse::Forbidden(_)));
+        let res: GrapevineResponse =
+            GrapevineServerError::AvailableNotPermitted(String::from("user")).into();
+        assert!(matches!(res, GrapevineResponse::Forbidden(_)));
+    }
+
+    #[test]
+    fn test_not_found_mapping() {
+        let res: GrapevineResponse =
+            GrapevineServerError::UserNotFound(String::from("user")).into();
+        match res {
+            GrapevineResponse::NotFound(msg) => assert_eq!(msg, "Username user does not exist"),
+            _ => panic!("UserNotFound should map to 404"),
+        }
+        let res: GrapevineResponse =
+            GrapevineServerError::RelationshipNotFound(String::from("a"), String::from("b")).into();
+        assert!(matches!(res, GrapevineResponse::NotFound(_)));
+        let res: GrapevineResponse = GrapevineServerError::UserGone(String::from("user")).into();
+        assert_eq!(res.status(), Status::Gone);
+        let res: GrapevineResponse =
+            GrapevineServerError::UploadNotFound(String::from("id")).into();
+        assert!(matches!(res, GrapevineResponse::NotFound(_)));
+        let res: GrapevineResponse =
+            GrapevineServerError::ProofNotFound(String::from("oid")).into();
+        assert!(matches!(res, GrapevineResponse::NotFound(_)));
+        let res: GrapevineResponse =
+            GrapevineServerError::SessionNotFound(String::from("id")).into();
+        assert!(matches!(res, GrapevineResponse::NotFound(_)));
+        let res: GrapevineResponse =
+            GrapevineServerError::PhraseNotFound(String::from("0x00")).into();
+        assert!(matches!(res, GrapevineResponse::NotFound(_)));
+        let res: GrapevineResponse =
+            GrapevineServerError::RouteNotFound(String::from("GET /")).into();
+        assert!(matches!(res, GrapevineResponse::NotFound(_)));
+    }
+
+    #[test]
+    fn test_conflict_mapping() {
+        let errors = vec![
+            GrapevineServerError::UsernameExists(String::from("user")),
+            GrapevineServerError::PubkeyExists(String::from("0x00")),
+            GrapevineServerError::UserExists(String::from("user")),
+            GrapevineServerError::RelationshipExists,
+            GrapevineServerError::PhraseAlreadyOriginated(String::from("0x00")),
+            GrapevineServerError::StaleProof(String::from("oid")),
+            GrapevineServerError::RelationshipConfirmed(String::from("user")),
+            GrapevineServerError::PhraseAlreadyKnown(String::from("0x00")),
+            GrapevineServerError::ProofReplayed(String::from("0x00")),
+        ];
+        for err in errors {
+            let res: GrapevineResponse = err.into();
+            assert!(matches!(res, GrapevineResponse::Conflict(_)));
+        }
+    }
+
+    #[test]
+    fn test_too_large_mapping() {
+        let res: GrapevineResponse = GrapevineServerError::BatchTooLarge(100).into();
+        assert_eq!(res.status(), Status::PayloadTooLarge);
+        let res: GrapevineResponse = GrapevineServerError::UploadTooLarge(100).into();
+        assert_eq!(res.status(), Status::PayloadTooLarge);
+        let res: GrapevineResponse = GrapevineServerError::UploadChunkLimit(256).into();
+        assert_eq!(res.status(), Status::PayloadTooLarge);
+    }
+
+    #[test]
+    fn test_too_many_requests_mapping() {
+        let res: GrapevineResponse = GrapevineServerError::RateLimited(30).into();
+        assert_eq!(res.status(), Status::TooManyRequests);
+        let res: GrapevineResponse = GrapevineServerError::UploadLimitReached(4).into();
+        assert_eq!(res.status(), Status::TooManyRequests);
+    }
+
+    #[test]
+    fn test_service_unavailable_mapping() {
+        let res: GrapevineResponse = GrapevineServerError::VerificationBusy(100).into();
+        assert_eq!(res.status(), Status::ServiceUnavailable);
+    }
+
+    #[test]
+    fn test_gateway_timeout_mapping() {
+        let res: GrapevineResponse = GrapevineServerError::VerificationTimeout(100).into();
+        assert_eq!(res.status(), Status::GatewayTimeout);
+    }
+
+    #[test]
+    fn test_internal_error_mapping() {
+        let errors = vec![
+            GrapevineServerError::MongoError(String::from("db")),
+            GrapevineServerError::InternalError,
+        ];
+        for err in errors {
+            let res: GrapevineResponse = err.into();
+            assert!(matches!(res, GrapevineResponse::InternalError(_)));
+        }
+    }
+
+    #[test]
+    fn test_circuit_error_mapping() {
+        let errors = vec![
+            (
+                GrapevineCircuitError::Verification(String::from("verify")),
+                Status::BadRequest,
+            ),
+            (
+                GrapevineCircuitError::Decompression(String::from("gzip")),
+                Status::BadRequest,
+            ),
+            (
+                GrapevineCircuitError::Serialization(String::from("json")),
+                Status::BadRequest,
+            ),
+            (
+                GrapevineCircuitError::ProofGeneration(String::from("fold")),
+                Status::InternalServerError,
+            ),
+            (
+                GrapevineCircuitError::WitnessGeneration(String::from("witness")),
+                Status::InternalServerError,
+            ),
+            (
+                GrapevineCircuitError::Artifact(String::from("r1cs")),
+                Status::InternalServerError,
+            ),
+            (
+                GrapevineCircuitError::PhraseTooLong(200),
+                Status::InternalServerError,
+            ),
+        ];
+        for (err, status) in errors {
+            let res: GrapevineResponse = err.into();
+            assert_eq!(res.status(), status);
+        }
+    }
+}
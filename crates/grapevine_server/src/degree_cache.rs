@@ -0,0 +1,124 @@
+use crate::mongo::GrapevineDB;
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/**
+ * How often every user's available degrees are recomputed in the background
+ * @dev writes handled by this server invalidate affected entries immediately, so this only bounds
+ *      staleness from writes made outside of this process (i.e. another server instance)
+ */
+pub const REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+lazy_static! {
+    /** Materialized view of find_available_degrees for each database */
+    static ref AVAILABLE_DEGREES: Mutex<HashMap<String, Materialized>> =
+        Mutex::new(HashMap::new());
+}
+
+#[derive(Default)]
+struct Materialized {
+    // bumped by every invalidation so views computed from reads made before it are not stored
+    generation: u64,
+    degrees: HashMap<String, Vec<String>>,
+}
+
+/**
+ * Get the materialized available degrees for a user, computing them if not present
+ * @dev the computed view is only stored if nothing was invalidated while it was computed
+ *
+ * @param db - the database to compute available degrees from on a miss
+ * @param username - the username to get available degrees for
+ * @returns - the stringified OIDs of the proofs the user can build from
+ */
+pub async fn get_available_degrees(db: &GrapevineDB, username: &String) -> Vec<String> {
+    let (cached, generation) = {
+        let mut views = AVAILABLE_DEGREES.lock().unwrap();
+        let view = views.entry(db.name().to_string()).or_default();
+        (view.degrees.get(username).cloned(), view.generation)
+    };
+    match cached {
+        Some(degrees) => degrees,
+        None => {
+            let degrees = db.find_available_degrees(username.clone()).await;
+            let mut views = AVAILABLE_DEGREES.lock().unwrap();
+            let view = views.entry(db.name().to_string()).or_default();
+            if view.generation == generation {
+                view.degrees.insert(username.clone(), degrees.clone());
+            }
+            degrees
+        }
+    }
+}
+
+/**
+ * Drop a user's materialized available degrees (i.e. after they receive a new relationship)
+ *
+ * @param db - the database the user belongs to
+ * @param username - the username whose entry is no longer valid
+ */
+pub fn invalidate(db: &GrapevineDB, username: &String) {
+    let mut views = AVAILABLE_DEGREES.lock().unwrap();
+    let view = views.entry(db.name().to_string()).or_default();
+    view.generation += 1;
+    view.degrees.remove(username);
+}
+
+/**
+ * Drop every user's materialized available degrees
+ * @dev used when a proof is added, as it can change the available degrees of any connection
+ *
+ * @param db - the database whose view is no longer valid
+ */
+pub fn invalidate_all(db: &GrapevineDB) {
+    let mut views = AVAILABLE_DEGREES.lock().unwrap();
+    let view = views.entry(db.name().to_string()).or_default();
+    view.generation += 1;
+    view.degrees.clear();
+}
+
+/**
+ * Recompute the available degrees for every user in the database
+ * @dev the recomputed view is dropped if anything was invalidated while it was computed, since
+ *      it may predate the write. The next miss or refresh recomputes it
+ *
+ * @param db - the database to compute available degrees from
+ */
+pub async fn refresh(db: &GrapevineDB) {
+    let generation = AVAILABLE_DEGREES
+        .lock()
+        .unwrap()
+        .entry(db.name().to_string())
+        .or_default()
+        .generation;
+    let usernames = match db.get_all_usernames().await {
+        Ok(usernames) => usernames,
+        Err(e) => {
+            println!("Error refreshing available degrees: {:?}", e);
+            return;
+        }
+    };
+    let mut degrees: HashMap<String, Vec<String>> = HashMap::new();
+    for username in usernames {
+        let available = db.find_available_degrees(username.clone()).await;
+        degrees.insert(username, available);
+    }
+    let mut views = AVAILABLE_DEGREES.lock().unwrap();
+    let view = views.entry(db.name().to_string()).or_default();
+    if view.generation == generation {
+        view.degrees = degrees;
+    }
+}
+
+/**
+ * Periodically rematerialize every user's available degrees every REFRESH_INTERVAL
+ */
+pub async fn refresh_task() {
+    let db = GrapevineDB::init().await;
+    let mut interval = tokio::time::interval(REFRESH_INTERVAL);
+    loop {
+        interval.tick().await;
+        refresh(&db).await;
+    }
+}
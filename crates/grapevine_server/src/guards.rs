@@ -1,25 +1,69 @@
 use crate::catchers::ErrorMessage;
 use grapevine_common::errors::GrapevineServerError;
 use crate::mongo::GrapevineDB;
+use crate::nonce_cache;
+use crate::registration::RegistrationConfig;
+use crate::session::SessionConfig;
+use crate::utils::{RATE_LIMIT_REQUESTS, RATE_LIMIT_WINDOW};
 use babyjubjub_rs::{decompress_point, decompress_signature, verify};
 use grapevine_common::crypto::nonce_hash;
+use grapevine_common::http::{AUTHORIZATION_HEADER, USERNAME_HEADER};
+use grapevine_common::nonce::Nonce;
+use hmac::{Hmac, Mac};
+use lazy_static::lazy_static;
 use num_bigint::{BigInt, Sign};
 use rocket::{
+    fairing::{Fairing, Info, Kind},
     http::Status,
     outcome::Outcome::{Error as Failure, Success},
     request::{FromRequest, Outcome, Request},
-    State,
+    Response, State,
 };
+use sha3::Sha3_256;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Instant;
 
-/** A username passed through header that passes the signed nonce check */
+lazy_static! {
+    /** Start of the current window and the number of requests in it per client and route */
+    static ref RATE_LIMITS: Mutex<HashMap<String, (Instant, u32)>> = Mutex::new(HashMap::new());
+}
+
+/**
+ * A username passed through header that passes the signed nonce check or holds an active session
+ * @dev the second field is the id of the session if the request was authenticated by X-Session
+ */
 #[derive(Debug, Clone)]
-pub struct AuthenticatedUser(pub String);
+pub struct AuthenticatedUser(pub String, pub Option<String>);
+
+/** The nonce the server expects next from the user authenticated on a request */
+struct NextNonce(Option<Nonce>);
+
+/** Fairing that tells clients their next expected nonce on responses to authenticated requests */
+pub struct NextNonceHeader;
 
 #[rocket::async_trait]
 impl<'r> FromRequest<'r> for AuthenticatedUser {
     type Error = ErrorMessage;
 
     async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let outcome = AuthenticatedUser::authenticate(request).await;
+        // rocket only passes the status of a failed guard to catchers, so keep the error for them
+        if let Failure((_, error)) = &outcome {
+            request.local_cache(|| error.clone());
+        }
+        outcome
+    }
+}
+
+impl AuthenticatedUser {
+    /**
+     * Authenticate a request by its session token or its signature over the user's nonce
+     *
+     * @param request - the request to authenticate
+     * @returns - the authenticated user, or the status and error to reject the request with
+     */
+    async fn authenticate(request: &Request<'_>) -> Outcome<Self, ErrorMessage> {
         // Connect to mongodb
         let mongo = match request.guard::<&State<GrapevineDB>>().await {
             Success(db) => db,
@@ -36,7 +80,7 @@ impl<'r> FromRequest<'r> for AuthenticatedUser {
             }
         };
         // Check for X-Username header
-        let username = match request.headers().get_one("X-Username") {
+        let username = match request.headers().get_one(USERNAME_HEADER) {
             Some(username) => String::from(username),
             None => {
                 return Failure((
@@ -50,11 +94,63 @@ impl<'r> FromRequest<'r> for AuthenticatedUser {
                 ));
             }
         };
+        // A session token authenticates the request in place of a signed nonce
+        if let Some(token) = request.headers().get_one("X-Session") {
+            let config = match request.guard::<&State<SessionConfig>>().await {
+                Success(config) => config,
+                _ => {
+                    return Failure((
+                        Status::InternalServerError,
+                        ErrorMessage(Some(GrapevineServerError::InternalError), None),
+                    ))
+                }
+            };
+            // the token must be issued to this username and not revoked or expired
+            let session = match config.verify(token) {
+                Some((subject, session)) if subject == username => session,
+                _ => {
+                    return Failure((
+                        Status::Unauthorized,
+                        ErrorMessage(Some(GrapevineServerError::SessionInvalid), None),
+                    ))
+                }
+            };
+            return match mongo.session_active(&username, &session).await {
+                Ok(true) => {
+                    mongo.touch_last_active(&username);
+                    let user = AuthenticatedUser(username, Some(session));
+                    request.local_cache(|| Some(user.clone()));
+                    Success(user)
+                }
+                Ok(false) => Failure((
+                    Status::Unauthorized,
+                    ErrorMessage(Some(GrapevineServerError::SessionInvalid), None),
+                )),
+                Err(e) => Failure((Status::InternalServerError, ErrorMessage(Some(e), None))),
+            };
+        }
         // Check for X-Authorization header (signature over nonce)
-        let signature = match request.headers().get_one("X-Authorization") {
+        let signature = match request.headers().get_one(AUTHORIZATION_HEADER) {
             Some(data) => {
                 // attempt to parse the signature
-                let bytes: [u8; 64] = hex::decode(data).unwrap().try_into().unwrap();
+                let bytes: Option<[u8; 64]> = match hex::decode(data) {
+                    Ok(bytes) => bytes.try_into().ok(),
+                    Err(_) => None,
+                };
+                let bytes = match bytes {
+                    Some(bytes) => bytes,
+                    None => {
+                        return Failure((
+                            Status::BadRequest,
+                            ErrorMessage(
+                                Some(GrapevineServerError::HeaderError(String::from(
+                                    "Malformed authorization header",
+                                ))),
+                                None,
+                            ),
+                        ));
+                    }
+                };
                 match decompress_signature(&bytes) {
                     Ok(signature) => signature,
                     Err(_) => {
@@ -82,38 +178,80 @@ impl<'r> FromRequest<'r> for AuthenticatedUser {
                 ));
             }
         };
-        // Retrieve nonce from database
-        let (nonce, pubkey) = match mongo.get_nonce(&username).await {
+        // Retrieve nonce from the cache, falling back to the database
+        // @dev deleted and deactivated users are rejected here so routes can rely on the user existing
+        // @dev usernames that never registered are rejected as not found instead
+        let (nonce, pubkey) = match nonce_cache::get_nonce(mongo, &username).await {
             Some(data) => data,
             None => {
-                return Failure((
-                    Status::NotFound,
-                    ErrorMessage(Some(GrapevineServerError::UserNotFound(username)), None),
-                ));
+                return match mongo.user_gone(&username).await {
+                    Ok(true) => Failure((
+                        Status::Gone,
+                        ErrorMessage(Some(GrapevineServerError::UserGone(username)), None),
+                    )),
+                    Ok(false) => Failure((
+                        Status::NotFound,
+                        ErrorMessage(Some(GrapevineServerError::UserNotFound(username)), None),
+                    )),
+                    Err(e) => Failure((Status::InternalServerError, ErrorMessage(Some(e), None))),
+                };
             }
         };
         // convert pubkey to bjj point (assumes won't fail due to other checks)
         let pubkey = decompress_point(pubkey).unwrap();
         // Take the sha256 hash of the nonce and username, and convert to bjj message format
-        let message = BigInt::from_bytes_le(Sign::Plus, &nonce_hash(&username, nonce));
+        // @dev usernames that bypassed registration checks may not encode, so reject them
+        let hash = match nonce_hash(&username, nonce) {
+            Ok(hash) => hash,
+            Err(e) => return Failure((Status::BadRequest, ErrorMessage(Some(e), None))),
+        };
+        let message = BigInt::from_bytes_le(Sign::Plus, &hash);
         // Check that signature matches expected nonce/ username hash
         match verify(pubkey, signature, message) {
             true => (),
             false => {
+                // the cached nonce may be behind the database, so reread it on the next attempt
+                nonce_cache::invalidate(mongo, &username);
                 return Failure((
                     Status::Unauthorized,
                     ErrorMessage(
                         Some(GrapevineServerError::Signature(String::from(
                             "Failed to verify nonce signature",
                         ))),
-                        Some(nonce),
+                        Some(nonce.into()),
                     ),
                 ));
             }
         };
-        // Increment nonce in database
-        match mongo.increment_nonce(&username).await {
-            Ok(_) => Success(AuthenticatedUser(username)),
+        // a nonce that cannot be incremented can never be consumed
+        if let Err(e) = nonce.increment() {
+            return Failure((
+                Status::BadRequest,
+                ErrorMessage(Some(GrapevineServerError::HeaderError(e.to_string())), None),
+            ));
+        };
+        // Increment nonce in database (and the cache if the nonce was still unused)
+        match nonce_cache::increment_nonce(mongo, &username, nonce).await {
+            // the nonce as stored is the one the client must sign next
+            Ok(Some(next_nonce)) => {
+                // record activity in the background so it adds no latency to the request
+                mongo.touch_last_active(&username);
+                // expose the incremented nonce so clients can resync from the response
+                request.local_cache(|| NextNonce(Some(next_nonce)));
+                // remember who authenticated so responses can be attributed in logs
+                request.local_cache(|| Some(AuthenticatedUser(username.clone(), None)));
+                Success(AuthenticatedUser(username, None))
+            }
+            // another request consumed the nonce between the lookup and the increment
+            Ok(None) => Failure((
+                Status::Unauthorized,
+                ErrorMessage(
+                    Some(GrapevineServerError::Signature(String::from(
+                        "Nonce has already been used",
+                    ))),
+                    None,
+                ),
+            )),
             Err(_) => Failure((
                 Status::InternalServerError,
                 ErrorMessage(
@@ -126,3 +264,150 @@ impl<'r> FromRequest<'r> for AuthenticatedUser {
         }
     }
 }
+
+/**
+ * Get the username authenticated by the AuthenticatedUser guard on a request
+ *
+ * @param request - the request to check
+ * @returns - the authenticated username, or None if the request was not authenticated
+ */
+pub fn authenticated_username<'r>(request: &'r Request<'_>) -> Option<&'r str> {
+    request
+        .local_cache(|| None::<AuthenticatedUser>)
+        .as_ref()
+        .map(|user| user.0.as_str())
+}
+
+#[rocket::async_trait]
+impl Fairing for NextNonceHeader {
+    fn info(&self) -> Info {
+        Info {
+            name: "X-Grapevine-Next-Nonce header",
+            kind: Kind::Response,
+        }
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        // only requests that passed the AuthenticatedUser guard have a next nonce
+        if let NextNonce(Some(nonce)) = request.local_cache(|| NextNonce(None)) {
+            response.set_raw_header("X-Grapevine-Next-Nonce", nonce.to_string());
+        }
+    }
+}
+
+/**
+ * A request carrying the server's admin key in the X-Admin-Key header
+ * @dev fails with 404 rather than 401 if no admin key is configured, so a disabled admin api
+ *      is indistinguishable from an unknown route
+ */
+#[derive(Debug, Clone)]
+pub struct AdminGuard;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for AdminGuard {
+    type Error = ErrorMessage;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let config = match request.guard::<&State<RegistrationConfig>>().await {
+            Success(config) => config,
+            _ => {
+                return Failure((
+                    Status::InternalServerError,
+                    ErrorMessage(Some(GrapevineServerError::InternalError), None),
+                ))
+            }
+        };
+        // admin routes are disabled entirely if no admin key is configured
+        let expected = match &config.admin_key {
+            Some(expected) => expected,
+            None => return Failure((Status::NotFound, ErrorMessage(None, None))),
+        };
+        match request.headers().get_one("X-Admin-Key") {
+            Some(provided) if admin_key_matches(provided, expected) => Success(AdminGuard),
+            _ => Failure((
+                Status::Unauthorized,
+                ErrorMessage(Some(GrapevineServerError::AdminUnauthorized), None),
+            )),
+        }
+    }
+}
+
+/**
+ * Compare a provided admin key to the configured one without leaking how much of it matched
+ * @dev both keys are hmac'd under the configured key, so the tags have a fixed length and are
+ *      compared in constant time by verify_slice
+ *
+ * @param provided - the key sent in the X-Admin-Key header
+ * @param expected - the configured admin key
+ * @returns - true if the keys are equal
+ */
+fn admin_key_matches(provided: &str, expected: &str) -> bool {
+    let tag = |key: &str| {
+        let mut mac = Hmac::<Sha3_256>::new_from_slice(expected.as_bytes()).unwrap();
+        mac.update(key.as_bytes());
+        mac
+    };
+    let expected_tag = tag(expected).finalize().into_bytes();
+    tag(provided).verify_slice(&expected_tag).is_ok()
+}
+
+/**
+ * A request from a client that has not exceeded RATE_LIMIT_REQUESTS to this route within the
+ * current RATE_LIMIT_WINDOW
+ * @dev clients are identified by ip address, so this does not consume a nonce
+ * @dev requests are counted per route rather than per path, so varying a dynamic segment (i.e.
+ *      the pubkey being looked up) does not reset the limit
+ * @dev ended windows are dropped by prune_task rather than on each request
+ */
+#[derive(Debug, Clone)]
+pub struct RateLimited;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for RateLimited {
+    type Error = ErrorMessage;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let client = match request.client_ip() {
+            Some(ip) => ip.to_string(),
+            None => String::from("unknown"),
+        };
+        let route = match request.route() {
+            Some(route) => route.uri.to_string(),
+            None => request.uri().path().to_string(),
+        };
+        let key = format!("{}:{}", client, route);
+        let mut limits = RATE_LIMITS.lock().unwrap();
+        let (start, count) = limits.entry(key).or_insert((Instant::now(), 0));
+        // start a new window for this client once its last one has ended
+        if start.elapsed() >= RATE_LIMIT_WINDOW {
+            *start = Instant::now();
+            *count = 0;
+        }
+        *count += 1;
+        match *count > RATE_LIMIT_REQUESTS {
+            false => Success(RateLimited),
+            true => {
+                let retry = RATE_LIMIT_WINDOW.saturating_sub(start.elapsed()).as_secs();
+                Failure((
+                    Status::TooManyRequests,
+                    ErrorMessage(Some(GrapevineServerError::RateLimited(retry)), None),
+                ))
+            }
+        }
+    }
+}
+
+/**
+ * Drop every rate limit window that has ended every RATE_LIMIT_WINDOW, so the map does not grow
+ * with every client seen
+ */
+pub async fn prune_task() {
+    let mut interval = tokio::time::interval(RATE_LIMIT_WINDOW);
+    loop {
+        interval.tick().await;
+        RATE_LIMITS
+            .lock()
+            .unwrap()
+            .retain(|_, (start, _)| start.elapsed() < RATE_LIMIT_WINDOW);
+    }
+}
@@ -0,0 +1,175 @@
+use crate::guards::authenticated_username;
+use rocket::{
+    fairing::{Fairing, Info, Kind},
+    Request, Response,
+};
+use tracing::Subscriber;
+use tracing_subscriber::{fmt::MakeWriter, util::SubscriberInitExt, EnvFilter};
+
+/** The level logged at if GRAPEVINE_LOG_LEVEL is unset or invalid */
+pub const DEFAULT_LOG_LEVEL: &str = "info";
+
+/** How log lines are written */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LogFormat {
+    // human readable multi-line output
+    Pretty,
+    // one json object per line for log aggregation
+    Json,
+}
+
+/** Server configuration for the tracing subscriber */
+pub struct LogConfig {
+    pub format: LogFormat,
+    // an EnvFilter directive (i.e. "debug" or "grapevine_server=debug,info")
+    pub level: String,
+}
+
+/** Fairing that logs the route, authenticated user, and status of every response */
+pub struct RequestLogger;
+
+impl LogConfig {
+    /**
+     * Read the logging config from the environment
+     *
+     * @returns - the config from GRAPEVINE_LOG_FORMAT (json or pretty, default pretty) and
+     *            GRAPEVINE_LOG_LEVEL (default info)
+     */
+    pub fn from_env() -> Self {
+        let format = match std::env::var("GRAPEVINE_LOG_FORMAT") {
+            Ok(format) if format.eq_ignore_ascii_case("json") => LogFormat::Json,
+            _ => LogFormat::Pretty,
+        };
+        let level = std::env::var("GRAPEVINE_LOG_LEVEL")
+            .unwrap_or_else(|_| String::from(DEFAULT_LOG_LEVEL));
+        LogConfig { format, level }
+    }
+
+    /**
+     * Build the level filter, falling back to DEFAULT_LOG_LEVEL if the level does not parse
+     */
+    fn filter(&self) -> EnvFilter {
+        EnvFilter::try_new(&self.level).unwrap_or_else(|_| EnvFilter::new(DEFAULT_LOG_LEVEL))
+    }
+
+    /**
+     * Install the configured subscriber as the global default, writing to stdout
+     */
+    pub fn init(&self) {
+        match self.format {
+            LogFormat::Json => json_subscriber(self.filter(), std::io::stdout).init(),
+            LogFormat::Pretty => tracing_subscriber::fmt()
+                .pretty()
+                .with_env_filter(self.filter())
+                .init(),
+        }
+    }
+}
+
+/**
+ * Build a subscriber that writes each event as a single line of json
+ *
+ * @param filter - the levels to log
+ * @param writer - where log lines are written
+ * @returns - the json subscriber
+ */
+fn json_subscriber<W>(filter: EnvFilter, writer: W) -> impl Subscriber + Send + Sync
+where
+    W: for<'a> MakeWriter<'a> + Send + Sync + 'static,
+{
+    tracing_subscriber::fmt()
+        .json()
+        .with_env_filter(filter)
+        .with_writer(writer)
+        .finish()
+}
+
+#[rocket::async_trait]
+impl Fairing for RequestLogger {
+    fn info(&self) -> Info {
+        Info {
+            name: "Request logger",
+            kind: Kind::Response,
+        }
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        // log the mounted route rather than the raw path so requests are grouped by endpoint
+        let route = match request.route() {
+            Some(route) => route.uri.to_string(),
+            None => request.uri().path().to_string(),
+        };
+        tracing::info!(
+            method = %request.method(),
+            route = %route,
+            username = authenticated_username(request).unwrap_or_default(),
+            status = response.status().code,
+            "request handled"
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    /** Captures log lines in memory */
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl<'a> MakeWriter<'a> for Captured {
+        type Writer = Captured;
+
+        fn make_writer(&'a self) -> Self::Writer {
+            self.clone()
+        }
+    }
+
+    #[test]
+    fn test_json_lines() {
+        let captured = Captured::default();
+        let subscriber = json_subscriber(EnvFilter::new("info"), captured.clone());
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!(
+                route = "/user/degrees",
+                username = "user",
+                status = 200,
+                "handled"
+            );
+            tracing::warn!("second line");
+            tracing::debug!("filtered out");
+        });
+        let output = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<serde_json::Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["level"], "INFO");
+        assert_eq!(lines[0]["fields"]["route"], "/user/degrees");
+        assert_eq!(lines[0]["fields"]["username"], "user");
+        assert_eq!(lines[0]["fields"]["status"], 200);
+        assert_eq!(lines[1]["fields"]["message"], "second line");
+    }
+
+    #[test]
+    fn test_invalid_level_falls_back() {
+        let config = LogConfig {
+            format: LogFormat::Json,
+            level: String::from("grapevine_server=verbose"),
+        };
+        assert_eq!(config.filter().to_string(), DEFAULT_LOG_LEVEL);
+    }
+}
@@ -1,36 +1,144 @@
+use crate::nonce_cache;
+use crate::phrase_key::PhraseKey;
+use crate::utils::LAST_ACTIVE_DEBOUNCE;
 use crate::{DATABASE_NAME, MONGODB_URI};
-use futures::stream::StreamExt;
+use futures::stream::{Stream, StreamExt};
 use grapevine_common::errors::GrapevineServerError;
-use grapevine_common::http::responses::DegreeData;
+use grapevine_common::http::responses::{
+    AuthHashReuse, ChainExport, ChainProof, ConnectionGraph, DegreeData, ExportedProof,
+    ExportedRelationship, ExportedUser, GlobalStats, GraphEdge, PhraseOrigin, PhraseOriginator,
+    ProofChild, ProofMetadata, ReceivedAuthSecret, RelationshipDirection, RelationshipSummary,
+    TopConnector,
+};
 use grapevine_common::models::proof::ProvingData;
+use grapevine_common::nonce::Nonce;
+use grapevine_common::utils::{canonical_pubkey, normalize_username};
+use grapevine_common::models::session::Session;
 use grapevine_common::models::{proof::DegreeProof, relationship::Relationship, user::User};
-use mongodb::bson::{self, doc, oid::ObjectId, Binary};
-use mongodb::options::{ClientOptions, FindOneOptions, FindOptions, ServerApi, ServerApiVersion};
-use mongodb::{Client, Collection};
+use mongodb::bson::{self, doc, oid::ObjectId, Binary, Bson, DateTime, Document};
+use mongodb::error::{ErrorKind, WriteFailure};
+use mongodb::options::{
+    ClientOptions, Collation, CollationStrength, CountOptions, FindOneAndUpdateOptions,
+    FindOneOptions, FindOptions, IndexOptions, ReturnDocument, ServerApi, ServerApiVersion,
+    UpdateOptions,
+};
+use mongodb::{Client, Collection, IndexModel};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/** The name of the case insensitive unique index on usernames */
+const USERNAME_INDEX: &str = "username_case_insensitive";
 
 pub struct GrapevineDB {
+    name: String,
+    // number of times a nonce has been read from the database
+    nonce_reads: AtomicU64,
     users: Collection<User>,
     relationships: Collection<Relationship>,
     degree_proofs: Collection<DegreeProof>,
+    frozen_phrases: Collection<Document>,
+    // usernames of removed users, so requests from them can be told apart from unknown users
+    deleted_users: Collection<Document>,
+    // if some, phrase hashes are stored encrypted behind a blind index
+    phrase_key: Option<PhraseKey>,
 }
 
 impl GrapevineDB {
     pub async fn init() -> Self {
+        Self::init_with_name(&**DATABASE_NAME).await
+    }
+
+    /**
+     * Connect to a database other than the configured DATABASE_NAME
+     *
+     * @param database_name - the name of the database to use
+     */
+    pub async fn init_with_name(database_name: &str) -> Self {
         let mut client_options = ClientOptions::parse(&**MONGODB_URI).await.unwrap();
         let server_api = ServerApi::builder().version(ServerApiVersion::V1).build();
         client_options.server_api = Some(server_api);
         let client = Client::with_options(client_options).unwrap();
-        let db = client.database(&**DATABASE_NAME);
+        let db = client.database(database_name);
         let users = db.collection("users");
         let relationships = db.collection("relationships");
         let degree_proofs = db.collection("degree_proofs");
+        let frozen_phrases = db.collection("frozen_phrases");
+        let deleted_users = db.collection("deleted_users");
+        // at most one relationship can exist in each direction between two users
+        let index = IndexModel::builder()
+            .keys(doc! { "sender": 1, "recipient": 1 })
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+        if let Err(e) = relationships.create_index(index, None).await {
+            println!("Error creating relationship index: {:?}", e);
+        }
+        // a phrase is frozen at most once
+        let index = IndexModel::builder()
+            .keys(doc! { "phrase_hash": 1 })
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+        if let Err(e) = frozen_phrases.create_index(index, None).await {
+            println!("Error creating frozen phrase index: {:?}", e);
+        }
+        // users are looked up by pubkey when added by key rather than username, and a pubkey
+        // belongs to at most one user
+        // @dev deployments with the earlier non-unique index must drop it for this to be created
+        let index = IndexModel::builder()
+            .keys(doc! { "pubkey": 1 })
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+        if let Err(e) = users.create_index(index, None).await {
+            println!("Error creating user pubkey index: {:?}", e);
+        }
+        // usernames are checked for case-variant collisions when users are created, and the
+        // unique index rejects a collision created concurrently with the check
+        let index = IndexModel::builder()
+            .keys(doc! { "username": 1 })
+            .options(
+                IndexOptions::builder()
+                    .name(String::from(USERNAME_INDEX))
+                    .unique(true)
+                    .collation(username_collation())
+                    .build(),
+            )
+            .build();
+        if let Err(e) = users.create_index(index, None).await {
+            println!("Error creating user username index: {:?}", e);
+        }
         Self {
+            name: database_name.to_string(),
+            nonce_reads: AtomicU64::new(0),
             users,
             relationships,
             degree_proofs,
+            frozen_phrases,
+            deleted_users,
+            phrase_key: PhraseKey::from_env(),
         }
     }
 
+    /**
+     * Store phrase hashes encrypted under a given key instead of the PHRASE_KEY configured
+     *
+     * @param key - the key to encrypt and blind index phrase hashes with
+     * @returns - the connection using the key
+     */
+    pub fn with_phrase_key(mut self, key: PhraseKey) -> Self {
+        self.phrase_key = Some(key);
+        self
+    }
+
+    /** Return the name of the database this connection uses */
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /** Return the number of times a nonce has been read from the database (not the cache) */
+    #[cfg(test)]
+    pub fn nonce_reads(&self) -> u64 {
+        self.nonce_reads.load(Ordering::Relaxed)
+    }
+
     /**
      * Drops the entire database to start off with clean state for testing
      */
@@ -45,19 +153,182 @@ impl GrapevineDB {
 
     /// USER FUNCTIONS ///
 
-    pub async fn increment_nonce(&self, username: &str) -> Result<(), GrapevineServerError> {
-        let filter = doc! { "username": username };
+    /**
+     * Increment a user's nonce only if it still matches the nonce a request was signed over
+     * @dev a single findOneAndUpdate filtered on the expected nonce, so of any concurrent or
+     *      replayed requests signed over the same nonce exactly one matches the user
+     *
+     * @param username - the username whose nonce is incremented
+     * @param nonce - the nonce the request was signed over
+     * @returns - the incremented nonce as stored, or None if the nonce no longer matches
+     */
+    pub async fn increment_nonce(
+        &self,
+        username: &str,
+        nonce: Nonce,
+    ) -> Result<Option<Nonce>, GrapevineServerError> {
+        let filter = doc! { "username": username, "nonce": u64::from(nonce) as i64 };
         let update = doc! { "$inc": { "nonce": 1 } };
+        let options = FindOneAndUpdateOptions::builder()
+            .projection(doc! { "nonce": 1, "pubkey": 1 })
+            .return_document(ReturnDocument::After)
+            .build();
+        match self.users.find_one_and_update(filter, update, options).await {
+            Ok(user) => Ok(user.and_then(|user| user.nonce)),
+            Err(e) => Err(GrapevineServerError::MongoError(e.to_string())),
+        }
+    }
+
+    /**
+     * Record that a user made an authenticated request without waiting for the write
+     * @dev only writes if last_active is unset or older than LAST_ACTIVE_DEBOUNCE, so repeated
+     *      requests do not each write to the database
+     *
+     * @param username - the username of the authenticated user
+     */
+    pub fn touch_last_active(&self, username: &str) {
+        let now = DateTime::now();
+        let cutoff =
+            DateTime::from_millis(now.timestamp_millis() - LAST_ACTIVE_DEBOUNCE.as_millis() as i64);
+        let filter = doc! {
+            "username": username,
+            "$or": [{ "last_active": { "$lt": cutoff } }, { "last_active": null }]
+        };
+        let update = doc! { "$set": { "last_active": now } };
+        let users = self.users.clone();
+        tokio::spawn(async move {
+            if let Err(e) = users.update_one(filter, update, None).await {
+                println!("Error updating last active: {:?}", e);
+            }
+        });
+    }
+
+    /**
+     * Get the usernames of all users that made an authenticated request since a given time
+     * @dev last_active is debounced, so activity within LAST_ACTIVE_DEBOUNCE of since may be missed
+     *
+     * @param since - the earliest activity to include
+     * @returns - the usernames of the active users
+     */
+    pub async fn get_active_users(
+        &self,
+        since: DateTime,
+    ) -> Result<Vec<String>, GrapevineServerError> {
+        let filter = doc! { "last_active": { "$gte": since } };
+        // TODO: Projection doesn't work without pubkey due to BSON deserialization error
+        let projection = doc! { "username": 1, "pubkey": 1 };
+        let find_options = FindOptions::builder().projection(projection).build();
+        let mut cursor = match self.users.find(filter, Some(find_options)).await {
+            Ok(cursor) => cursor,
+            Err(e) => return Err(GrapevineServerError::MongoError(e.to_string())),
+        };
+        let mut usernames: Vec<String> = vec![];
+        while let Some(result) = cursor.next().await {
+            match result {
+                Ok(user) => usernames.push(user.username.unwrap()),
+                Err(e) => return Err(GrapevineServerError::MongoError(e.to_string())),
+            }
+        }
+        Ok(usernames)
+    }
+
+    /**
+     * Store a newly issued session on a user, dropping any of their sessions that have expired
+     *
+     * @param username - the username the session was issued to
+     * @param session - the session to store
+     * @returns - nothing if the session was stored, or an error
+     */
+    pub async fn add_session(
+        &self,
+        username: &str,
+        session: &Session,
+    ) -> Result<(), GrapevineServerError> {
+        let filter = doc! { "username": username };
+        let prune = doc! { "$pull": { "sessions": { "expires_at": { "$lte": DateTime::now() } } } };
+        if let Err(e) = self.users.update_one(filter.clone(), prune, None).await {
+            return Err(GrapevineServerError::MongoError(e.to_string()));
+        }
+        let session = bson::to_bson(session).unwrap();
+        let update = doc! { "$push": { "sessions": session } };
         match self.users.update_one(filter, update, None).await {
             Ok(_) => Ok(()),
             Err(e) => Err(GrapevineServerError::MongoError(e.to_string())),
         }
     }
 
-    pub async fn get_nonce(&self, username: &str) -> Option<(u64, [u8; 32])> {
-        // Verify user existence
+    /**
+     * Get the sessions of a user that have not yet expired
+     *
+     * @param username - the username whose sessions are returned
+     * @returns - the unexpired sessions, oldest first
+     */
+    pub async fn get_sessions(&self, username: &str) -> Result<Vec<Session>, GrapevineServerError> {
         let filter = doc! { "username": username };
         // TODO: Projection doesn't work without pubkey due to BSON deserialization error
+        let projection = doc! { "sessions": 1, "pubkey": 1 };
+        let find_options = FindOneOptions::builder().projection(projection).build();
+        let user = match self.users.find_one(filter, Some(find_options)).await {
+            Ok(user) => user,
+            Err(e) => return Err(GrapevineServerError::MongoError(e.to_string())),
+        };
+        let now = DateTime::now();
+        Ok(user
+            .and_then(|user| user.sessions)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|session| session.expires_at > now)
+            .collect())
+    }
+
+    /**
+     * Revoke one of a user's sessions so its token no longer authenticates requests
+     *
+     * @param username - the username whose session is revoked
+     * @param id - the id of the session to revoke
+     * @returns - true if the session was found and removed
+     */
+    pub async fn remove_session(
+        &self,
+        username: &str,
+        id: &str,
+    ) -> Result<bool, GrapevineServerError> {
+        let filter = doc! { "username": username, "sessions.id": id };
+        let update = doc! { "$pull": { "sessions": { "id": id } } };
+        match self.users.update_one(filter, update, None).await {
+            Ok(res) => Ok(res.modified_count == 1),
+            Err(e) => Err(GrapevineServerError::MongoError(e.to_string())),
+        }
+    }
+
+    /**
+     * Check that a session is still stored on an active user and has not expired
+     *
+     * @param username - the username the session token was issued to
+     * @param id - the id of the session
+     * @returns - true if the session can authenticate requests
+     */
+    pub async fn session_active(
+        &self,
+        username: &str,
+        id: &str,
+    ) -> Result<bool, GrapevineServerError> {
+        let filter = doc! {
+            "username": username,
+            "deactivated": { "$ne": true },
+            "sessions": { "$elemMatch": { "id": id, "expires_at": { "$gt": DateTime::now() } } }
+        };
+        match self.users.count_documents(filter, None).await {
+            Ok(count) => Ok(count > 0),
+            Err(e) => Err(GrapevineServerError::MongoError(e.to_string())),
+        }
+    }
+
+    pub async fn get_nonce(&self, username: &str) -> Option<(Nonce, [u8; 32])> {
+        self.nonce_reads.fetch_add(1, Ordering::Relaxed);
+        // Verify user existence (deactivated users are treated as nonexistent)
+        let filter = doc! { "username": username, "deactivated": { "$ne": true } };
+        // TODO: Projection doesn't work without pubkey due to BSON deserialization error
         let projection = doc! { "nonce": 1, "pubkey": 1 };
         let find_options = FindOneOptions::builder().projection(projection).build();
         let user = self
@@ -71,9 +342,32 @@ impl GrapevineDB {
         }
     }
 
+    /**
+     * Check whether a username belonged to a user that has since been deleted or deactivated
+     * @dev get_nonce treats both as nonexistent, so this tells them apart from unknown usernames
+     *
+     * @param username - the username to check
+     * @returns - true if the user is deactivated or was removed
+     */
+    pub async fn user_gone(&self, username: &str) -> Result<bool, GrapevineServerError> {
+        let filter = doc! { "username": username, "deactivated": true };
+        match self.users.count_documents(filter, None).await {
+            Ok(0) => (),
+            Ok(_) => return Ok(true),
+            Err(e) => return Err(GrapevineServerError::MongoError(e.to_string())),
+        };
+        let filter = doc! { "username": username };
+        match self.deleted_users.count_documents(filter, None).await {
+            Ok(count) => Ok(count > 0),
+            Err(e) => Err(GrapevineServerError::MongoError(e.to_string())),
+        }
+    }
+
     /**
      * Queries the DB for documents where username OR pubkey matches an existing document
      * @dev used in user creation. If true, then fail to create the user
+     * @notice - usernames collide if their normalized forms match (e.g. "Alice" and "alice"), and
+     *           pubkeys collide if they encode the same point
      *
      * @param username - the username to check for existence
      * @param pubkey - the pubkey to check
@@ -85,29 +379,39 @@ impl GrapevineDB {
         pubkey: &[u8; 32],
     ) -> Result<[bool; 2], GrapevineServerError> {
         // Verify user existence
-        let pubkey_binary = Binary {
-            subtype: bson::spec::BinarySubtype::Generic,
-            bytes: pubkey.to_vec(),
-        };
+        let normalized = normalize_username(username);
+        let canonical = canonical_pubkey(pubkey).unwrap_or(*pubkey);
+        let pubkeys: Vec<Binary> = vec![*pubkey, canonical]
+            .into_iter()
+            .map(|pubkey| Binary {
+                subtype: bson::spec::BinarySubtype::Generic,
+                bytes: pubkey.to_vec(),
+            })
+            .collect();
         let query = doc! {
             "$or": [
                 { "username": username },
-                { "pubkey": pubkey_binary }
+                { "pubkey": { "$in": pubkeys } }
             ]
         };
         let projection = doc! { "username": 1, "pubkey": 1 };
-        let find_options = FindOptions::builder().projection(projection).build();
+        // match usernames case insensitively with the collation of the username index
+        let find_options = FindOptions::builder()
+            .projection(projection)
+            .collation(username_collation())
+            .build();
         let mut cursor = self.users.find(query, Some(find_options)).await.unwrap();
         let mut found = [false; 2];
         while let Some(result) = cursor.next().await {
             match result {
                 Ok(user) => {
-                    // Check if the username matches
-                    if &user.username.unwrap() == username {
+                    // Check if the normalized username matches
+                    if normalize_username(&user.username.unwrap()) == normalized {
                         found[0] = true;
                     }
-                    // Check if the pubkey matches
-                    if &user.pubkey.unwrap() == pubkey {
+                    // Check if the pubkey encodes the same point
+                    let existing = user.pubkey.unwrap();
+                    if canonical_pubkey(&existing).unwrap_or(existing) == canonical {
                         found[1] = true;
                     }
                 }
@@ -141,8 +445,17 @@ impl GrapevineDB {
         };
 
         // insert the user into the collection
+        // @dev the unique indexes reject a username or pubkey taken since the route's checks
         match self.users.insert_one(&user, None).await {
             Ok(result) => Ok(result.inserted_id.as_object_id().unwrap()),
+            Err(e) if is_duplicate_key(&e) => {
+                if e.to_string().contains(USERNAME_INDEX) {
+                    Err(GrapevineServerError::UsernameExists(user.username.unwrap()))
+                } else {
+                    let pubkey = hex::encode(user.pubkey.unwrap());
+                    Err(GrapevineServerError::PubkeyExists(format!("0x{}", pubkey)))
+                }
+            }
             Err(e) => Err(GrapevineServerError::MongoError(e.to_string())),
         }
     }
@@ -157,6 +470,92 @@ impl GrapevineDB {
             .unwrap()
     }
 
+    /**
+     * Look up the publicly visible fields of a user
+     *
+     * @param username - the username of the user to find
+     * @returns - the user with only username and pubkey, or None if the user does not exist
+     */
+    pub async fn get_public_user(
+        &self,
+        username: &str,
+    ) -> Result<Option<User>, GrapevineServerError> {
+        let filter = doc! { "username": username };
+        let projection = doc! { "_id": 0, "username": 1, "pubkey": 1 };
+        let find_options = FindOneOptions::builder().projection(projection).build();
+        match self.users.find_one(filter, Some(find_options)).await {
+            Ok(user) => Ok(user),
+            Err(e) => Err(GrapevineServerError::MongoError(e.to_string())),
+        }
+    }
+
+    /**
+     * Look up the full document of a user, including relationship and degree proof references
+     *
+     * @param username - the username of the user to find
+     * @returns - the full user document, or None if the user does not exist
+     */
+    pub async fn get_full_user(
+        &self,
+        username: &str,
+    ) -> Result<Option<User>, GrapevineServerError> {
+        let filter = doc! { "username": username };
+        match self.users.find_one(filter, None).await {
+            Ok(user) => Ok(user),
+            Err(e) => Err(GrapevineServerError::MongoError(e.to_string())),
+        }
+    }
+
+    /**
+     * Look up many users by username in a single query
+     *
+     * @param usernames - the usernames of the users to find
+     * @returns - the found users (with only _id, username, and pubkey), in no particular order
+     */
+    pub async fn get_users_by_username(
+        &self,
+        usernames: &Vec<String>,
+    ) -> Result<Vec<User>, GrapevineServerError> {
+        let filter = doc! { "username": { "$in": usernames.clone() } };
+        let projection = doc! { "_id": 1, "username": 1, "pubkey": 1 };
+        let find_options = FindOptions::builder().projection(projection).build();
+        let mut cursor = match self.users.find(filter, Some(find_options)).await {
+            Ok(cursor) => cursor,
+            Err(e) => return Err(GrapevineServerError::MongoError(e.to_string())),
+        };
+        let mut users: Vec<User> = vec![];
+        while let Some(result) = cursor.next().await {
+            match result {
+                Ok(user) => users.push(user),
+                Err(e) => return Err(GrapevineServerError::MongoError(e.to_string())),
+            }
+        }
+        Ok(users)
+    }
+
+    /**
+     * Get the username of every user in the database
+     *
+     * @returns - all registered usernames
+     */
+    pub async fn get_all_usernames(&self) -> Result<Vec<String>, GrapevineServerError> {
+        // TODO: Projection doesn't work without pubkey due to BSON deserialization error
+        let projection = doc! { "username": 1, "pubkey": 1 };
+        let find_options = FindOptions::builder().projection(projection).build();
+        let mut cursor = match self.users.find(doc! {}, Some(find_options)).await {
+            Ok(cursor) => cursor,
+            Err(e) => return Err(GrapevineServerError::MongoError(e.to_string())),
+        };
+        let mut usernames: Vec<String> = vec![];
+        while let Some(result) = cursor.next().await {
+            match result {
+                Ok(user) => usernames.push(user.username.unwrap()),
+                Err(e) => return Err(GrapevineServerError::MongoError(e.to_string())),
+            }
+        }
+        Ok(usernames)
+    }
+
     pub async fn get_pubkey(&self, username: String) -> Option<[u8; 32]> {
         let filter = doc! { "username": username };
         let projection = doc! { "pubkey": 1 };
@@ -176,57 +575,514 @@ impl GrapevineDB {
         }
     }
 
+    /**
+     * Get the username of the user with a given public key
+     * @notice - pubkeys match if they encode the same point, as in check_creation_params
+     *
+     * @param pubkey - the compressed public key of the user
+     * @returns - the username of the user with a matching pubkey, or None if not found
+     */
+    pub async fn get_username_by_pubkey(
+        &self,
+        pubkey: &[u8; 32],
+    ) -> Result<Option<String>, GrapevineServerError> {
+        let canonical = canonical_pubkey(pubkey).unwrap_or(*pubkey);
+        let pubkeys: Vec<Binary> = vec![*pubkey, canonical]
+            .into_iter()
+            .map(|pubkey| Binary {
+                subtype: bson::spec::BinarySubtype::Generic,
+                bytes: pubkey.to_vec(),
+            })
+            .collect();
+        let filter = doc! { "pubkey": { "$in": pubkeys } };
+        let projection = doc! { "username": 1 };
+        let find_options = FindOneOptions::builder().projection(projection).build();
+        match self.users.find_one(filter, Some(find_options)).await {
+            Ok(user) => Ok(user.and_then(|user| user.username)),
+            Err(e) => Err(GrapevineServerError::MongoError(e.to_string())),
+        }
+    }
+
+    /**
+     * Add a relationship and reference it from both participants
+     * @notice - the relationship oid is pushed to the relationships array of both the sender and
+     *           the recipient, and delete_relationship pulls it from both
+     *
+     * @param relationship - the relationship document to insert
+     * @param max_relationships - the most relationships the sender can be a participant in
+     * @returns - the oid of the new relationship, or RelationshipLimitReached if the sender already
+     *            has max_relationships relationships
+     */
     pub async fn add_relationship(
         &self,
         relationship: &Relationship,
+        max_relationships: usize,
     ) -> Result<ObjectId, GrapevineServerError> {
-        // @TODO: check to see whether relation already exists between the two users
+        // ensure the sender has not reached the relationship limit
+        let query = doc! { "_id": relationship.sender };
+        let projection = doc! { "relationships": 1 };
+        let find_options = FindOneOptions::builder().projection(projection).build();
+        let count = match self.users.find_one(query, Some(find_options)).await {
+            Ok(Some(user)) => user.relationships.map_or(0, |relationships| relationships.len()),
+            Ok(None) => 0,
+            Err(e) => return Err(GrapevineServerError::MongoError(e.to_string())),
+        };
+        if count >= max_relationships {
+            return Err(GrapevineServerError::RelationshipLimitReached(
+                max_relationships,
+            ));
+        }
 
         // create new relationship document
-        let relationship_oid = self
-            .relationships
-            .insert_one(relationship, None)
-            .await
-            .unwrap()
-            .inserted_id
-            .as_object_id()
-            .unwrap();
+        // @dev the unique (sender, recipient) index rejects duplicates even if requests race
+        let relationship_oid = match self.relationships.insert_one(relationship, None).await {
+            Ok(res) => res.inserted_id.as_object_id().unwrap(),
+            Err(e) if is_duplicate_key(&e) => {
+                return Err(GrapevineServerError::RelationshipExists)
+            }
+            Err(e) => return Err(GrapevineServerError::MongoError(e.to_string())),
+        };
 
-        // push the relationship to the user's list of relationships
-        let query = doc! { "_id": relationship.recipient };
+        // push the relationship to both participants' lists of relationships
+        let query = doc! { "_id": { "$in": [relationship.sender, relationship.recipient] } };
         let update =
             doc! { "$push": { "relationships": bson::to_bson(&relationship_oid).unwrap()} };
-        match self.users.update_one(query, update, None).await {
+        match self.users.update_many(query, update, None).await {
             Ok(_) => Ok(relationship_oid),
             Err(e) => Err(GrapevineServerError::MongoError(e.to_string())),
         }
     }
 
-    pub async fn add_proof(
+    /**
+     * Delete a relationship and remove the reference to it from both participants
+     *
+     * @param relationship - the relationship document to delete
+     */
+    pub async fn delete_relationship(
+        &self,
+        relationship: &Relationship,
+    ) -> Result<(), GrapevineServerError> {
+        let relationship_oid = relationship.id.unwrap();
+        // pull the relationship from both participants' lists of relationships
+        let query = doc! { "_id": { "$in": [relationship.sender, relationship.recipient] } };
+        let update = doc! { "$pull": { "relationships": relationship_oid } };
+        if let Err(e) = self.users.update_many(query, update, None).await {
+            return Err(GrapevineServerError::MongoError(e.to_string()));
+        }
+        // remove the relationship document
+        match self
+            .relationships
+            .delete_one(doc! { "_id": relationship_oid }, None)
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(e) => Err(GrapevineServerError::MongoError(e.to_string())),
+        }
+    }
+
+    /**
+     * Find the raw relationship document from a sender to a recipient
+     * @dev not deserialized as a Relationship so malformed keys or ciphertexts can be inspected
+     *
+     * @param sender - the oid of the user who shared their auth secret
+     * @param recipient - the oid of the user who received the auth secret
+     * @returns - the relationship document if it exists
+     */
+    pub async fn get_relationship_document(
+        &self,
+        sender: &ObjectId,
+        recipient: &ObjectId,
+    ) -> Result<Option<Document>, GrapevineServerError> {
+        let filter = doc! { "sender": sender, "recipient": recipient };
+        let relationships = self.relationships.clone_with_type::<Document>();
+        match relationships.find_one(filter, None).await {
+            Ok(document) => Ok(document),
+            Err(e) => Err(GrapevineServerError::MongoError(e.to_string())),
+        }
+    }
+
+    /**
+     * Find the relationship document from a sender to a recipient
+     *
+     * @param sender - the oid of the user who shared their auth secret
+     * @param recipient - the oid of the user who received the auth secret
+     * @returns - the relationship if it exists
+     */
+    pub async fn get_relationship(
+        &self,
+        sender: &ObjectId,
+        recipient: &ObjectId,
+    ) -> Option<Relationship> {
+        let filter = doc! { "sender": sender, "recipient": recipient };
+        self.relationships.find_one(filter, None).await.unwrap()
+    }
+
+    /**
+     * Check whether a relationship from a sender to a recipient exists
+     *
+     * @param sender - the oid of the user who added the relationship
+     * @param recipient - the oid of the user the relationship was added for
+     * @returns - true if the relationship exists in the given direction
+     */
+    pub async fn relationship_exists(
+        &self,
+        sender: &ObjectId,
+        recipient: &ObjectId,
+    ) -> Result<bool, GrapevineServerError> {
+        let filter = doc! { "sender": sender, "recipient": recipient };
+        match self.relationships.count_documents(filter, None).await {
+            Ok(count) => Ok(count > 0),
+            Err(e) => Err(GrapevineServerError::MongoError(e.to_string())),
+        }
+    }
+
+    /**
+     * Replace the encrypted auth secret of an existing relationship in place
+     * @notice - the relationship oid and created_at timestamp are preserved
+     *
+     * @param relationship - the oid of the relationship to update
+     * @param ephemeral_key - the new ephemeral pubkey used to derive the AES key
+     * @param ciphertext - the new encrypted auth secret
+     * @param nonce - the aes-gcm nonce of the new ciphertext (None if legacy aes-cbc)
+     */
+    pub async fn update_relationship(
+        &self,
+        relationship: &ObjectId,
+        ephemeral_key: &[u8; 32],
+        ciphertext: &[u8; 48],
+        nonce: &Option<[u8; 12]>,
+    ) -> Result<(), GrapevineServerError> {
+        let ephemeral_key_binary = Binary {
+            subtype: bson::spec::BinarySubtype::Generic,
+            bytes: ephemeral_key.to_vec(),
+        };
+        let ciphertext_binary = Binary {
+            subtype: bson::spec::BinarySubtype::Generic,
+            bytes: ciphertext.to_vec(),
+        };
+        let nonce_binary = nonce.map(|nonce| Binary {
+            subtype: bson::spec::BinarySubtype::Generic,
+            bytes: nonce.to_vec(),
+        });
+        let query = doc! { "_id": relationship };
+        let update = doc! {
+            "$set": {
+                "ephemeral_key": ephemeral_key_binary,
+                "ciphertext": ciphertext_binary,
+                "nonce": nonce_binary
+            }
+        };
+        match self.relationships.update_one(query, update, None).await {
+            Ok(_) => Ok(()),
+            Err(e) => Err(GrapevineServerError::MongoError(e.to_string())),
+        }
+    }
+
+    /**
+     * Get the encrypted auth secret of every relationship a user is the recipient of
+     * @dev relationships from senders that no longer exist are skipped
+     *
+     * @param user - the oid of the recipient
+     * @returns - the sender username and encrypted auth secret of each relationship, oldest first
+     */
+    pub async fn get_received_relationships(
         &self,
         user: &ObjectId,
-        proof: &DegreeProof,
-    ) -> Result<ObjectId, GrapevineServerError> {
-        // check if an existing proof in this chain exists for the user
-        // todo: make this not ugly
-        let phrase_hash_bson: Vec<i32> = proof
-            .phrase_hash
-            .unwrap()
-            .to_vec()
+    ) -> Result<Vec<ReceivedAuthSecret>, GrapevineServerError> {
+        let filter = doc! { "recipient": user };
+        let find_options = FindOptions::builder().sort(doc! { "_id": 1 }).build();
+        let mut cursor = match self.relationships.find(filter, Some(find_options)).await {
+            Ok(cursor) => cursor,
+            Err(e) => return Err(GrapevineServerError::MongoError(e.to_string())),
+        };
+        let mut relationships: Vec<Relationship> = vec![];
+        while let Some(result) = cursor.next().await {
+            match result {
+                Ok(relationship) => relationships.push(relationship),
+                Err(e) => return Err(GrapevineServerError::MongoError(e.to_string())),
+            }
+        }
+        // resolve the usernames of the senders
+        let oids: Vec<ObjectId> = relationships
             .iter()
-            .map(|x| *x as i32)
+            .map(|relationship| relationship.sender.unwrap())
             .collect();
+        let filter = doc! { "_id": { "$in": oids } };
+        let projection = doc! { "_id": 1, "username": 1, "pubkey": 1 };
+        let find_options = FindOptions::builder().projection(projection).build();
+        let mut cursor = match self.users.find(filter, Some(find_options)).await {
+            Ok(cursor) => cursor,
+            Err(e) => return Err(GrapevineServerError::MongoError(e.to_string())),
+        };
+        let mut usernames: HashMap<ObjectId, String> = HashMap::new();
+        while let Some(result) = cursor.next().await {
+            match result {
+                Ok(user) => {
+                    usernames.insert(user.id.unwrap(), user.username.unwrap());
+                }
+                Err(e) => return Err(GrapevineServerError::MongoError(e.to_string())),
+            }
+        }
+        Ok(relationships
+            .into_iter()
+            .filter_map(|relationship| {
+                Some(ReceivedAuthSecret {
+                    sender: usernames.get(&relationship.sender.unwrap())?.clone(),
+                    ephemeral_key: relationship.ephemeral_key.unwrap(),
+                    ciphertext: relationship.ciphertext.unwrap(),
+                    nonce: relationship.nonce,
+                })
+            })
+            .collect())
+    }
 
-        let mut proof_chain: Vec<DegreeProof> = vec![];
-        // fetch all proofs preceding this one
-        let mut cursor = self
-            .degree_proofs
+    /**
+     * Get the usernames of users who sent a relationship to a user that was not sent back
+     *
+     * @param user - the oid of the recipient of the relationships
+     * @returns - the usernames of the senders the user has no relationship to, oldest first
+     */
+    pub async fn get_unreciprocated_senders(
+        &self,
+        user: &ObjectId,
+    ) -> Result<Vec<String>, GrapevineServerError> {
+        let pipeline = vec![
+            doc! { "$match": { "recipient": user } },
+            doc! { "$sort": { "_id": 1 } },
+            // look up the relationship sent back to the sender if it exists
+            doc! {
+                "$lookup": {
+                    "from": "relationships",
+                    "let": { "sender": "$sender" },
+                    "pipeline": [
+                        doc! {
+                            "$match": {
+                                "$expr": {
+                                    "$and": [
+                                        { "$eq": ["$sender", user] },
+                                        { "$eq": ["$recipient", "$$sender"] }
+                                    ]
+                                }
+                            }
+                        },
+                        doc! { "$project": { "_id": 1 } }
+                    ],
+                    "as": "reciprocated"
+                }
+            },
+            doc! { "$match": { "reciprocated": { "$size": 0 } } },
+            // resolve the username of the sender
+            doc! {
+                "$lookup": {
+                    "from": "users",
+                    "localField": "sender",
+                    "foreignField": "_id",
+                    "as": "senderUser",
+                    "pipeline": [doc! { "$project": { "username": 1 } }]
+                }
+            },
+            doc! { "$unwind": "$senderUser" },
+            doc! { "$project": { "_id": 0, "username": "$senderUser.username" } },
+        ];
+        let mut cursor = match self.relationships.aggregate(pipeline, None).await {
+            Ok(cursor) => cursor,
+            Err(e) => return Err(GrapevineServerError::MongoError(e.to_string())),
+        };
+        let mut usernames: Vec<String> = vec![];
+        while let Some(result) = cursor.next().await {
+            match result {
+                Ok(document) => match document.get_str("username") {
+                    Ok(username) => usernames.push(username.to_string()),
+                    Err(e) => return Err(GrapevineServerError::MongoError(e.to_string())),
+                },
+                Err(e) => return Err(GrapevineServerError::MongoError(e.to_string())),
+            }
+        }
+        Ok(usernames)
+    }
+
+    /**
+     * Get every relationship a user is the sender or recipient of, labeled with its direction
+     * @dev relationships with users that no longer exist are skipped
+     *
+     * @param user - the oid of the user
+     * @returns - the counterparty, direction, and whether the counterparty has a relationship in
+     *            the other direction for each relationship, oldest first
+     */
+    pub async fn get_relationship_summaries(
+        &self,
+        user: &ObjectId,
+    ) -> Result<Vec<RelationshipSummary>, GrapevineServerError> {
+        let sent = doc! { "$eq": ["$sender", user] };
+        let pipeline = vec![
+            doc! { "$match": { "$or": [{ "sender": user }, { "recipient": user }] } },
+            doc! { "$sort": { "_id": 1 } },
+            doc! {
+                "$project": {
+                    "sent": sent.clone(),
+                    "counterparty": { "$cond": [sent, "$recipient", "$sender"] }
+                }
+            },
+            // resolve the username of the counterparty
+            doc! {
+                "$lookup": {
+                    "from": "users",
+                    "localField": "counterparty",
+                    "foreignField": "_id",
+                    "as": "counterpartyUser",
+                    "pipeline": [doc! { "$project": { "username": 1 } }]
+                }
+            },
+            doc! { "$unwind": "$counterpartyUser" },
+            doc! { "$project": { "_id": 0, "sent": 1, "username": "$counterpartyUser.username" } },
+        ];
+        let mut cursor = match self.relationships.aggregate(pipeline, None).await {
+            Ok(cursor) => cursor,
+            Err(e) => return Err(GrapevineServerError::MongoError(e.to_string())),
+        };
+        let mut relationships: Vec<(String, bool)> = vec![];
+        while let Some(result) = cursor.next().await {
+            let document = match result {
+                Ok(document) => document,
+                Err(e) => return Err(GrapevineServerError::MongoError(e.to_string())),
+            };
+            match (document.get_str("username"), document.get_bool("sent")) {
+                (Ok(username), Ok(sent)) => relationships.push((username.to_string(), sent)),
+                _ => {
+                    return Err(GrapevineServerError::MongoError(String::from(
+                        "Malformed relationship summary",
+                    )))
+                }
+            }
+        }
+        // a relationship is confirmed if the counterparty appears in both directions
+        let summaries = relationships
+            .iter()
+            .map(|(username, sent)| RelationshipSummary {
+                username: username.clone(),
+                direction: match sent {
+                    true => RelationshipDirection::Sent,
+                    false => RelationshipDirection::Received,
+                },
+                confirmed: relationships.contains(&(username.clone(), !sent)),
+            })
+            .collect();
+        Ok(summaries)
+    }
+
+    /**
+     * Find the users reachable from a user through relationships within a given depth
+     * @dev bounded BFS following relationships towards their senders. Each user is visited once at
+     *      their lowest degree, so cycles are never walked again
+     *
+     * @param username - the user at the root of the graph
+     * @param depth - the maximum degree of separation to explore
+     * @returns - the reachable usernames and the edges discovered to reach them
+     */
+    pub async fn get_connection_graph(
+        &self,
+        username: &String,
+        depth: u8,
+    ) -> Result<ConnectionGraph, GrapevineServerError> {
+        let root = match self.get_user(username).await {
+            Some(user) => user.id.unwrap(),
+            None => return Err(GrapevineServerError::UserNotFound(username.clone())),
+        };
+        // track the degree each user was first reached at
+        let mut visited: HashMap<ObjectId, u8> = HashMap::from([(root, 0)]);
+        let mut edges: Vec<(ObjectId, ObjectId, u8)> = vec![];
+        let mut frontier: Vec<ObjectId> = vec![root];
+        for degree in 1..=depth {
+            if frontier.is_empty() {
+                break;
+            }
+            let query = doc! { "recipient": { "$in": frontier.clone() } };
+            let mut cursor = match self.relationships.find(query, None).await {
+                Ok(cursor) => cursor,
+                Err(e) => return Err(GrapevineServerError::MongoError(e.to_string())),
+            };
+            let mut next: Vec<ObjectId> = vec![];
+            while let Some(result) = cursor.next().await {
+                let relationship = match result {
+                    Ok(relationship) => relationship,
+                    Err(e) => return Err(GrapevineServerError::MongoError(e.to_string())),
+                };
+                let (from, to) = (
+                    relationship.recipient.unwrap(),
+                    relationship.sender.unwrap(),
+                );
+                match visited.get(&to) {
+                    // first time reaching this user
+                    None => {
+                        visited.insert(to, degree);
+                        next.push(to);
+                        edges.push((from, to, degree));
+                    }
+                    // another path of the same length to a user in this level
+                    Some(found) if *found == degree => edges.push((from, to, degree)),
+                    // user already reached at a lower degree
+                    Some(_) => (),
+                }
+            }
+            frontier = next;
+        }
+        // resolve the usernames of every visited user
+        let oids: Vec<ObjectId> = visited.keys().cloned().collect();
+        let filter = doc! { "_id": { "$in": oids } };
+        let projection = doc! { "_id": 1, "username": 1, "pubkey": 1 };
+        let find_options = FindOptions::builder().projection(projection).build();
+        let mut cursor = match self.users.find(filter, Some(find_options)).await {
+            Ok(cursor) => cursor,
+            Err(e) => return Err(GrapevineServerError::MongoError(e.to_string())),
+        };
+        let mut usernames: HashMap<ObjectId, String> = HashMap::new();
+        while let Some(result) = cursor.next().await {
+            match result {
+                Ok(user) => {
+                    usernames.insert(user.id.unwrap(), user.username.unwrap());
+                }
+                Err(e) => return Err(GrapevineServerError::MongoError(e.to_string())),
+            }
+        }
+        let mut nodes: Vec<(u8, String)> = visited
+            .iter()
+            .filter(|(oid, _)| **oid != root)
+            .map(|(oid, degree)| (*degree, usernames[oid].clone()))
+            .collect();
+        nodes.sort();
+        Ok(ConnectionGraph {
+            nodes: nodes.into_iter().map(|(_, username)| username).collect(),
+            edges: edges
+                .into_iter()
+                .map(|(from, to, degree)| GraphEdge {
+                    from: usernames[&from].clone(),
+                    to: usernames[&to].clone(),
+                    degree,
+                })
+                .collect(),
+        })
+    }
+
+    pub async fn add_proof(
+        &self,
+        user: &ObjectId,
+        proof: &DegreeProof,
+    ) -> Result<ObjectId, GrapevineServerError> {
+        // check if an existing proof in this chain exists for the user
+        let phrase_hash_bson = self.phrase_hash_bson(&proof.phrase_hash.unwrap());
+
+        let mut proof_chain: Vec<DegreeProof> = vec![];
+        // fetch all proofs preceding this one
+        let mut cursor = self
+            .degree_proofs
             .aggregate(
                 vec![
+                    // degree 0 knowledge proofs stand alone, so they are never superseded
                     doc! {
                       "$match": {
                         "user": user,
-                        "phrase_hash": phrase_hash_bson.clone()
+                        "phrase_hash": phrase_hash_bson.clone(),
+                        "degree": { "$gt": 0 }
                       }
                     },
                     doc! {
@@ -355,10 +1211,21 @@ impl GrapevineDB {
             .await
             .expect("Error updating degree proof");
 
-        // create new proof document
+        // create new proof document, storing only the blind index and ciphertext of the phrase
+        let mut document = bson::to_document(proof).unwrap();
+        if let Some(key) = &self.phrase_key {
+            let phrase_hash = proof.phrase_hash.unwrap();
+            let ciphertext = Binary {
+                subtype: bson::spec::BinarySubtype::Generic,
+                bytes: key.encrypt(&phrase_hash),
+            };
+            document.insert("phrase_hash", self.phrase_hash_bson(&phrase_hash));
+            document.insert("phrase_ciphertext", ciphertext);
+        }
         let proof_oid = self
             .degree_proofs
-            .insert_one(proof, None)
+            .clone_with_type::<Document>()
+            .insert_one(document, None)
             .await
             .unwrap()
             .inserted_id
@@ -366,9 +1233,14 @@ impl GrapevineDB {
             .unwrap();
 
         // reference this proof in previous proof if not first proof in chain
+        // @dev children all have the same degree, so sorting by oid orders them by degree then oid
         if proof.preceding.is_some() {
             let query = doc! { "_id": proof.preceding.unwrap() };
-            let update = doc! { "$push": { "proceeding": bson::to_bson(&proof_oid).unwrap()} };
+            let update = doc! {
+                "$push": {
+                    "proceeding": { "$each": [bson::to_bson(&proof_oid).unwrap()], "$sort": 1 }
+                }
+            };
             self.degree_proofs
                 .update_one(query, update, None)
                 .await
@@ -391,211 +1263,961 @@ impl GrapevineDB {
         Ok(proof_oid)
     }
 
-    pub async fn get_proof(&self, proof_oid: &ObjectId) -> Option<DegreeProof> {
-        self.degree_proofs
-            .find_one(doc! { "_id": proof_oid }, None)
+    /**
+     * Remove superseded proofs that are older than a cutoff, relinking the chains through them
+     * @notice degree 1 proofs originate a phrase and are never removed
+     * @dev a superseded proof is one marked inactive after its owner proved a lower degree; it is
+     *      only kept because other proofs are built on it. Its children are re-pointed at its
+     *      preceding proof, so the children's degrees no longer step by exactly one from the
+     *      proof they reference (the chain proofs themselves are unchanged and still verify)
+     *
+     * @param cutoff - superseded proofs created strictly before this time are removed
+     * @returns - the number of proofs removed
+     */
+    pub async fn remove_superseded_proofs(
+        &self,
+        cutoff: DateTime,
+    ) -> Result<u64, GrapevineServerError> {
+        // the smallest oid that can be generated at the cutoff (for proofs without created_at)
+        let cutoff_secs = cutoff.timestamp_millis().div_euclid(1000);
+        let cutoff_secs = cutoff_secs.clamp(0, u32::MAX as i64) as u32;
+        let mut cutoff_oid = [0u8; 12];
+        cutoff_oid[..4].copy_from_slice(&cutoff_secs.to_be_bytes());
+        let cutoff_oid = ObjectId::from_bytes(cutoff_oid);
+        let filter = doc! {
+            "inactive": true,
+            "degree": { "$gt": 1 },
+            "$or": [
+                { "created_at": { "$lt": cutoff } },
+                { "created_at": null, "_id": { "$lt": cutoff_oid } }
+            ]
+        };
+        let find_options = FindOptions::builder().projection(doc! { "_id": 1 }).build();
+        let mut cursor = match self.degree_proofs.find(filter, find_options).await {
+            Ok(cursor) => cursor,
+            Err(e) => return Err(GrapevineServerError::MongoError(e.to_string())),
+        };
+        let mut expired: Vec<ObjectId> = vec![];
+        while let Some(result) = cursor.next().await {
+            match result {
+                Ok(proof) => expired.push(proof.id.unwrap()),
+                Err(e) => return Err(GrapevineServerError::MongoError(e.to_string())),
+            }
+        }
+
+        let map_err = |e: mongodb::error::Error| GrapevineServerError::MongoError(e.to_string());
+        let projection = doc! { "_id": 1, "user": 1, "preceding": 1, "proceeding": 1 };
+        let mut removed = 0;
+        for oid in expired {
+            // refetch the links, as removing an earlier proof may have relinked this one
+            let find_options = FindOneOptions::builder()
+                .projection(projection.clone())
+                .build();
+            let proof = match self
+                .degree_proofs
+                .find_one(doc! { "_id": oid, "inactive": true }, find_options)
+                .await
+                .map_err(map_err)?
+            {
+                Some(proof) => proof,
+                None => continue,
+            };
+            let children = proof.proceeding.unwrap_or_default();
+
+            // point the children at the removed proof's parent
+            if !children.is_empty() {
+                let query = doc! { "_id": { "$in": children.clone() } };
+                let update = doc! { "$set": { "preceding": proof.preceding } };
+                self.degree_proofs
+                    .update_many(query, update, None)
+                    .await
+                    .map_err(map_err)?;
+            }
+
+            // replace the removed proof with its children in the parent's proceeding list
+            if let Some(parent) = proof.preceding {
+                let query = doc! { "_id": parent };
+                let update = doc! { "$pull": { "proceeding": oid } };
+                self.degree_proofs
+                    .update_one(query.clone(), update, None)
+                    .await
+                    .map_err(map_err)?;
+                if !children.is_empty() {
+                    let update = doc! {
+                        "$push": { "proceeding": { "$each": children, "$sort": 1 } }
+                    };
+                    self.degree_proofs
+                        .update_one(query, update, None)
+                        .await
+                        .map_err(map_err)?;
+                }
+            }
+
+            // drop the proof from its owner's list of proofs
+            if let Some(user) = proof.user {
+                let update = doc! { "$pull": { "degree_proofs": oid } };
+                self.users
+                    .update_one(doc! { "_id": user }, update, None)
+                    .await
+                    .map_err(map_err)?;
+            }
+
+            self.degree_proofs
+                .delete_one(doc! { "_id": oid }, None)
+                .await
+                .map_err(map_err)?;
+            removed += 1;
+        }
+        Ok(removed)
+    }
+
+    /**
+     * Check whether a degree 1 proof (the origin of a phrase) already exists for a phrase hash
+     *
+     * @param phrase_hash - the poseidon hash of the phrase
+     * @returns - true if the phrase has already been created by any user
+     */
+    pub async fn phrase_originated(
+        &self,
+        phrase_hash: &[u8; 32],
+    ) -> Result<bool, GrapevineServerError> {
+        let query = doc! { "phrase_hash": self.phrase_hash_bson(phrase_hash), "degree": 1 };
+        match self.degree_proofs.count_documents(query, None).await {
+            Ok(count) => Ok(count > 0),
+            Err(e) => Err(GrapevineServerError::MongoError(e.to_string())),
+        }
+    }
+
+    /**
+     * Freeze a phrase so no new proofs can be added for it
+     * @notice - existing proofs for the phrase are left as is and remain readable
+     *
+     * @param phrase_hash - the poseidon hash of the phrase
+     * @returns - true if the phrase was newly frozen, or false if it was already frozen
+     */
+    pub async fn freeze_phrase(
+        &self,
+        phrase_hash: &[u8; 32],
+    ) -> Result<bool, GrapevineServerError> {
+        let filter = doc! { "phrase_hash": self.phrase_hash_bson(phrase_hash) };
+        let update = doc! { "$setOnInsert": { "frozen_at": DateTime::now() } };
+        let options = UpdateOptions::builder().upsert(true).build();
+        match self.frozen_phrases.update_one(filter, update, options).await {
+            Ok(res) => Ok(res.upserted_id.is_some()),
+            Err(e) => Err(GrapevineServerError::MongoError(e.to_string())),
+        }
+    }
+
+    /**
+     * Check whether a phrase has been frozen
+     *
+     * @param phrase_hash - the poseidon hash of the phrase
+     * @returns - true if new proofs for the phrase must be rejected
+     */
+    pub async fn phrase_frozen(
+        &self,
+        phrase_hash: &[u8; 32],
+    ) -> Result<bool, GrapevineServerError> {
+        let filter = doc! { "phrase_hash": self.phrase_hash_bson(phrase_hash) };
+        match self.frozen_phrases.count_documents(filter, None).await {
+            Ok(count) => Ok(count > 0),
+            Err(e) => Err(GrapevineServerError::MongoError(e.to_string())),
+        }
+    }
+
+    /**
+     * Find the user who created a phrase and the degree 1 proof they created it with
+     * @dev only one degree 1 proof should exist per phrase, but the earliest is returned if not
+     *
+     * @param phrase_hash - the poseidon hash of the phrase
+     * @returns - the username (None if originated anonymously) and proof oid of the phrase origin,
+     *            or None if it has no origin
+     */
+    pub async fn get_phrase_originator(
+        &self,
+        phrase_hash: &[u8; 32],
+    ) -> Result<Option<PhraseOriginator>, GrapevineServerError> {
+        let query = doc! { "phrase_hash": self.phrase_hash_bson(phrase_hash), "degree": 1 };
+        // proofs without a creation time predate those with one, and ObjectIDs break ties
+        let find_options = FindOneOptions::builder()
+            .projection(doc! { "user": 1, "anonymous": 1 })
+            .sort(doc! { "created_at": 1, "_id": 1 })
+            .build();
+        let proof = match self.degree_proofs.find_one(query, Some(find_options)).await {
+            Ok(Some(proof)) => proof,
+            Ok(None) => return Ok(None),
+            Err(e) => return Err(GrapevineServerError::MongoError(e.to_string())),
+        };
+        // withhold the creator of an anonymously originated phrase
+        if proof.anonymous == Some(true) {
+            return Ok(Some(PhraseOriginator {
+                username: None,
+                oid: proof.id.unwrap().to_hex(),
+            }));
+        }
+        // get the username of the proof creator
+        let projection = doc! { "username": 1 };
+        let find_options = FindOneOptions::builder().projection(projection).build();
+        match self
+            .users
+            .find_one(doc! { "_id": proof.user }, Some(find_options))
             .await
-            .unwrap()
+        {
+            Ok(Some(user)) => Ok(Some(PhraseOriginator {
+                username: user.username,
+                oid: proof.id.unwrap().to_hex(),
+            })),
+            Ok(None) => Ok(None),
+            Err(e) => Err(GrapevineServerError::MongoError(e.to_string())),
+        }
     }
 
-    pub async fn remove_user(&self, user: &ObjectId) {
-        self.users
-            .delete_one(doc! { "_id": user }, None)
+    /**
+     * Check whether a user holds an active proof within a maximum degree of a phrase
+     * @notice - only existence is checked, so none of the chain is read or returned
+     *
+     * @param username - the username of the user the claim is about
+     * @param phrase_hash - the poseidon hash of the phrase
+     * @param max_degree - the highest degree of separation that satisfies the claim
+     * @returns - true if the user has a proof at or below max_degree (false if the user does not
+     *            exist)
+     */
+    pub async fn has_proof_within(
+        &self,
+        username: &str,
+        phrase_hash: &[u8; 32],
+        max_degree: u8,
+    ) -> Result<bool, GrapevineServerError> {
+        // get the oid of the user
+        let find_options = FindOneOptions::builder()
+            .projection(doc! { "_id": 1 })
+            .build();
+        let user = match self
+            .users
+            .find_one(doc! { "username": username }, Some(find_options))
             .await
-            .expect("Failed to remove user");
+        {
+            Ok(Some(user)) => user.id.unwrap(),
+            Ok(None) => return Ok(false),
+            Err(e) => return Err(GrapevineServerError::MongoError(e.to_string())),
+        };
+        // check for any active proof by the user in the chain at or below the max degree
+        let query = doc! {
+            "user": user,
+            "phrase_hash": self.phrase_hash_bson(phrase_hash),
+            "degree": { "$lte": max_degree as i32 },
+            "inactive": { "$ne": true }
+        };
+        let count_options = CountOptions::builder().limit(1).build();
+        match self
+            .degree_proofs
+            .count_documents(query, Some(count_options))
+            .await
+        {
+            Ok(count) => Ok(count > 0),
+            Err(e) => Err(GrapevineServerError::MongoError(e.to_string())),
+        }
     }
 
     /**
-     * Given a user, find available degrees of separation proofs they can build from
-     *   - find degree chains they are not a part of
-     *   - find lower degree proofs they can build from
+     * Get the chain of proofs from the origin of a phrase down to a user's proof of it
+     * @dev the user's active proof is followed through its preceding links, so each step names
+     *      the creator of the proof it was built from as in get_all_degrees (withheld if that
+     *      proof anonymously originated the phrase)
+     *
+     * @param username - the username of the user whose path to return
+     * @param phrase_hash - the poseidon hash of the phrase
+     * @returns - the degree data of each proof ordered from the degree 1 origin to the user's
+     *            proof, or None if the user does not exist or has no proof for the phrase
      */
-    pub async fn find_available_degrees(&self, username: String) -> Vec<String> {
-        // find degree chains they are not a part of
+    pub async fn get_phrase_path(
+        &self,
+        username: &str,
+        phrase_hash: &[u8; 32],
+    ) -> Result<Option<Vec<DegreeData>>, GrapevineServerError> {
+        // get the oid of the user
+        let find_options = FindOneOptions::builder()
+            .projection(doc! { "_id": 1 })
+            .build();
+        let user = match self
+            .users
+            .find_one(doc! { "username": username }, Some(find_options))
+            .await
+        {
+            Ok(Some(user)) => user.id.unwrap(),
+            Ok(None) => return Ok(None),
+            Err(e) => return Err(GrapevineServerError::MongoError(e.to_string())),
+        };
+        // get the user's active proof of the phrase and every proof it builds on
         let pipeline = vec![
-            // find the user to find available proofs for
-            doc! { "$match": { "username": username } },
-            doc! { "$project": { "relationships": 1, "degree_proofs": 1, "_id": 0 } },
-            // look up the degree proofs made by this user
-            doc! {
-                "$lookup": {
-                    "from": "degree_proofs",
-                    "localField": "degree_proofs",
-                    "foreignField": "_id",
-                    "as": "userDegreeProofs",
-                    "pipeline": [doc! { "$project": { "degree": 1, "phrase_hash": 1 } }]
-                }
-            },
-            // look up the relationships made by this user
             doc! {
-                "$lookup": {
-                    "from": "relationships",
-                    "localField": "relationships",
-                    "foreignField": "_id",
-                    "as": "userRelationships",
-                    "pipeline": [doc! { "$project": { "sender": 1 } }]
+                "$match": {
+                    "user": user,
+                    "phrase_hash": self.phrase_hash_bson(phrase_hash),
+                    "inactive": { "$ne": true }
                 }
             },
-            // look up the degree proofs made by relationships
-            // @todo: allow limitation of degrees of separation here
+            doc! { "$sort": { "degree": 1 } },
+            doc! { "$limit": 1 },
             doc! {
-                "$lookup": {
+                "$graphLookup": {
                     "from": "degree_proofs",
-                    "localField": "userRelationships.sender",
-                    "foreignField": "user",
-                    "as": "relationshipDegreeProofs",
-                    "pipeline": [
-                        doc! { "$match": { "inactive": { "$ne": true } } },
-                        doc! { "$project": { "degree": 1, "phrase_hash": 1 } }
-                    ]
+                    "startWith": "$preceding",
+                    "connectFromField": "preceding",
+                    "connectToField": "_id",
+                    "as": "preceding_chain",
                 }
             },
-            // unwind the results
-            doc! { "$project": { "userDegreeProofs": 1, "relationshipDegreeProofs": 1 } },
-            doc! { "$unwind": "$relationshipDegreeProofs" },
-            // find the lowest degree proof in each chain from relationship proofs and reference user proofs in this chain if exists
             doc! {
-                "$group": {
-                    "_id": "$relationshipDegreeProofs.phrase_hash",
-                    "originalId": { "$first": "$relationshipDegreeProofs._id" },
-                    "degree": { "$min": "$relationshipDegreeProofs.degree" },
-                    "userProof": {
-                        "$first": {
-                            "$arrayElemAt": [{
-                                "$filter": {
-                                    "input": "$userDegreeProofs",
-                                    "as": "userProof",
-                                    "cond": { "$eq": ["$$userProof.phrase_hash", "$relationshipDegreeProofs.phrase_hash"] }
-                                }
-                            }, 0]
+                "$project": {
+                    "_id": 1,
+                    "degree": 1,
+                    "user": 1,
+                    "preceding": 1,
+                    "anonymous": 1,
+                    "preceding_chain": {
+                        "$map": {
+                            "input": "$preceding_chain",
+                            "as": "chain",
+                            "in": {
+                                "_id": "$$chain._id",
+                                "degree": "$$chain.degree",
+                                "user": "$$chain.user",
+                                "preceding": "$$chain.preceding",
+                                "anonymous": "$$chain.anonymous",
+                            }
                         }
                     }
                 }
             },
-            // remove the proofs that do not offer improved degrees of separation from existing user proofs
-            doc! {
-                "$match": {
-                    "$expr": {
-                        "$or": [
-                            { "$gte": ["$userProof.degree", { "$add": ["$degree", 2] }] },
-                            { "$eq": ["$userProof", null] }
-                        ]
-                    }
-                }
-            },
-            // project only the ids of the proofs the user can build from
-            doc! { "$project": { "_id": "$originalId" } },
         ];
-        // get the OID's of degree proofs the user can build from
-        let mut proofs: Vec<String> = vec![];
-        let mut cursor = self.users.aggregate(pipeline, None).await.unwrap();
+        let mut cursor = match self.degree_proofs.aggregate(pipeline, None).await {
+            Ok(cursor) => cursor,
+            Err(e) => return Err(GrapevineServerError::MongoError(e.to_string())),
+        };
+        let mut document = match cursor.next().await {
+            Some(Ok(document)) => document,
+            Some(Err(e)) => return Err(GrapevineServerError::MongoError(e.to_string())),
+            None => return Ok(None),
+        };
+        let preceding_chain = document.remove("preceding_chain");
+        let mut proofs: Vec<DegreeProof> = match preceding_chain {
+            Some(chain) => bson::from_bson(chain)
+                .map_err(|e| GrapevineServerError::SerdeError(e.to_string()))?,
+            None => vec![],
+        };
+        match bson::from_document::<DegreeProof>(document) {
+            Ok(proof) => proofs.push(proof),
+            Err(e) => return Err(GrapevineServerError::SerdeError(e.to_string())),
+        }
+        proofs.sort_by_key(|proof| proof.degree);
+        // name the creator of each proof in the path
+        let user_oids: Vec<ObjectId> = proofs.iter().filter_map(|proof| proof.user).collect();
+        let query = doc! { "_id": { "$in": user_oids } };
+        let find_options = FindOptions::builder()
+            .projection(doc! { "_id": 1, "username": 1 })
+            .build();
+        let mut cursor = match self.users.find(query, find_options).await {
+            Ok(cursor) => cursor,
+            Err(e) => return Err(GrapevineServerError::MongoError(e.to_string())),
+        };
+        let mut usernames: HashMap<ObjectId, String> = HashMap::new();
         while let Some(result) = cursor.next().await {
             match result {
-                Ok(document) => {
-                    let oid = document
-                        .get("_id")
-                        .and_then(|id| id.as_object_id())
-                        .unwrap();
-                    proofs.push(oid.to_string());
+                Ok(user) => {
+                    usernames.insert(user.id.unwrap(), user.username.unwrap());
                 }
-                Err(e) => println!("Error: {}", e),
+                Err(e) => return Err(GrapevineServerError::MongoError(e.to_string())),
             }
         }
+        // withhold the creator of an anonymously originated phrase
+        let creators: HashMap<ObjectId, ObjectId> = proofs
+            .iter()
+            .filter(|proof| proof.anonymous != Some(true))
+            .filter_map(|proof| Some((proof.id?, proof.user?)))
+            .collect();
+        let path = proofs
+            .iter()
+            .map(|proof| DegreeData {
+                degree: proof.degree.unwrap(),
+                relation: proof
+                    .preceding
+                    .and_then(|preceding| creators.get(&preceding))
+                    .and_then(|creator| usernames.get(creator).cloned()),
+                phrase_hash: *phrase_hash,
+            })
+            .collect();
+        Ok(Some(path))
+    }
 
-        proofs
+    /**
+     * Check whether any stored proof outputs a given auth hash
+     * @notice the auth hash commits to the prover's username and auth secret, so the same proof
+     *         bytes (or a proof of the same phrase by the same prover) always share one auth hash
+     *
+     * @param auth_hash - the auth hash output by the proof being added
+     * @returns - true if a proof with the auth hash is already stored
+     */
+    pub async fn auth_hash_stored(
+        &self,
+        auth_hash: &[u8; 32],
+    ) -> Result<bool, GrapevineServerError> {
+        let query = doc! { "auth_hash": phrase_hash_to_bson(auth_hash) };
+        let count_options = CountOptions::builder().limit(1).build();
+        match self
+            .degree_proofs
+            .count_documents(query, Some(count_options))
+            .await
+        {
+            Ok(count) => Ok(count > 0),
+            Err(e) => Err(GrapevineServerError::MongoError(e.to_string())),
+        }
     }
 
-    // @todo: ask chatgpt for better name
-    pub async fn get_all_degrees(&self, username: String) -> Option<Vec<DegreeData>> {
-        let pipeline = vec![
-            // get the user to find the proofs of degrees of separation for the user
-            doc! { "$match": { "username": username } },
-            doc! { "$project": { "_id": 1, "degree_proofs": 1 } },
-            // look up the degree proof documents
+    /**
+     * Check whether a user already holds an active proof continuing from a given proof
+     *
+     * @param user - the oid of the user continuing the proof
+     * @param preceding - the oid of the proof being continued from
+     * @returns - true if the user has an active proof built on the preceding proof
+     */
+    pub async fn proof_continued_by(
+        &self,
+        user: &ObjectId,
+        preceding: &ObjectId,
+    ) -> Result<bool, GrapevineServerError> {
+        let query = doc! {
+            "user": user,
+            "preceding": preceding,
+            "inactive": { "$ne": true }
+        };
+        let count_options = CountOptions::builder().limit(1).build();
+        match self
+            .degree_proofs
+            .count_documents(query, Some(count_options))
+            .await
+        {
+            Ok(count) => Ok(count > 0),
+            Err(e) => Err(GrapevineServerError::MongoError(e.to_string())),
+        }
+    }
+
+    /**
+     * List the proofs built directly on top of a given proof
+     * @dev reads the parent's proceeding array, so only proofs that have not been removed from
+     *      the chain are returned
+     * @dev children whose creator no longer exists are skipped
+     *
+     * @param proof - the oid of the parent proof
+     * @returns - the oid, creator, and degree of each child proof (empty if a leaf), or None if
+     *            the parent proof does not exist
+     */
+    pub async fn get_proof_children(
+        &self,
+        proof: &ObjectId,
+    ) -> Result<Option<Vec<ProofChild>>, GrapevineServerError> {
+        // get the oids of the proofs built on the parent
+        let projection = doc! { "proceeding": 1 };
+        let find_options = FindOneOptions::builder().projection(projection).build();
+        let proceeding = match self
+            .degree_proofs
+            .find_one(doc! { "_id": proof }, Some(find_options))
+            .await
+        {
+            Ok(Some(parent)) => parent.proceeding.unwrap_or_default(),
+            Ok(None) => return Ok(None),
+            Err(e) => return Err(GrapevineServerError::MongoError(e.to_string())),
+        };
+        if proceeding.is_empty() {
+            return Ok(Some(vec![]));
+        }
+        // look up each child proof and the username of its creator
+        let pipeline = vec![
+            doc! { "$match": { "_id": { "$in": proceeding.clone() } } },
+            doc! { "$project": { "_id": 1, "degree": 1, "user": 1 } },
             doc! {
                 "$lookup": {
-                    "from": "degree_proofs",
-                    "localField": "degree_proofs",
+                    "from": "users",
+                    "localField": "user",
                     "foreignField": "_id",
-                    "as": "proofs",
-                    "pipeline": [doc! { "$project": { "degree": 1, "preceding": 1, "phrase_hash": 1 } }]
+                    "as": "user",
+                    "pipeline": [doc! { "$project": { "_id": 0, "username": 1 } }]
                 }
             },
-            doc! { "$unwind": "$proofs" },
             doc! {
                 "$project": {
-                    "degree": "$proofs.degree",
-                    "preceding": "$proofs.preceding",
-                    "phrase_hash": "$proofs.phrase_hash",
-                    "_id": 0
+                    "_id": 1,
+                    "degree": 1,
+                    "username": { "$arrayElemAt": ["$user.username", 0] }
                 }
             },
-            // get the preceding proof if it exists, then get the user who made it to show the connection
-            doc! {
-                "$lookup": {
-                    "from": "degree_proofs",
-                    "localField": "preceding",
-                    "foreignField": "_id",
-                    "as": "relation",
-                    "pipeline": [doc! { "$project": { "user": 1, "_id": 0 } }]
+            doc! { "$sort": { "degree": 1, "_id": 1 } },
+        ];
+        let mut cursor = match self.degree_proofs.aggregate(pipeline, None).await {
+            Ok(cursor) => cursor,
+            Err(e) => return Err(GrapevineServerError::MongoError(e.to_string())),
+        };
+        let mut documents: Vec<Document> = vec![];
+        while let Some(result) = cursor.next().await {
+            match result {
+                Ok(document) => documents.push(document),
+                Err(e) => return Err(GrapevineServerError::MongoError(e.to_string())),
+            }
+        }
+        let children = documents
+            .iter()
+            .filter_map(|document| {
+                Some(ProofChild {
+                    oid: document.get_object_id("_id").ok()?.to_hex(),
+                    username: document.get_str("username").ok()?.to_string(),
+                    degree: document.get_i32("degree").ok()? as u8,
+                })
+            })
+            .collect();
+        Ok(Some(children))
+    }
+
+    /**
+     * Record a public params version on every proof stored without one
+     *
+     * @param version - the params version to record
+     * @returns - the number of proofs the version was recorded on
+     */
+    pub async fn migrate_params_version(&self, version: &str) -> Result<u64, GrapevineServerError> {
+        let query = doc! { "params_version": null };
+        let update = doc! { "$set": { "params_version": version } };
+        match self.degree_proofs.update_many(query, update, None).await {
+            Ok(res) => Ok(res.modified_count),
+            Err(e) => Err(GrapevineServerError::MongoError(e.to_string())),
+        }
+    }
+
+    /**
+     * Move proofs stored before a phrase key was set behind the blind index
+     * @notice proofs stored without a phrase key are queried by their plaintext phrase hash, so
+     *         none of them can be found once a key is set until they are re-indexed
+     *
+     * @returns - the number of proofs re-indexed (0 if no phrase key is set)
+     */
+    pub async fn migrate_phrase_index(&self) -> Result<u64, GrapevineServerError> {
+        let key = match &self.phrase_key {
+            Some(key) => key,
+            None => return Ok(0),
+        };
+        let map_err = |e: mongodb::error::Error| GrapevineServerError::MongoError(e.to_string());
+        // proofs stored under a key always carry the ciphertext of their phrase hash
+        let query = doc! { "phrase_ciphertext": { "$exists": false } };
+        let find_options = FindOptions::builder()
+            .projection(doc! { "phrase_hash": 1 })
+            .build();
+        let mut cursor = self
+            .degree_proofs
+            .clone_with_type::<Document>()
+            .find(query, Some(find_options))
+            .await
+            .map_err(map_err)?;
+        let mut migrated = 0;
+        while let Some(document) = cursor.next().await {
+            let document = document.map_err(map_err)?;
+            let oid = document
+                .get_object_id("_id")
+                .map_err(|_| GrapevineServerError::InternalError)?;
+            let phrase_hash = match hash_from_bson(document.get("phrase_hash")) {
+                Some(phrase_hash) => phrase_hash,
+                None => continue,
+            };
+            let ciphertext = Binary {
+                subtype: bson::spec::BinarySubtype::Generic,
+                bytes: key.encrypt(&phrase_hash),
+            };
+            // the filter is repeated so a proof re-indexed concurrently is not indexed twice
+            let filter = doc! { "_id": oid, "phrase_ciphertext": { "$exists": false } };
+            let update = doc! {
+                "$set": {
+                    "phrase_hash": self.phrase_hash_bson(&phrase_hash),
+                    "phrase_ciphertext": ciphertext,
                 }
-            },
-            doc! {
-                "$project": {
-                    "degree": 1,
-                    "preceding": 1,
-                    "phrase_hash": 1,
-                    "relation": { "$arrayElemAt": ["$relation.user", 0] },
-                    "_id": 0
+            };
+            let res = self
+                .degree_proofs
+                .update_one(filter, update, None)
+                .await
+                .map_err(map_err)?;
+            migrated += res.modified_count;
+        }
+        Ok(migrated)
+    }
+
+    /**
+     * Get the non-sensitive metadata of a proof
+     * @notice - the creation time is taken from the proof's ObjectID timestamp
+     * @notice - the creator is withheld if the proof anonymously originated its phrase
+     *
+     * @param proof - the oid of the proof to describe
+     * @returns - the degree, phrase hash, creator, and creation time of the proof, or None if the
+     *            proof does not exist
+     */
+    pub async fn get_proof_metadata(
+        &self,
+        proof: &ObjectId,
+    ) -> Result<Option<ProofMetadata>, GrapevineServerError> {
+        // get the proof without the proof body
+        let projection = doc! {
+            "degree": 1, "phrase_hash": 1, "phrase_ciphertext": 1, "user": 1, "anonymous": 1
+        };
+        let find_options = FindOneOptions::builder().projection(projection).build();
+        let proof = match self
+            .degree_proofs
+            .clone_with_type::<Document>()
+            .find_one(doc! { "_id": proof }, Some(find_options))
+            .await
+        {
+            Ok(Some(proof)) => self.reveal_proof(proof)?,
+            Ok(None) => return Ok(None),
+            Err(e) => return Err(GrapevineServerError::MongoError(e.to_string())),
+        };
+        // get the username of the proof creator
+        let projection = doc! { "username": 1 };
+        let find_options = FindOneOptions::builder().projection(projection).build();
+        let username = match self
+            .users
+            .find_one(doc! { "_id": proof.user }, Some(find_options))
+            .await
+        {
+            Ok(Some(user)) => user.username,
+            Ok(None) => return Ok(None),
+            Err(e) => return Err(GrapevineServerError::MongoError(e.to_string())),
+        };
+        // withhold the creator of an anonymously originated phrase
+        let username = match proof.anonymous {
+            Some(true) => None,
+            _ => username,
+        };
+        let oid = proof.id.unwrap();
+        Ok(Some(ProofMetadata {
+            oid: oid.to_hex(),
+            degree: proof.degree.unwrap(),
+            phrase_hash: proof.phrase_hash.unwrap(),
+            username,
+            created_at: oid.timestamp().timestamp_millis(),
+        }))
+    }
+
+    pub async fn get_proof(
+        &self,
+        proof_oid: &ObjectId,
+    ) -> Result<Option<DegreeProof>, GrapevineServerError> {
+        match self
+            .degree_proofs
+            .clone_with_type::<Document>()
+            .find_one(doc! { "_id": proof_oid }, None)
+            .await
+        {
+            Ok(Some(proof)) => self.reveal_proof(proof).map(Some),
+            Ok(None) => Ok(None),
+            Err(e) => Err(GrapevineServerError::MongoError(e.to_string())),
+        }
+    }
+
+    /**
+     * Get the degrees of a set of proofs without their proof bytes
+     *
+     * @param oids - the oids of the proofs to get the degrees of
+     * @returns - the degree of each proof that exists, keyed by oid
+     */
+    pub async fn get_proof_degrees(
+        &self,
+        oids: &Vec<ObjectId>,
+    ) -> Result<HashMap<ObjectId, u8>, GrapevineServerError> {
+        let filter = doc! { "_id": { "$in": oids } };
+        let find_options = FindOptions::builder()
+            .projection(doc! { "_id": 1, "degree": 1 })
+            .build();
+        let mut cursor = match self.degree_proofs.find(filter, find_options).await {
+            Ok(cursor) => cursor,
+            Err(e) => return Err(GrapevineServerError::MongoError(e.to_string())),
+        };
+        let mut degrees: HashMap<ObjectId, u8> = HashMap::new();
+        while let Some(result) = cursor.next().await {
+            match result {
+                Ok(proof) => {
+                    degrees.insert(proof.id.unwrap(), proof.degree.unwrap());
                 }
-            },
-            doc! {
-                "$lookup": {
-                    "from": "users",
-                    "localField": "relation",
-                    "foreignField": "_id",
-                    "as": "relation",
-                    "pipeline": [doc! { "$project": { "_id": 0, "username": 1 } }]
+                Err(e) => return Err(GrapevineServerError::MongoError(e.to_string())),
+            }
+        }
+        Ok(degrees)
+    }
+
+    pub async fn remove_user(&self, user: &ObjectId) {
+        let removed = self
+            .users
+            .find_one_and_delete(doc! { "_id": user }, None)
+            .await
+            .expect("Failed to remove user");
+        // a removed user must not keep authenticating from a cached nonce
+        if let Some(username) = removed.and_then(|user| user.username) {
+            nonce_cache::invalidate(self, &username);
+            // remember the username so its requests are rejected as gone rather than unknown
+            let options = UpdateOptions::builder().upsert(true).build();
+            self.deleted_users
+                .update_one(
+                    doc! { "username": &username },
+                    doc! { "$set": { "username": &username } },
+                    options,
+                )
+                .await
+                .expect("Failed to record removed user");
+        }
+    }
+
+    /**
+     * Delete users created by a write that failed part way, without recording them as removed
+     * @dev unlike remove_user no tombstone is kept, as the users never existed to any client
+     *
+     * @param users - the oids of the users to delete
+     */
+    pub async fn discard_users(&self, users: &[ObjectId]) -> Result<(), GrapevineServerError> {
+        for user in users {
+            match self.users.find_one_and_delete(doc! { "_id": user }, None).await {
+                Ok(Some(User { username: Some(username), .. })) => {
+                    nonce_cache::invalidate(self, &username)
                 }
-            },
+                Ok(_) => (),
+                Err(e) => return Err(GrapevineServerError::MongoError(e.to_string())),
+            }
+        }
+        Ok(())
+    }
+
+    /**
+     * Delete proofs added by a write that failed part way, unlinking them from their creators and
+     * from the proofs they build on
+     * @dev proofs that adding them marked inactive are left inactive
+     *
+     * @param proofs - the oids of the proofs to delete
+     */
+    pub async fn discard_proofs(&self, proofs: &[ObjectId]) -> Result<(), GrapevineServerError> {
+        let map_err = |e: mongodb::error::Error| GrapevineServerError::MongoError(e.to_string());
+        let query = doc! { "degree_proofs": { "$in": proofs } };
+        let update = doc! { "$pull": { "degree_proofs": { "$in": proofs } } };
+        self.users
+            .update_many(query, update, None)
+            .await
+            .map_err(map_err)?;
+        let query = doc! { "proceeding": { "$in": proofs } };
+        let update = doc! { "$pull": { "proceeding": { "$in": proofs } } };
+        self.degree_proofs
+            .update_many(query, update, None)
+            .await
+            .map_err(map_err)?;
+        self.degree_proofs
+            .delete_many(doc! { "_id": { "$in": proofs } }, None)
+            .await
+            .map_err(map_err)?;
+        Ok(())
+    }
+
+    /**
+     * Given a user, find available degrees of separation proofs they can build from
+     *   - find degree chains they are not a part of
+     *   - find lower degree proofs they can build from
+     * @notice the oids are ordered by hex phrase hash, then ascending degree, then oid so repeated
+     *         calls return the same list
+     * @dev the phrase hash ordered by is the blind index if PHRASE_KEY is set
+     */
+    pub async fn find_available_degrees(&self, username: String) -> Vec<String> {
+        // the hex encoding of each byte so phrase hashes can be ordered as hex strings
+        let hex_bytes: Vec<String> = (0..=255u8).map(|byte| format!("{:02x}", byte)).collect();
+        let mut pipeline = available_degrees_pipeline(username);
+        pipeline.extend(vec![
+            // order by phrase hash (arrays sort by their smallest element, so compare as hex)
             doc! {
-                "$project": {
-                    "degree": 1,
-                    "phrase_hash": 1,
-                    "relation": { "$arrayElemAt": ["$relation.username", 0] },
-                    "_id": 0
+                "$addFields": {
+                    "phraseHex": {
+                        "$reduce": {
+                            "input": "$_id",
+                            "initialValue": "",
+                            "in": {
+                                "$concat": ["$$value", { "$arrayElemAt": [hex_bytes, "$$this"] }]
+                            }
+                        }
+                    }
                 }
             },
-            doc! { "$sort": { "degree": 1 }},
-        ];
+            doc! { "$sort": { "phraseHex": 1, "degree": 1, "originalId": 1 } },
+            // project only the ids of the proofs the user can build from
+            doc! { "$project": { "_id": "$originalId" } },
+        ]);
         // get the OID's of degree proofs the user can build from
-        let mut degrees: Vec<DegreeData> = vec![];
+        let mut proofs: Vec<String> = vec![];
         let mut cursor = self.users.aggregate(pipeline, None).await.unwrap();
         while let Some(result) = cursor.next().await {
             match result {
                 Ok(document) => {
-                    let degree = document.get_i32("degree").unwrap() as u8;
-                    let relation = match document.get("relation") {
-                        Some(relation) => Some(relation.as_str().unwrap().to_string()),
-                        None => None,
-                    };
-                    // @todo: can this be retrieved better?
-                    let phrase_hash: [u8; 32] = document
-                        .get("phrase_hash")
-                        .unwrap()
-                        .as_array()
-                        .unwrap()
-                        .iter()
-                        .map(|x| x.as_i32().unwrap() as u8)
-                        .collect::<Vec<u8>>()
-                        .try_into()
+                    let oid = document
+                        .get("_id")
+                        .and_then(|id| id.as_object_id())
                         .unwrap();
-                    degrees.push(DegreeData {
-                        degree,
-                        relation,
-                        phrase_hash,
-                    });
+                    proofs.push(oid.to_string());
                 }
+                Err(e) => println!("Error: {}", e),
+            }
+        }
+
+        proofs
+    }
+
+    /**
+     * Count the proofs a user can build from without collecting their oids
+     * @dev a proof is only available if it is the lowest degree relationship proof in its chain
+     *      and improves on the user's own proof in that chain, which needs the grouping of the
+     *      aggregation rather than a count_documents filter. The $count runs on the grouped chains
+     *      without the ordering stages of find_available_degrees, so no oids are sorted or sent
+     *
+     * @param username - the username of the user looking to build a proof
+     * @returns - the number of oids find_available_degrees would return
+     */
+    pub async fn count_available_degrees(
+        &self,
+        username: String,
+    ) -> Result<u64, GrapevineServerError> {
+        let mut pipeline = available_degrees_pipeline(username);
+        pipeline.push(doc! { "$count": "count" });
+        let mut cursor = match self.users.aggregate(pipeline, None).await {
+            Ok(cursor) => cursor,
+            Err(e) => return Err(GrapevineServerError::MongoError(e.to_string())),
+        };
+        // $count produces no document at all if nothing is available
+        match cursor.next().await {
+            Some(Ok(document)) => match document.get("count") {
+                Some(Bson::Int32(count)) => Ok(*count as u64),
+                Some(Bson::Int64(count)) => Ok(*count as u64),
+                _ => Err(GrapevineServerError::MongoError(String::from("Malformed count"))),
+            },
+            Some(Err(e)) => Err(GrapevineServerError::MongoError(e.to_string())),
+            None => Ok(0),
+        }
+    }
+
+    /**
+     * Find the lowest degree proof a user can build from in a given phrase's chain
+     *
+     * @param username - the username of the user looking to build a proof
+     * @param phrase_hash - the hash of the phrase to build a proof for
+     * @returns - the oid of the proof to build from, or None if the user cannot join the chain
+     */
+    pub async fn find_best_available_degree(
+        &self,
+        username: String,
+        phrase_hash: &[u8; 32],
+    ) -> Result<Option<ObjectId>, GrapevineServerError> {
+        let available = self
+            .find_available_degrees(username)
+            .await
+            .iter()
+            .map(|oid| ObjectId::parse_str(oid).unwrap())
+            .collect::<Vec<ObjectId>>();
+        let query = doc! {
+            "_id": { "$in": available },
+            "phrase_hash": self.phrase_hash_bson(phrase_hash)
+        };
+        let find_options = FindOneOptions::builder()
+            .projection(doc! { "_id": 1 })
+            .sort(doc! { "degree": 1, "_id": 1 })
+            .build();
+        match self.degree_proofs.find_one(query, Some(find_options)).await {
+            Ok(proof) => Ok(proof.map(|proof| proof.id.unwrap())),
+            Err(e) => Err(GrapevineServerError::MongoError(e.to_string())),
+        }
+    }
+
+    // @todo: ask chatgpt for better name
+    pub async fn get_all_degrees(&self, username: String) -> Option<Vec<DegreeData>> {
+        self.find_degrees(username, None).await
+    }
+
+    /**
+     * Get the degree data for each proof a user has made at a specific degree of separation
+     *
+     * @param username - the username of the user to get degrees for
+     * @param degree - the degree of separation to filter the user's proofs by
+     * @returns - the degree data of the matching proofs (empty if none), or None if the query fails
+     */
+    pub async fn get_degrees_at(&self, username: String, degree: u8) -> Option<Vec<DegreeData>> {
+        self.find_degrees(username, Some(doc! { "degree": degree as i32 }))
+            .await
+    }
+
+    /**
+     * Get the degree data for each proof a user has made after a given time
+     * @dev proofs added before created_at was stored fall back to the creation time in their oid
+     *
+     * @param username - the username of the user to get degrees for
+     * @param since - only proofs created strictly after this time are returned
+     * @returns - the degree data of the matching proofs (empty if none), or None if the query fails
+     */
+    pub async fn get_degrees_since(
+        &self,
+        username: String,
+        since: DateTime,
+    ) -> Option<Vec<DegreeData>> {
+        // the smallest oid that can be generated in the second after the cutoff
+        let since_secs = since.timestamp_millis().div_euclid(1000) + 1;
+        let since_secs = since_secs.clamp(0, u32::MAX as i64) as u32;
+        let mut since_oid = [0u8; 12];
+        since_oid[..4].copy_from_slice(&since_secs.to_be_bytes());
+        let since_oid = ObjectId::from_bytes(since_oid);
+        let filter = doc! {
+            "$or": [
+                { "created_at": { "$gt": since } },
+                { "created_at": null, "_id": { "$gte": since_oid } }
+            ]
+        };
+        self.find_degrees(username, Some(filter)).await
+    }
+
+    /**
+     * Stream the degree data for each proof a user has made without collecting them in memory
+     *
+     * @param username - the username of the user to get degrees for
+     * @returns - a stream of the user's degree data ordered by degree, or an error if the query
+     *            could not be started
+     */
+    pub async fn stream_all_degrees(
+        &self,
+        username: String,
+    ) -> Result<impl Stream<Item = Result<DegreeData, GrapevineServerError>>, GrapevineServerError>
+    {
+        let pipeline = degrees_pipeline(username, None);
+        let phrase_key = self.phrase_key.clone();
+        match self.users.aggregate(pipeline, None).await {
+            Ok(cursor) => Ok(cursor.map(move |result| match result {
+                Ok(document) => parse_degree(&document, phrase_key.as_ref()),
+                Err(e) => Err(GrapevineServerError::MongoError(e.to_string())),
+            })),
+            Err(e) => Err(GrapevineServerError::MongoError(e.to_string())),
+        }
+    }
+
+    async fn find_degrees(
+        &self,
+        username: String,
+        proof_filter: Option<Document>,
+    ) -> Option<Vec<DegreeData>> {
+        let pipeline = degrees_pipeline(username, proof_filter);
+        // get the OID's of degree proofs the user can build from
+        let mut degrees: Vec<DegreeData> = vec![];
+        let mut cursor = self.users.aggregate(pipeline, None).await.unwrap();
+        while let Some(result) = cursor.next().await {
+            match result {
+                Ok(document) => match parse_degree(&document, self.phrase_key.as_ref()) {
+                    Ok(degree) => degrees.push(degree),
+                    Err(e) => {
+                        println!("Error: {}", e);
+                        return None
+                    },
+                },
                 Err(e) => {
                     println!("Error: {}", e);
                     return None
@@ -605,6 +2227,85 @@ impl GrapevineDB {
         Some(degrees)
     }
 
+    /// STATS FUNCTIONS ///
+
+    /**
+     * Count the documents in each collection for aggregate service statistics
+     * @dev a phrase is counted by the degree 1 proof that originated it
+     *
+     * @returns - the total number of users, phrases, proofs, and relationships
+     */
+    pub async fn get_global_stats(&self) -> Result<GlobalStats, GrapevineServerError> {
+        let users = self.users.count_documents(doc! {}, None).await;
+        let phrases = self
+            .degree_proofs
+            .count_documents(doc! { "degree": 1 }, None)
+            .await;
+        let proofs = self.degree_proofs.count_documents(doc! {}, None).await;
+        let relationships = self.relationships.count_documents(doc! {}, None).await;
+        match (users, phrases, proofs, relationships) {
+            (Ok(users), Ok(phrases), Ok(proofs), Ok(relationships)) => Ok(GlobalStats {
+                users,
+                phrases,
+                proofs,
+                relationships,
+            }),
+            _ => Err(GrapevineServerError::MongoError(String::from(
+                "Failed to count documents for stats",
+            ))),
+        }
+    }
+
+    /**
+     * Rank users by how many relationships they participate in
+     * @notice - a relationship counts for both its sender and recipient, whether or not it has
+     *           been reciprocated, and deactivated users are left out
+     *
+     * @param limit - the most users to return
+     * @returns - the usernames and relationship counts of the top users, ordered by most
+     *            relationships then username
+     */
+    pub async fn get_top_connectors(
+        &self,
+        limit: u32,
+    ) -> Result<Vec<TopConnector>, GrapevineServerError> {
+        let pipeline = vec![
+            doc! { "$match": { "deactivated": { "$ne": true } } },
+            doc! {
+                "$project": {
+                    "_id": 0,
+                    "username": 1,
+                    "relationships": { "$size": { "$ifNull": ["$relationships", []] } }
+                }
+            },
+            doc! { "$sort": { "relationships": -1, "username": 1 } },
+            doc! { "$limit": limit as i64 },
+        ];
+        let mut cursor = match self.users.aggregate(pipeline, None).await {
+            Ok(cursor) => cursor,
+            Err(e) => return Err(GrapevineServerError::MongoError(e.to_string())),
+        };
+        let mut connectors: Vec<TopConnector> = vec![];
+        while let Some(result) = cursor.next().await {
+            let document = match result {
+                Ok(document) => document,
+                Err(e) => return Err(GrapevineServerError::MongoError(e.to_string())),
+            };
+            match (document.get_str("username"), document.get_i32("relationships")) {
+                (Ok(username), Ok(relationships)) => connectors.push(TopConnector {
+                    username: username.to_string(),
+                    relationships: relationships as u64,
+                }),
+                _ => {
+                    return Err(GrapevineServerError::MongoError(String::from(
+                        "Malformed relationship count",
+                    )))
+                }
+            }
+        }
+        Ok(connectors)
+    }
+
     // used by passing args hash to check if existing phrase hash exists and deletes it
     // pub async fn delete_proof(&self, user: oid: ObjectId) -> Result<(), GrapevineServerError> {
     //     // delete the proof document
@@ -624,93 +2325,648 @@ impl GrapevineDB {
      *
      * @param username - the username of the user proving a degree of separation
      * @param oid - the id of the proof to get
+     * @returns - the proving data and decompressed proof size (None if stored before sizes were
+     *            recorded), or ProofNotFound if the proof does not exist and ProofNotEntitled if
+     *            the proof creator has no relationship with the user or the proof is a degree 0
+     *            knowledge proof
      */
     pub async fn get_proof_and_data(
         &self,
         username: String,
         proof: ObjectId,
-    ) -> Option<ProvingData> {
+    ) -> Result<(ProvingData, Option<u64>), GrapevineServerError> {
         // @todo: aggregation pipeline
         // get the proof
+        let oid = proof.to_hex();
         let filter = doc! { "_id": proof };
-        let projection = doc! { "user": 1, "degree": 1, "proof": 1 };
+        let projection = doc! { "user": 1, "degree": 1, "proof": 1, "proof_size": 1 };
         let find_options = FindOneOptions::builder().projection(projection).build();
-        println!("getting proof {:?}", proof);
-        let proof = self
+        let proof = match self
             .degree_proofs
             .find_one(filter, Some(find_options))
             .await
-            .unwrap()
-            .unwrap();
+        {
+            Ok(Some(proof)) => proof,
+            Ok(None) => return Err(GrapevineServerError::ProofNotFound(oid)),
+            Err(e) => return Err(GrapevineServerError::MongoError(e.to_string())),
+        };
+        // knowledge proofs are outside the chain, so nobody is entitled to build from them
+        if proof.degree == Some(0) {
+            return Err(GrapevineServerError::ProofNotEntitled(oid));
+        }
         // get the username of the user who made the proof
         let proof_creator = proof.user.unwrap();
         let filter = doc! { "_id": proof_creator };
         let projection = doc! { "username": 1, "pubkey": 1 };
         let find_options = FindOneOptions::builder().projection(projection).build();
-        println!("proof creator: {:?}", proof_creator);
-        let proof_creator_username = self
+        let proof_creator_username = match self
             .users
             .find_one(filter, Some(find_options))
             .await
-            .unwrap()
-            .unwrap()
-            .username
-            .unwrap();
-        println!("got proof creator");
+        {
+            Ok(Some(user)) => user.username.unwrap(),
+            Ok(None) => return Err(GrapevineServerError::ProofNotFound(oid)),
+            Err(e) => return Err(GrapevineServerError::MongoError(e.to_string())),
+        };
         // get the oid of message sender
-        let filter = doc! { "username": username };
+        let filter = doc! { "username": &username };
         let projection = doc! { "_id": 1, "pubkey": 1 };
         let find_options = FindOneOptions::builder().projection(projection).build();
-        let caller = self
+        let caller = match self
             .users
             .find_one(filter, Some(find_options))
             .await
-            .unwrap()
-            .unwrap()
-            .id
-            .unwrap();
-        println!("got caller");
+        {
+            Ok(Some(user)) => user.id.unwrap(),
+            Ok(None) => return Err(GrapevineServerError::UserNotFound(username)),
+            Err(e) => return Err(GrapevineServerError::MongoError(e.to_string())),
+        };
         // look up relationship with sender and recipient
         let filter = doc! { "sender": proof_creator, "recipient": caller };
-        let projection = doc! { "ephemeral_key": 1, "ciphertext": 1 };
+        let projection = doc! { "ephemeral_key": 1, "ciphertext": 1, "nonce": 1 };
         let find_options = FindOneOptions::builder().projection(projection).build();
-        let relationship = self
+        let relationship = match self
             .relationships
             .find_one(filter, Some(find_options))
             .await
-            .unwrap()
-            .unwrap();
-        println!("got relationship");
+        {
+            Ok(Some(relationship)) => relationship,
+            Ok(None) => return Err(GrapevineServerError::ProofNotEntitled(oid)),
+            Err(e) => return Err(GrapevineServerError::MongoError(e.to_string())),
+        };
         // return the proof data
-        Some(ProvingData {
+        let data = ProvingData {
             degree: proof.degree.unwrap(),
             proof: proof.proof.unwrap(),
             username: proof_creator_username,
             ephemeral_key: relationship.ephemeral_key.unwrap(),
             ciphertext: relationship.ciphertext.unwrap(),
-        })
+            nonce: relationship.nonce,
+        };
+        Ok((data, proof.proof_size))
+    }
+
+    /**
+     * Get the compressed proof bytes alone as a given user
+     * @dev gated by the same relationship as get_proof_and_data
+     *
+     * @param username - the username of the user requesting the proof
+     * @param oid - the id of the proof to get
+     * @returns - the gzip-compressed proof, or ProofNotFound if the proof does not exist and
+     *            ProofNotEntitled if the proof creator has no relationship with the user
+     */
+    pub async fn get_proof_bytes(
+        &self,
+        username: String,
+        proof: ObjectId,
+    ) -> Result<Vec<u8>, GrapevineServerError> {
+        // get the proof
+        let oid = proof.to_hex();
+        let projection = doc! { "user": 1, "proof": 1 };
+        let find_options = FindOneOptions::builder().projection(projection).build();
+        let proof = match self
+            .degree_proofs
+            .find_one(doc! { "_id": proof }, Some(find_options))
+            .await
+        {
+            Ok(Some(proof)) => proof,
+            Ok(None) => return Err(GrapevineServerError::ProofNotFound(oid)),
+            Err(e) => return Err(GrapevineServerError::MongoError(e.to_string())),
+        };
+        // get the oid of the requester
+        let caller = match self.get_user(&username).await {
+            Some(user) => user.id.unwrap(),
+            None => return Err(GrapevineServerError::UserNotFound(username)),
+        };
+        // only a recipient of the proof creator's auth secret may retrieve the proof
+        match self.relationship_exists(&proof.user.unwrap(), &caller).await? {
+            true => Ok(proof.proof.unwrap()),
+            false => Err(GrapevineServerError::ProofNotEntitled(oid)),
+        }
     }
 
     /**
      * Get chain of degree proofs linked to a phrase
      *
      * @param phrase_hash - hash of the phrase linking the proof chain together
+     * @returns - the oid and degree of each proof in the chain ordered by degree then oid
      */
-    pub async fn get_proof_chain(&self, phrase_hash: &str) -> Vec<DegreeProof> {
-        let mut proofs: Vec<DegreeProof> = vec![];
-        let query = doc! { "phrase_hash": phrase_hash };
+    pub async fn get_proof_chain(
+        &self,
+        phrase_hash: &[u8; 32],
+    ) -> Result<Vec<ChainProof>, GrapevineServerError> {
+        let mut proofs: Vec<ChainProof> = vec![];
+        let query = doc! { "phrase_hash": self.phrase_hash_bson(phrase_hash) };
         let projection = doc! { "_id":1, "degree": 1 };
-        let find_options = FindOptions::builder().projection(projection).build();
-        let mut cursor = self.degree_proofs.find(query, find_options).await.unwrap();
+        let find_options = FindOptions::builder()
+            .projection(projection)
+            .sort(doc! { "degree": 1, "_id": 1 })
+            .build();
+        let mut cursor = match self.degree_proofs.find(query, find_options).await {
+            Ok(cursor) => cursor,
+            Err(e) => return Err(GrapevineServerError::MongoError(e.to_string())),
+        };
 
         while let Some(result) = cursor.next().await {
             match result {
-                Ok(proof) => {
-                    proofs.push(proof);
+                Ok(proof) => proofs.push(ChainProof {
+                    oid: proof.id.unwrap().to_hex(),
+                    degree: proof.degree.unwrap(),
+                }),
+                Err(e) => return Err(GrapevineServerError::MongoError(e.to_string())),
+            }
+        }
+        Ok(proofs)
+    }
+
+    /**
+     * Export every proof in a phrase chain with the users and relationships linking them
+     * @notice relationships are only included between the creators of consecutive proofs, since
+     *         those are the relationships the chain was built through
+     *
+     * @param phrase_hash - the poseidon hash of the phrase at the start of the chain
+     * @returns - the exported chain ordered by degree, or None if no proofs exist for the phrase
+     */
+    pub async fn export_chain(
+        &self,
+        phrase_hash: &[u8; 32],
+    ) -> Result<Option<ChainExport>, GrapevineServerError> {
+        // get every proof in the chain so each follows the proof it builds on
+        let query = doc! { "phrase_hash": self.phrase_hash_bson(phrase_hash) };
+        let find_options = FindOptions::builder()
+            .sort(doc! { "degree": 1, "_id": 1 })
+            .build();
+        let mut cursor = match self
+            .degree_proofs
+            .clone_with_type::<Document>()
+            .find(query, find_options)
+            .await
+        {
+            Ok(cursor) => cursor,
+            Err(e) => return Err(GrapevineServerError::MongoError(e.to_string())),
+        };
+        let mut proofs: Vec<DegreeProof> = vec![];
+        while let Some(result) = cursor.next().await {
+            match result {
+                Ok(proof) => proofs.push(self.reveal_proof(proof)?),
+                Err(e) => return Err(GrapevineServerError::MongoError(e.to_string())),
+            }
+        }
+        if proofs.is_empty() {
+            return Ok(None);
+        }
+        // get the creator of each proof
+        let user_oids: Vec<ObjectId> = proofs.iter().map(|proof| proof.user.unwrap()).collect();
+        let query = doc! { "_id": { "$in": user_oids } };
+        let projection = doc! { "_id": 1, "username": 1, "pubkey": 1 };
+        let find_options = FindOptions::builder().projection(projection).build();
+        let mut cursor = match self.users.find(query, find_options).await {
+            Ok(cursor) => cursor,
+            Err(e) => return Err(GrapevineServerError::MongoError(e.to_string())),
+        };
+        let mut users: HashMap<ObjectId, ExportedUser> = HashMap::new();
+        while let Some(result) = cursor.next().await {
+            match result {
+                Ok(user) => {
+                    let exported = ExportedUser {
+                        username: user.username.unwrap(),
+                        pubkey: user.pubkey.unwrap(),
+                    };
+                    users.insert(user.id.unwrap(), exported);
                 }
-                Err(e) => println!("Error: {:?}", e),
+                Err(e) => return Err(GrapevineServerError::MongoError(e.to_string())),
             }
         }
-        proofs
+        let username = |oid: &ObjectId| match users.get(oid) {
+            Some(user) => Ok(user.username.clone()),
+            None => Err(GrapevineServerError::UserNotFound(oid.to_hex())),
+        };
+        // export each proof, and the relationship it was built through
+        let creators: HashMap<ObjectId, ObjectId> = proofs
+            .iter()
+            .map(|proof| (proof.id.unwrap(), proof.user.unwrap()))
+            .collect();
+        let mut exported_proofs = vec![];
+        let mut relationships = vec![];
+        for proof in proofs {
+            let user = proof.user.unwrap();
+            if let Some(sender) = proof.preceding.and_then(|oid| creators.get(&oid)) {
+                if let Some(relationship) = self.get_relationship(sender, &user).await {
+                    relationships.push(ExportedRelationship {
+                        sender: username(sender)?,
+                        recipient: username(&user)?,
+                        ephemeral_key: relationship.ephemeral_key.unwrap(),
+                        ciphertext: relationship.ciphertext.unwrap(),
+                        nonce: relationship.nonce,
+                    });
+                }
+            }
+            exported_proofs.push(ExportedProof {
+                oid: proof.id.unwrap().to_hex(),
+                username: username(&user)?,
+                degree: proof.degree.unwrap(),
+                phrase_hash: proof.phrase_hash.unwrap(),
+                auth_hash: proof.auth_hash.unwrap(),
+                proof: proof.proof.unwrap(),
+                preceding: proof.preceding.map(|oid| oid.to_hex()),
+            });
+        }
+        Ok(Some(ChainExport {
+            users: users.into_values().collect(),
+            relationships,
+            proofs: exported_proofs,
+        }))
+    }
+
+    /**
+     * Find auth hashes shared by degree 1 proofs of different phrases
+     * @notice the degree 1 auth hash is derived from the originator's auth secret, so one auth
+     *         hash across several phrases suggests a reused auth secret (i.e. seeding phrases)
+     *
+     * @returns - each reused auth hash with the proofs, creators, and phrases it appears in,
+     *            ordered by auth hash
+     */
+    pub async fn find_auth_hash_reuse(&self) -> Result<Vec<AuthHashReuse>, GrapevineServerError> {
+        let pipeline = vec![
+            doc! { "$match": { "degree": 1 } },
+            doc! { "$sort": { "_id": 1 } },
+            // group the degree 1 proofs by auth hash, keeping only hashes used for many phrases
+            doc! {
+                "$group": {
+                    "_id": "$auth_hash",
+                    "phrases": { "$addToSet": "$phrase_hash" },
+                    "proofs": {
+                        "$push": {
+                            "oid": "$_id",
+                            "user": "$user",
+                            "phrase_hash": "$phrase_hash",
+                            "phrase_ciphertext": "$phrase_ciphertext"
+                        }
+                    }
+                }
+            },
+            doc! { "$match": { "phrases.1": { "$exists": true } } },
+            // name the creator of each proof
+            doc! {
+                "$lookup": {
+                    "from": "users",
+                    "localField": "proofs.user",
+                    "foreignField": "_id",
+                    "as": "users",
+                    "pipeline": [doc! { "$project": { "_id": 1, "username": 1 } }]
+                }
+            },
+            doc! { "$sort": { "_id": 1 } },
+        ];
+        let mut cursor = match self.degree_proofs.aggregate(pipeline, None).await {
+            Ok(cursor) => cursor,
+            Err(e) => return Err(GrapevineServerError::MongoError(e.to_string())),
+        };
+        let invalid = |field: &str| GrapevineServerError::SerdeError(format!("reuse {}", field));
+        let mut reused = vec![];
+        while let Some(result) = cursor.next().await {
+            let document = match result {
+                Ok(document) => document,
+                Err(e) => return Err(GrapevineServerError::MongoError(e.to_string())),
+            };
+            let auth_hash = hash_from_bson(document.get("_id")).ok_or(invalid("auth_hash"))?;
+            let mut usernames: HashMap<ObjectId, String> = HashMap::new();
+            for user in document.get_array("users").map_err(|_| invalid("users"))? {
+                let user = user.as_document().ok_or(invalid("users"))?;
+                if let Ok(username) = user.get_str("username") {
+                    let oid = user.get_object_id("_id").map_err(|_| invalid("users"))?;
+                    usernames.insert(oid, username.to_string());
+                }
+            }
+            let mut origins = vec![];
+            for proof in document.get_array("proofs").map_err(|_| invalid("proofs"))? {
+                let proof = proof.as_document().ok_or(invalid("proofs"))?;
+                let oid = proof.get_object_id("oid").map_err(|_| invalid("oid"))?;
+                let username = match proof.get_object_id("user") {
+                    Ok(user) => usernames.get(&user).cloned(),
+                    Err(_) => None,
+                };
+                // phrases stored behind a blind index are reported by their decrypted hash
+                let ciphertext = proof.get_binary_generic("phrase_ciphertext");
+                let phrase_hash = match (&self.phrase_key, ciphertext) {
+                    (Some(key), Ok(ciphertext)) => key.decrypt(ciphertext),
+                    _ => hash_from_bson(proof.get("phrase_hash")),
+                };
+                origins.push(PhraseOrigin {
+                    oid: oid.to_hex(),
+                    username,
+                    phrase_hash: phrase_hash.ok_or(invalid("phrase_hash"))?,
+                });
+            }
+            reused.push(AuthHashReuse { auth_hash, origins });
+        }
+        Ok(reused)
+    }
+
+    /**
+     * Convert a phrase hash to the form proofs store and are queried by
+     *
+     * @param phrase_hash - the poseidon hash of the phrase
+     * @returns - the blind index of the phrase hash if a phrase key is set, otherwise the phrase
+     *            hash itself, as a vector usable in bson queries
+     */
+    fn phrase_hash_bson(&self, phrase_hash: &[u8; 32]) -> Vec<i32> {
+        match &self.phrase_key {
+            Some(key) => phrase_hash_to_bson(&key.index(phrase_hash)),
+            None => phrase_hash_to_bson(phrase_hash),
+        }
+    }
+
+    /**
+     * Parse a stored proof, restoring its phrase hash from the ciphertext if it was encrypted
+     *
+     * @param document - the proof document as stored in the degree_proofs collection
+     * @returns - the proof with its plaintext phrase hash, or an error if it cannot be decrypted
+     */
+    fn reveal_proof(&self, mut document: Document) -> Result<DegreeProof, GrapevineServerError> {
+        if let Some(key) = &self.phrase_key {
+            if let Ok(ciphertext) = document.get_binary_generic("phrase_ciphertext") {
+                let phrase_hash = key
+                    .decrypt(ciphertext)
+                    .ok_or(GrapevineServerError::InternalError)?;
+                document.insert("phrase_hash", phrase_hash_to_bson(&phrase_hash));
+            }
+        }
+        bson::from_document(document)
+            .map_err(|e| GrapevineServerError::SerdeError(e.to_string()))
     }
 }
+
+/**
+ * Check whether a write failed because it would violate a unique index
+ *
+ * @param err - the error returned by the write
+ * @returns - true if the error is a duplicate key error
+ */
+fn is_duplicate_key(err: &mongodb::error::Error) -> bool {
+    match &*err.kind {
+        ErrorKind::Write(WriteFailure::WriteError(e)) => e.code == 11000,
+        _ => false,
+    }
+}
+
+/**
+ * The collation usernames are compared under when checking for collisions
+ * @dev strength 2 compares case insensitively, matching normalize_username for ascii usernames
+ *
+ * @returns - the case insensitive english collation
+ */
+fn username_collation() -> Collation {
+    Collation::builder()
+        .locale("en")
+        .strength(CollationStrength::Secondary)
+        .build()
+}
+
+/**
+ * Convert a phrase hash to the form it is stored in by the degree_proofs collection
+ * @dev serde stores [u8; 32] as an array of int32 values
+ *
+ * @param phrase_hash - the phrase hash to convert
+ * @returns - the phrase hash as a vector usable in bson queries
+ */
+fn phrase_hash_to_bson(phrase_hash: &[u8; 32]) -> Vec<i32> {
+    phrase_hash.iter().map(|x| *x as i32).collect()
+}
+
+/**
+ * Parse a hash stored by serde as an array of int32 values
+ *
+ * @param value - the stored hash, if present
+ * @returns - the hash, or None if the value is missing or not a 32 byte array
+ */
+fn hash_from_bson(value: Option<&Bson>) -> Option<[u8; 32]> {
+    value?
+        .as_array()?
+        .iter()
+        .map(|x| x.as_i32().map(|x| x as u8))
+        .collect::<Option<Vec<u8>>>()?
+        .try_into()
+        .ok()
+}
+
+/**
+ * Build the aggregation over the users collection that finds the proofs a user can build from
+ *
+ * @param username - the username of the user looking to build a proof
+ * @returns - the pipeline producing one unordered document per phrase chain the user can join,
+ *            with the oid of the proof to build from in originalId and its degree in degree
+ */
+fn available_degrees_pipeline(username: String) -> Vec<Document> {
+    vec![
+        // find the user to find available proofs for
+        doc! { "$match": { "username": username } },
+        doc! { "$project": { "relationships": 1, "degree_proofs": 1, "_id": 1 } },
+        // look up the degree proofs made by this user
+        doc! {
+            "$lookup": {
+                "from": "degree_proofs",
+                "localField": "degree_proofs",
+                "foreignField": "_id",
+                "as": "userDegreeProofs",
+                "pipeline": [
+                    // knowing a phrase directly does not stop a user from joining its chain
+                    doc! { "$match": { "degree": { "$gt": 0 } } },
+                    doc! { "$project": { "degree": 1, "phrase_hash": 1 } }
+                ]
+            }
+        },
+        // look up the relationships sent to this user (sent relationships are also referenced)
+        doc! {
+            "$lookup": {
+                "from": "relationships",
+                "localField": "relationships",
+                "foreignField": "_id",
+                "as": "userRelationships",
+                "let": { "user": "$_id" },
+                "pipeline": [
+                    doc! { "$match": { "$expr": { "$eq": ["$recipient", "$$user"] } } },
+                    doc! { "$project": { "sender": 1 } }
+                ]
+            }
+        },
+        // look up the degree proofs made by relationships
+        // @todo: allow limitation of degrees of separation here
+        doc! {
+            "$lookup": {
+                "from": "degree_proofs",
+                "localField": "userRelationships.sender",
+                "foreignField": "user",
+                "as": "relationshipDegreeProofs",
+                "pipeline": [
+                    // degree 0 knowledge proofs carry no auth hash to build from
+                    doc! { "$match": { "inactive": { "$ne": true }, "degree": { "$gt": 0 } } },
+                    doc! { "$project": { "degree": 1, "phrase_hash": 1 } }
+                ]
+            }
+        },
+        // unwind the results
+        doc! { "$project": { "userDegreeProofs": 1, "relationshipDegreeProofs": 1 } },
+        doc! { "$unwind": "$relationshipDegreeProofs" },
+        // order so the first proof in each chain is the lowest degree proof
+        doc! {
+            "$sort": {
+                "relationshipDegreeProofs.degree": 1,
+                "relationshipDegreeProofs._id": 1
+            }
+        },
+        // find the lowest degree proof in each chain from relationship proofs and reference user proofs in this chain if exists
+        doc! {
+            "$group": {
+                "_id": "$relationshipDegreeProofs.phrase_hash",
+                "originalId": { "$first": "$relationshipDegreeProofs._id" },
+                "degree": { "$min": "$relationshipDegreeProofs.degree" },
+                "userProof": {
+                    "$first": {
+                        "$arrayElemAt": [{
+                            "$filter": {
+                                "input": "$userDegreeProofs",
+                                "as": "userProof",
+                                "cond": { "$eq": ["$$userProof.phrase_hash", "$relationshipDegreeProofs.phrase_hash"] }
+                            }
+                        }, 0]
+                    }
+                }
+            }
+        },
+        // remove the proofs that do not offer improved degrees of separation from existing user proofs
+        doc! {
+            "$match": {
+                "$expr": {
+                    "$or": [
+                        { "$gte": ["$userProof.degree", { "$add": ["$degree", 2] }] },
+                        { "$eq": ["$userProof", null] }
+                    ]
+                }
+            }
+        },
+    ]
+}
+
+/**
+ * Build the aggregation over the users collection that resolves a user's degree data
+ *
+ * @param username - the username of the user to get degrees for
+ * @param proof_filter - if some, only the user's proofs matching this filter are looked up
+ * @returns - the pipeline producing one document per proof ordered by degree
+ */
+fn degrees_pipeline(username: String, proof_filter: Option<Document>) -> Vec<Document> {
+    // only look up the user's proofs matching the filter if one is given
+    let mut proof_pipeline = vec![];
+    if let Some(proof_filter) = proof_filter {
+        proof_pipeline.push(doc! { "$match": proof_filter });
+    }
+    proof_pipeline.push(doc! {
+        "$project": { "degree": 1, "preceding": 1, "phrase_hash": 1, "phrase_ciphertext": 1 }
+    });
+    vec![
+        // get the user to find the proofs of degrees of separation for the user
+        doc! { "$match": { "username": username } },
+        doc! { "$project": { "_id": 1, "degree_proofs": 1 } },
+        // look up the degree proof documents
+        doc! {
+            "$lookup": {
+                "from": "degree_proofs",
+                "localField": "degree_proofs",
+                "foreignField": "_id",
+                "as": "proofs",
+                "pipeline": proof_pipeline
+            }
+        },
+        doc! { "$unwind": "$proofs" },
+        doc! {
+            "$project": {
+                "degree": "$proofs.degree",
+                "preceding": "$proofs.preceding",
+                "phrase_hash": "$proofs.phrase_hash",
+                "phrase_ciphertext": "$proofs.phrase_ciphertext",
+                "_id": 0
+            }
+        },
+        // get the preceding proof if it exists, then get the user who made it to show the connection
+        doc! {
+            "$lookup": {
+                "from": "degree_proofs",
+                "localField": "preceding",
+                "foreignField": "_id",
+                "as": "relation",
+                "pipeline": [doc! { "$project": { "user": 1, "_id": 0 } }]
+            }
+        },
+        doc! {
+            "$project": {
+                "degree": 1,
+                "preceding": 1,
+                "phrase_hash": 1,
+                "phrase_ciphertext": 1,
+                "relation": { "$arrayElemAt": ["$relation.user", 0] },
+                "_id": 0
+            }
+        },
+        doc! {
+            "$lookup": {
+                "from": "users",
+                "localField": "relation",
+                "foreignField": "_id",
+                "as": "relation",
+                "pipeline": [doc! { "$project": { "_id": 0, "username": 1 } }]
+            }
+        },
+        doc! {
+            "$project": {
+                "degree": 1,
+                "phrase_hash": 1,
+                "phrase_ciphertext": 1,
+                "relation": { "$arrayElemAt": ["$relation.username", 0] },
+                "_id": 0
+            }
+        },
+        doc! { "$sort": { "degree": 1 }},
+    ]
+}
+
+/**
+ * Parse a document produced by degrees_pipeline into degree data
+ *
+ * @param document - the document for a single proof
+ * @param phrase_key - if some, the key to decrypt the phrase hash of the proof with
+ * @returns - the degree data of the proof, or an error if its phrase hash cannot be decrypted
+ */
+fn parse_degree(
+    document: &Document,
+    phrase_key: Option<&PhraseKey>,
+) -> Result<DegreeData, GrapevineServerError> {
+    let degree = document.get_i32("degree").unwrap() as u8;
+    let relation = match document.get("relation") {
+        Some(relation) => Some(relation.as_str().unwrap().to_string()),
+        None => None,
+    };
+    // @todo: can this be retrieved better?
+    let phrase_hash: [u8; 32] = document
+        .get("phrase_hash")
+        .unwrap()
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|x| x.as_i32().unwrap() as u8)
+        .collect::<Vec<u8>>()
+        .try_into()
+        .unwrap();
+    // proofs stored before a phrase key was set keep their plaintext phrase hash
+    let phrase_hash = match (phrase_key, document.get_binary_generic("phrase_ciphertext")) {
+        (Some(key), Ok(ciphertext)) => key
+            .decrypt(ciphertext)
+            .ok_or(GrapevineServerError::InternalError)?,
+        _ => phrase_hash,
+    };
+    Ok(DegreeData {
+        degree,
+        relation,
+        phrase_hash,
+    })
+}
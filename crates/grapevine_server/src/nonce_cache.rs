@@ -0,0 +1,74 @@
+use crate::mongo::GrapevineDB;
+use grapevine_common::errors::GrapevineServerError;
+use grapevine_common::nonce::Nonce;
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+lazy_static! {
+    /**
+     * The nonce and pubkey of recently authenticated users keyed by database name and username
+     * @dev the database stays the source of truth: entries are only filled from it, and nonces
+     *      are only consumed by a conditional write against it, so a stale entry can fail a
+     *      request but never admit a replayed signature
+     */
+    static ref NONCES: Mutex<HashMap<(String, String), (Nonce, [u8; 32])>> =
+        Mutex::new(HashMap::new());
+}
+
+/**
+ * Get the nonce the server expects from a user and their pubkey, reading the database on a miss
+ *
+ * @param db - the database to read the nonce from on a miss
+ * @param username - the username to get the nonce for
+ * @returns - the expected nonce and the compressed pubkey, or None if the user cannot authenticate
+ */
+pub async fn get_nonce(db: &GrapevineDB, username: &str) -> Option<(Nonce, [u8; 32])> {
+    let key = (db.name().to_string(), username.to_string());
+    let cached = NONCES.lock().unwrap().get(&key).cloned();
+    match cached {
+        Some(entry) => Some(entry),
+        None => {
+            let entry = db.get_nonce(username).await?;
+            NONCES.lock().unwrap().insert(key, entry);
+            Some(entry)
+        }
+    }
+}
+
+/**
+ * Consume a user's nonce, writing through to the cache if the database accepts it
+ * @dev the entry is taken out before the write is awaited, so a request that is dropped
+ *      mid-write leaves nothing cached rather than a nonce that may already be consumed
+ *
+ * @param db - the database to consume the nonce in
+ * @param username - the username whose nonce is consumed
+ * @param nonce - the nonce the request was signed over
+ * @returns - the next nonce if this request consumed the nonce, or None if it no longer matches
+ *            the database
+ */
+pub async fn increment_nonce(
+    db: &GrapevineDB,
+    username: &str,
+    nonce: Nonce,
+) -> Result<Option<Nonce>, GrapevineServerError> {
+    let key = (db.name().to_string(), username.to_string());
+    let cached = NONCES.lock().unwrap().remove(&key);
+    let incremented = db.increment_nonce(username, nonce).await?;
+    if let (Some(next), Some((_, pubkey))) = (incremented, cached) {
+        NONCES.lock().unwrap().insert(key, (next, pubkey));
+    }
+    Ok(incremented)
+}
+
+/**
+ * Drop a user's cached nonce
+ * @dev must be called by any write that changes a user's nonce, pubkey, or deactivated flag
+ *
+ * @param db - the database the user belongs to
+ * @param username - the username whose entry is no longer valid
+ */
+pub fn invalidate(db: &GrapevineDB, username: &str) {
+    let key = (db.name().to_string(), username.to_string());
+    NONCES.lock().unwrap().remove(&key);
+}
@@ -0,0 +1,841 @@
+use grapevine_common::errors::GrapevineServerError;
+use grapevine_common::http::requests::{
+    AutoParamsRequest, CreateUserRequest, DegreeProofRequest, GetNonceRequest,
+    NewPhraseBatchRequest, NewPhraseRequest, NewRelationshipRequest, UpdateRelationshipRequest,
+    VerifyClaimRequest, VerifyProofRequest, VerifySignatureRequest,
+};
+use grapevine_common::http::responses::{
+    AuthHashReuse, AvailableProof, BatchRelationshipResult, ChainExport, ChainImport, ChainProof,
+    CircuitInfo, ConnectionGraph, Dashboard, DegreeData, GlobalStats, ParamsMigration,
+    PhraseOriginator, ProofChild, ProofMetadata, ProofVerification, ReceivedAuthSecret,
+    RelationshipData, RelationshipDiagnostics, RelationshipSummary, SessionData, SessionToken,
+    SignedClaim, TopConnector,
+};
+use grapevine_common::http::{AUTHORIZATION_HEADER, USERNAME_HEADER};
+use grapevine_common::models::proof::ProvingData;
+use lazy_static::lazy_static;
+use schemars::gen::SchemaSettings;
+use serde_json::{json, Map, Value};
+
+lazy_static! {
+    /** The OpenAPI document served at /openapi.json */
+    pub static ref SPEC: Value = spec();
+}
+
+/** How the body of a request or response is encoded */
+enum Body {
+    // json matching a component schema
+    Json(&'static str),
+    // json array of a component schema
+    JsonArray(&'static str),
+    // bincode serialized component schema
+    Bincode(&'static str),
+    // a json value with no component schema
+    JsonValue(&'static str),
+    // plain text
+    Text,
+    // raw bytes
+    Bytes,
+}
+
+/** What a route requires to authenticate */
+enum Auth {
+    None,
+    // X-Username with X-Authorization (signed nonce) or X-Session
+    User,
+    // X-Admin-Key
+    Admin,
+}
+
+/** A route as documented in the spec */
+struct Operation {
+    method: &'static str,
+    // the full mounted path with {param} segments
+    path: &'static str,
+    summary: &'static str,
+    auth: Auth,
+    // (name, "path" or "query", json schema type)
+    params: &'static [(&'static str, &'static str, &'static str)],
+    request: Option<Body>,
+    response: Option<Body>,
+    // every status the route documents
+    statuses: &'static [u16],
+}
+
+/**
+ * Every mounted route, in the order they are mounted
+ */
+fn operations() -> Vec<Operation> {
+    use Auth::{Admin, User};
+    use Body::*;
+    vec![
+        Operation {
+            method: "post",
+            path: "/user/create",
+            summary: "Create a new user",
+            auth: Auth::None,
+            params: &[],
+            request: Some(Json("CreateUserRequest")),
+            response: Some(Text),
+            statuses: &[200, 201, 400, 401, 403, 409, 500],
+        },
+        Operation {
+            method: "post",
+            path: "/user/relationship",
+            summary: "Send a relationship and encrypted auth secret to another user",
+            auth: User,
+            params: &[],
+            request: Some(Json("NewRelationshipRequest")),
+            response: None,
+            statuses: &[201, 400, 401, 403, 404, 409, 413],
+        },
+        Operation {
+            method: "post",
+            path: "/user/relationships/batch",
+            summary: "Send many relationships at once",
+            auth: User,
+            params: &[],
+            request: Some(JsonArray("NewRelationshipRequest")),
+            response: Some(JsonArray("BatchRelationshipResult")),
+            statuses: &[200, 401, 413, 500],
+        },
+        Operation {
+            method: "put",
+            path: "/user/relationship/{username}",
+            summary: "Replace the auth secret shared in an existing relationship",
+            auth: User,
+            params: &[("username", "path", "string")],
+            request: Some(Json("UpdateRelationshipRequest")),
+            response: None,
+            statuses: &[200, 401, 403, 404, 500],
+        },
+        Operation {
+            method: "delete",
+            path: "/user/relationship/{username}",
+            summary: "Delete a relationship the caller sent",
+            auth: User,
+            params: &[("username", "path", "string")],
+            request: None,
+            response: None,
+            statuses: &[200, 401, 403, 404, 500],
+        },
+        Operation {
+            method: "delete",
+            path: "/user/relationship/sent/{username}",
+            summary: "Withdraw a relationship the caller sent that was not sent back",
+            auth: User,
+            params: &[("username", "path", "string")],
+            request: None,
+            response: None,
+            statuses: &[200, 401, 404, 409, 500],
+        },
+        Operation {
+            method: "get",
+            path: "/user/challenge",
+            summary: "Issue a single-use user creation challenge",
+            auth: Auth::None,
+            params: &[],
+            request: None,
+            response: Some(Text),
+            statuses: &[200],
+        },
+        Operation {
+            method: "get",
+            path: "/user/{username}",
+            summary: "Return the public view (username and pubkey) of a user",
+            auth: Auth::None,
+            params: &[("username", "path", "string")],
+            request: None,
+            response: Some(JsonValue("object")),
+            statuses: &[200, 404, 500],
+        },
+        Operation {
+            method: "get",
+            path: "/user/{username}/full",
+            summary: "Return the full user document to the user it belongs to",
+            auth: User,
+            params: &[("username", "path", "string")],
+            request: None,
+            response: Some(JsonValue("object")),
+            statuses: &[200, 401, 403, 404, 500],
+        },
+        Operation {
+            method: "post",
+            path: "/user/nonce",
+            summary: "Recover the current nonce of a user with a signature over the username",
+            auth: Auth::None,
+            params: &[],
+            request: Some(Json("GetNonceRequest")),
+            response: Some(Text),
+            statuses: &[200, 400, 404, 500],
+        },
+        Operation {
+            method: "get",
+            path: "/user/{username}/pubkey",
+            summary: "Return the hex encoded public key of a user",
+            auth: Auth::None,
+            params: &[("username", "path", "string")],
+            request: None,
+            response: Some(Text),
+            statuses: &[200, 404, 500],
+        },
+        Operation {
+            method: "get",
+            path: "/user/{username}/within/{phrase_hash}/{n}",
+            summary: "Return a server-signed answer to whether the caller is within n degrees",
+            auth: User,
+            params: &[
+                ("username", "path", "string"),
+                ("phrase_hash", "path", "string"),
+                ("n", "path", "integer"),
+            ],
+            request: None,
+            response: Some(Json("SignedClaim")),
+            statuses: &[200, 400, 401, 403, 500],
+        },
+        Operation {
+            method: "get",
+            path: "/user/by-pubkey/{pubkey}",
+            summary: "Return the username of the user with a hex encoded public key",
+            auth: Auth::None,
+            params: &[("pubkey", "path", "string")],
+            request: None,
+            response: Some(Text),
+            statuses: &[200, 400, 404, 429, 500],
+        },
+        Operation {
+            method: "get",
+            path: "/user/relationship/{username}",
+            summary: "Return the relationship between the caller and another user",
+            auth: User,
+            params: &[("username", "path", "string")],
+            request: None,
+            response: Some(Json("RelationshipData")),
+            statuses: &[200, 401, 404, 500],
+        },
+        Operation {
+            method: "get",
+            path: "/user/relationship/{username}/diagnose",
+            summary: "Check the stored encryption of a relationship without revealing the secret",
+            auth: User,
+            params: &[("username", "path", "string")],
+            request: None,
+            response: Some(Json("RelationshipDiagnostics")),
+            statuses: &[200, 401, 404, 500],
+        },
+        Operation {
+            method: "get",
+            path: "/user/relationships/full",
+            summary: "Return every relationship of the caller labeled sent or received",
+            auth: User,
+            params: &[],
+            request: None,
+            response: Some(JsonArray("RelationshipSummary")),
+            statuses: &[200, 401, 500],
+        },
+        Operation {
+            method: "get",
+            path: "/user/secrets",
+            summary: "Return every auth secret encrypted to the caller",
+            auth: User,
+            params: &[],
+            request: None,
+            response: Some(JsonArray("ReceivedAuthSecret")),
+            statuses: &[200, 401, 500],
+        },
+        Operation {
+            method: "post",
+            path: "/user/session",
+            summary: "Issue a session token that authenticates in place of a signed nonce",
+            auth: User,
+            params: &[],
+            request: None,
+            response: Some(Json("SessionToken")),
+            statuses: &[201, 401, 403, 500],
+        },
+        Operation {
+            method: "get",
+            path: "/user/sessions",
+            summary: "List the caller's active sessions",
+            auth: User,
+            params: &[],
+            request: None,
+            response: Some(JsonArray("SessionData")),
+            statuses: &[200, 401, 500],
+        },
+        Operation {
+            method: "delete",
+            path: "/user/session/{id}",
+            summary: "Revoke one of the caller's sessions",
+            auth: User,
+            params: &[("id", "path", "string")],
+            request: None,
+            response: None,
+            statuses: &[200, 401, 404, 500],
+        },
+        Operation {
+            method: "get",
+            path: "/relationship/exists/{from}/{to}",
+            summary: "Check whether a relationship exists between two users",
+            auth: User,
+            params: &[("from", "path", "string"), ("to", "path", "string")],
+            request: None,
+            response: Some(JsonValue("boolean")),
+            statuses: &[200, 401, 403, 404, 500],
+        },
+        Operation {
+            method: "get",
+            path: "/user/degrees",
+            summary: "Return the caller's degree of separation for every phrase",
+            auth: User,
+            params: &[],
+            request: None,
+            response: Some(JsonArray("DegreeData")),
+            statuses: &[200, 401, 404, 500],
+        },
+        Operation {
+            method: "get",
+            path: "/user/degree/{degree}",
+            summary: "Return the phrases the caller has proven at a degree of separation",
+            auth: User,
+            params: &[("degree", "path", "integer")],
+            request: None,
+            response: Some(JsonArray("DegreeData")),
+            statuses: &[200, 400, 401, 404, 500],
+        },
+        Operation {
+            method: "get",
+            path: "/user/degrees/since/{timestamp}",
+            summary: "Return the caller's degrees proven since a unix timestamp in milliseconds",
+            auth: User,
+            params: &[("timestamp", "path", "integer")],
+            request: None,
+            response: Some(JsonArray("DegreeData")),
+            statuses: &[200, 401, 404, 500],
+        },
+        Operation {
+            method: "get",
+            path: "/user/phrase/{phrase_hash}/path",
+            summary: "Return the chain of degrees from a phrase's origin to the caller's proof",
+            auth: User,
+            params: &[("phrase_hash", "path", "string")],
+            request: None,
+            response: Some(JsonArray("DegreeData")),
+            statuses: &[200, 400, 401, 404, 500],
+        },
+        Operation {
+            method: "get",
+            path: "/user/graph",
+            summary: "Return the caller's connection graph up to a depth",
+            auth: User,
+            params: &[("depth", "query", "integer")],
+            request: None,
+            response: Some(Json("ConnectionGraph")),
+            statuses: &[200, 401, 404, 500],
+        },
+        Operation {
+            method: "get",
+            path: "/user/dashboard",
+            summary: "Return everything a client needs on app open",
+            auth: User,
+            params: &[],
+            request: None,
+            response: Some(Json("Dashboard")),
+            statuses: &[200, 401, 404, 500],
+        },
+        Operation {
+            method: "get",
+            path: "/users/active",
+            summary: "Return the users active since a unix timestamp in milliseconds",
+            auth: Auth::None,
+            params: &[("since", "query", "integer")],
+            request: None,
+            response: Some(JsonValue("array")),
+            statuses: &[200, 500],
+        },
+        Operation {
+            method: "post",
+            path: "/proof/create",
+            summary: "Create a new phrase with a degree 1 proof",
+            auth: User,
+            params: &[],
+            request: Some(Bincode("NewPhraseRequest")),
+            response: None,
+            statuses: &[201, 400, 401, 403, 404, 409, 500, 503, 504],
+        },
+        Operation {
+            method: "post",
+            path: "/proof/create/batch",
+            summary: "Create several new phrases at once",
+            auth: User,
+            params: &[],
+            request: Some(Bincode("NewPhraseBatchRequest")),
+            response: None,
+            statuses: &[201, 400, 401, 403, 404, 409, 413, 500, 503, 504],
+        },
+        Operation {
+            method: "post",
+            path: "/proof/create/anonymous",
+            summary: "Create a new phrase without revealing its originator",
+            auth: User,
+            params: &[],
+            request: Some(Bincode("NewPhraseRequest")),
+            response: None,
+            statuses: &[201, 400, 401, 403, 404, 409, 500, 503, 504],
+        },
+        Operation {
+            method: "post",
+            path: "/proof/create/knowledge",
+            summary: "Prove knowledge of a phrase without joining its chain",
+            auth: User,
+            params: &[],
+            request: Some(Bincode("NewPhraseRequest")),
+            response: None,
+            statuses: &[201, 400, 401, 403, 404, 409, 500, 503, 504],
+        },
+        Operation {
+            method: "post",
+            path: "/proof/continue",
+            summary: "Build a degree proof from a previous proof",
+            auth: User,
+            params: &[],
+            request: Some(Bincode("DegreeProofRequest")),
+            response: None,
+            statuses: &[201, 400, 401, 403, 404, 409, 500, 503, 504],
+        },
+        Operation {
+            method: "post",
+            path: "/proof/verify",
+            summary: "Verify a proof without storing it",
+            auth: Auth::None,
+            params: &[],
+            request: Some(Bincode("VerifyProofRequest")),
+            response: Some(Json("ProofVerification")),
+            statuses: &[200, 400, 429, 503, 504],
+        },
+        Operation {
+            method: "post",
+            path: "/proof/upload/init",
+            summary: "Begin a chunked upload of a phrase or degree proof request body",
+            auth: User,
+            params: &[("kind", "query", "string")],
+            request: None,
+            response: Some(Text),
+            statuses: &[201, 401, 429],
+        },
+        Operation {
+            method: "put",
+            path: "/proof/upload/{id}/{index}",
+            summary: "Store one chunk of an in-progress upload",
+            auth: User,
+            params: &[("id", "path", "string"), ("index", "path", "integer")],
+            request: Some(Bytes),
+            response: None,
+            statuses: &[200, 401, 404, 413],
+        },
+        Operation {
+            method: "post",
+            path: "/proof/upload/{id}/finalize",
+            summary: "Reassemble an upload and handle it as the route chosen at init",
+            auth: User,
+            params: &[("id", "path", "string")],
+            request: None,
+            response: None,
+            statuses: &[201, 400, 401, 403, 404, 409, 413, 500, 503, 504],
+        },
+        Operation {
+            method: "get",
+            path: "/proof/available",
+            summary: "Return the oids of proofs the caller can build a new degree proof from",
+            auth: User,
+            params: &[],
+            request: None,
+            response: Some(JsonValue("array")),
+            statuses: &[200, 401, 404, 500],
+        },
+        Operation {
+            method: "get",
+            path: "/proof/{username}/available/count",
+            summary: "Return how many proofs the caller can build a new degree proof from",
+            auth: User,
+            params: &[("username", "path", "string")],
+            request: None,
+            response: Some(JsonValue("integer")),
+            statuses: &[200, 401, 403, 500],
+        },
+        Operation {
+            method: "get",
+            path: "/proof/params/{oid}",
+            summary: "Return the signed proving data needed to build from a proof",
+            auth: User,
+            params: &[("oid", "path", "string")],
+            request: None,
+            response: Some(Json("ProvingData")),
+            statuses: &[200, 304, 401, 403, 404, 500],
+        },
+        Operation {
+            method: "post",
+            path: "/proof/autoparams",
+            summary: "Return the signed proving data for the best proof to build from",
+            auth: User,
+            params: &[],
+            request: Some(Json("AutoParamsRequest")),
+            response: Some(Json("ProvingData")),
+            statuses: &[200, 401, 404, 500],
+        },
+        Operation {
+            method: "get",
+            path: "/proof/chain/{phrase_hash}",
+            summary: "Return the proofs linked to a phrase hash",
+            auth: Auth::None,
+            params: &[("phrase_hash", "path", "string")],
+            request: None,
+            response: Some(JsonArray("ChainProof")),
+            statuses: &[200, 400, 500],
+        },
+        Operation {
+            method: "get",
+            path: "/proof/{oid}/children",
+            summary: "Return the proofs built directly on top of a proof",
+            auth: User,
+            params: &[("oid", "path", "string")],
+            request: None,
+            response: Some(JsonArray("ProofChild")),
+            statuses: &[200, 401, 404, 500],
+        },
+        Operation {
+            method: "get",
+            path: "/proof/{oid}/bytes",
+            summary: "Return the raw compressed bytes of a proof",
+            auth: User,
+            params: &[("oid", "path", "string")],
+            request: None,
+            response: Some(Bytes),
+            statuses: &[200, 401, 403, 404, 500],
+        },
+        Operation {
+            method: "get",
+            path: "/proof/{oid}",
+            summary: "Return the metadata of a proof",
+            auth: Auth::None,
+            params: &[("oid", "path", "string")],
+            request: None,
+            response: Some(Json("ProofMetadata")),
+            statuses: &[200, 404, 500],
+        },
+        Operation {
+            method: "get",
+            path: "/phrase/{phrase_hash}/originator",
+            summary: "Return the user who created a phrase",
+            auth: Auth::None,
+            params: &[("phrase_hash", "path", "string")],
+            request: None,
+            response: Some(Json("PhraseOriginator")),
+            statuses: &[200, 400, 404, 500],
+        },
+        Operation {
+            method: "get",
+            path: "/stats/global",
+            summary: "Return service wide counts of users, phrases, proofs, and relationships",
+            auth: Auth::None,
+            params: &[],
+            request: None,
+            response: Some(Json("GlobalStats")),
+            statuses: &[200, 500],
+        },
+        Operation {
+            method: "get",
+            path: "/stats/top-connectors",
+            summary: "Rank users by how many relationships they participate in",
+            auth: Auth::None,
+            params: &[("limit", "query", "integer")],
+            request: None,
+            response: Some(JsonArray("TopConnector")),
+            statuses: &[200, 500],
+        },
+        Operation {
+            method: "get",
+            path: "/circuit/info",
+            summary: "Return the hashes of the circuit artifacts the server verifies with",
+            auth: Auth::None,
+            params: &[],
+            request: None,
+            response: Some(Json("CircuitInfo")),
+            statuses: &[200],
+        },
+        Operation {
+            method: "get",
+            path: "/server/pubkey",
+            summary: "Return the hex encoded public key the server signs responses with",
+            auth: Auth::None,
+            params: &[],
+            request: None,
+            response: Some(Text),
+            statuses: &[200],
+        },
+        Operation {
+            method: "post",
+            path: "/verify/claim",
+            summary: "Check whether a user is within a degree of separation of a phrase",
+            auth: Auth::None,
+            params: &[],
+            request: Some(Json("VerifyClaimRequest")),
+            response: Some(JsonValue("boolean")),
+            statuses: &[200, 400, 500],
+        },
+        Operation {
+            method: "post",
+            path: "/verify/signature",
+            summary: "Check a user's signature over an arbitrary message",
+            auth: Auth::None,
+            params: &[],
+            request: Some(Json("VerifySignatureRequest")),
+            response: Some(JsonValue("boolean")),
+            statuses: &[200, 400, 404],
+        },
+        Operation {
+            method: "post",
+            path: "/admin/migrate-params",
+            summary: "Mark proofs made with the current public params as current",
+            auth: Admin,
+            params: &[],
+            request: None,
+            response: Some(Json("ParamsMigration")),
+            statuses: &[200, 401, 404, 500],
+        },
+        Operation {
+            method: "post",
+            path: "/admin/migrate-phrases",
+            summary: "Move proofs stored before PHRASE_KEY was set behind the blind index",
+            auth: Admin,
+            params: &[],
+            request: None,
+            response: Some(JsonValue("integer")),
+            statuses: &[200, 401, 404, 500],
+        },
+        Operation {
+            method: "post",
+            path: "/admin/import-chain",
+            summary: "Import a proof chain exported from another server",
+            auth: Admin,
+            params: &[],
+            request: Some(Bincode("ChainExport")),
+            response: Some(Json("ChainImport")),
+            statuses: &[200, 400, 401, 403, 404, 409, 413, 500, 503, 504],
+        },
+        Operation {
+            method: "post",
+            path: "/admin/phrase/{phrase_hash}/freeze",
+            summary: "Freeze a phrase so it can no longer be proven",
+            auth: Admin,
+            params: &[("phrase_hash", "path", "string")],
+            request: None,
+            response: None,
+            statuses: &[200, 400, 401, 404, 500],
+        },
+        Operation {
+            method: "post",
+            path: "/admin/invite/{username}",
+            summary: "Mint an invite token for a username while registration is closed",
+            auth: Admin,
+            params: &[("username", "path", "string")],
+            request: None,
+            response: Some(Text),
+            statuses: &[201, 401, 404],
+        },
+        Operation {
+            method: "get",
+            path: "/admin/export-chain/{phrase_hash}",
+            summary: "Export the proof chain of a phrase",
+            auth: Admin,
+            params: &[("phrase_hash", "path", "string")],
+            request: None,
+            response: Some(Json("ChainExport")),
+            statuses: &[200, 400, 401, 404, 500],
+        },
+        Operation {
+            method: "get",
+            path: "/admin/auth-hash-reuse",
+            summary: "Report auth hashes reused across the degree 1 proofs of different phrases",
+            auth: Admin,
+            params: &[],
+            request: None,
+            response: Some(JsonArray("AuthHashReuse")),
+            statuses: &[200, 401, 404, 500],
+        },
+    ]
+}
+
+/**
+ * Build the json schema of every request and response type, keyed by type name
+ *
+ * @returns - the components.schemas object of the spec
+ */
+fn schemas() -> Value {
+    let mut gen = SchemaSettings::openapi3().into_generator();
+    // requests
+    gen.subschema_for::<CreateUserRequest>();
+    gen.subschema_for::<GetNonceRequest>();
+    gen.subschema_for::<NewRelationshipRequest>();
+    gen.subschema_for::<UpdateRelationshipRequest>();
+    gen.subschema_for::<NewPhraseRequest>();
+    gen.subschema_for::<NewPhraseBatchRequest>();
+    gen.subschema_for::<DegreeProofRequest>();
+    gen.subschema_for::<VerifyProofRequest>();
+    gen.subschema_for::<AutoParamsRequest>();
+    gen.subschema_for::<VerifyClaimRequest>();
+    gen.subschema_for::<VerifySignatureRequest>();
+    // responses
+    gen.subschema_for::<AuthHashReuse>();
+    gen.subschema_for::<AvailableProof>();
+    gen.subschema_for::<BatchRelationshipResult>();
+    gen.subschema_for::<ChainExport>();
+    gen.subschema_for::<ChainImport>();
+    gen.subschema_for::<ChainProof>();
+    gen.subschema_for::<CircuitInfo>();
+    gen.subschema_for::<ConnectionGraph>();
+    gen.subschema_for::<Dashboard>();
+    gen.subschema_for::<DegreeData>();
+    gen.subschema_for::<GlobalStats>();
+    gen.subschema_for::<ParamsMigration>();
+    gen.subschema_for::<PhraseOriginator>();
+    gen.subschema_for::<ProofChild>();
+    gen.subschema_for::<ProofMetadata>();
+    gen.subschema_for::<ProofVerification>();
+    gen.subschema_for::<ProvingData>();
+    gen.subschema_for::<ReceivedAuthSecret>();
+    gen.subschema_for::<RelationshipData>();
+    gen.subschema_for::<RelationshipDiagnostics>();
+    gen.subschema_for::<RelationshipSummary>();
+    gen.subschema_for::<SessionData>();
+    gen.subschema_for::<SessionToken>();
+    gen.subschema_for::<SignedClaim>();
+    gen.subschema_for::<TopConnector>();
+    // error bodies
+    gen.subschema_for::<GrapevineServerError>();
+    serde_json::to_value(gen.take_definitions()).unwrap()
+}
+
+/**
+ * Describe a request or response body as an openapi content object
+ *
+ * @param body - how the body is encoded
+ * @returns - the content object keyed by media type
+ */
+fn content(body: &Body) -> Value {
+    let schema_ref = |name: &str| json!({ "$ref": format!("#/components/schemas/{}", name) });
+    match body {
+        Body::Json(name) => json!({ "application/json": { "schema": schema_ref(name) } }),
+        Body::JsonArray(name) => json!({
+            "application/json": { "schema": { "type": "array", "items": schema_ref(name) } }
+        }),
+        Body::Bincode(name) => json!({
+            "application/octet-stream": {
+                "schema": { "type": "string", "format": "binary" },
+                "x-bincode": schema_ref(name)
+            }
+        }),
+        Body::JsonValue(kind) => json!({ "application/json": { "schema": { "type": kind } } }),
+        Body::Text => json!({ "text/plain": { "schema": { "type": "string" } } }),
+        Body::Bytes => json!({
+            "application/octet-stream": { "schema": { "type": "string", "format": "binary" } }
+        }),
+    }
+}
+
+/**
+ * Describe the responses of a route
+ * @dev error responses carry a json GrapevineServerError, except 404 and 413 which are text and
+ *      304 which has no body
+ *
+ * @param operation - the route to describe
+ * @returns - the responses object keyed by status
+ */
+fn responses(operation: &Operation) -> Value {
+    let mut responses = Map::new();
+    for status in operation.statuses {
+        let mut response = Map::new();
+        let description = rocket::http::Status::from_code(*status)
+            .and_then(|status| status.reason())
+            .unwrap_or("");
+        response.insert(String::from("description"), json!(description));
+        let body = match status {
+            200..=299 => operation.response.as_ref().map(content),
+            304 => None,
+            404 | 413 => Some(content(&Body::Text)),
+            _ => Some(content(&Body::Json("GrapevineServerError"))),
+        };
+        if let Some(body) = body {
+            response.insert(String::from("content"), body);
+        }
+        responses.insert(status.to_string(), Value::Object(response));
+    }
+    Value::Object(responses)
+}
+
+/**
+ * Assemble the OpenAPI 3 document for every mounted route
+ *
+ * @returns - the OpenAPI document
+ */
+pub fn spec() -> Value {
+    let mut paths = Map::new();
+    for operation in operations() {
+        let mut op = Map::new();
+        op.insert(String::from("summary"), json!(operation.summary));
+        let parameters: Vec<Value> = operation
+            .params
+            .iter()
+            .map(|(name, location, kind)| {
+                json!({
+                    "name": name,
+                    "in": location,
+                    "required": true,
+                    "schema": { "type": kind }
+                })
+            })
+            .collect();
+        op.insert(String::from("parameters"), json!(parameters));
+        if let Some(body) = &operation.request {
+            op.insert(
+                String::from("requestBody"),
+                json!({ "required": true, "content": content(body) }),
+            );
+        }
+        op.insert(String::from("responses"), responses(&operation));
+        match operation.auth {
+            Auth::User => op.insert(
+                String::from("security"),
+                json!([
+                    { "signedNonce": [], "nonceUsername": [] },
+                    { "session": [], "nonceUsername": [] }
+                ]),
+            ),
+            Auth::Admin => op.insert(String::from("security"), json!([{ "adminKey": [] }])),
+            Auth::None => None,
+        };
+        let path = paths
+            .entry(operation.path)
+            .or_insert_with(|| Value::Object(Map::new()));
+        path.as_object_mut()
+            .unwrap()
+            .insert(String::from(operation.method), Value::Object(op));
+    }
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "Grapevine",
+            "version": env!("CARGO_PKG_VERSION")
+        },
+        "paths": paths,
+        "components": {
+            "schemas": schemas(),
+            "securitySchemes": {
+                "nonceUsername": { "type": "apiKey", "in": "header", "name": USERNAME_HEADER },
+                "signedNonce": { "type": "apiKey", "in": "header", "name": AUTHORIZATION_HEADER },
+                "session": { "type": "apiKey", "in": "header", "name": "X-Session" },
+                "adminKey": { "type": "apiKey", "in": "header", "name": "X-Admin-Key" }
+            }
+        }
+    })
+}
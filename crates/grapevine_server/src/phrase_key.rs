@@ -0,0 +1,110 @@
+use grapevine_common::crypto::{decrypt, encrypt};
+use hmac::{Hmac, Mac};
+use sha3::Sha3_256;
+
+/**
+ * The key phrase hashes are encrypted at rest and blind indexed under
+ * @notice only the indexed phrase_hash field is protected. The stored proof bytes still output
+ *         the phrase hash, so anyone who can read and verify the proofs can recover it
+ * @dev proofs store the blind index in place of the phrase hash so equality queries and the
+ *      chain lookups joining proofs by phrase still work, alongside the encrypted hash
+ */
+#[derive(Clone)]
+pub struct PhraseKey {
+    // the aes-gcm-128 key phrase hashes are encrypted under
+    encryption_key: [u8; 16],
+    // the hmac key of the blind index
+    index_key: [u8; 16],
+}
+
+impl PhraseKey {
+    /**
+     * Create a phrase key
+     *
+     * @param key - the 32 byte secret, split into the encryption and blind index keys
+     * @returns - the phrase key
+     */
+    pub fn new(key: [u8; 32]) -> Self {
+        PhraseKey {
+            encryption_key: key[..16].try_into().unwrap(),
+            index_key: key[16..].try_into().unwrap(),
+        }
+    }
+
+    /**
+     * Read the phrase key from the environment
+     * @notice only proofs stored while a key is set are encrypted, and changing the key makes
+     *         proofs stored under the old key unsearchable. Proofs stored before a key was first
+     *         set are unsearchable until moved behind the blind index by /admin/migrate-phrases
+     *
+     * @returns - the key from PHRASE_KEY (hex encoded 32 bytes), or None to store phrase hashes
+     *            in plaintext
+     */
+    pub fn from_env() -> Option<Self> {
+        std::env::var("PHRASE_KEY").ok().map(|key| {
+            let key = hex::decode(key)
+                .ok()
+                .and_then(|key| key.try_into().ok())
+                .expect("PHRASE_KEY must be a hex encoded 32 byte key");
+            Self::new(key)
+        })
+    }
+
+    /**
+     * Compute the blind index a phrase hash is stored and queried under
+     *
+     * @param phrase_hash - the poseidon hash of the phrase
+     * @returns - the hmac-sha3-256 of the phrase hash
+     */
+    pub fn index(&self, phrase_hash: &[u8; 32]) -> [u8; 32] {
+        let mut mac = Hmac::<Sha3_256>::new_from_slice(&self.index_key).unwrap();
+        mac.update(phrase_hash);
+        mac.finalize().into_bytes().into()
+    }
+
+    /**
+     * Encrypt a phrase hash for storage
+     *
+     * @param phrase_hash - the poseidon hash of the phrase
+     * @returns - the aes-gcm nonce followed by the ciphertext and tag
+     */
+    pub fn encrypt(&self, phrase_hash: &[u8; 32]) -> Vec<u8> {
+        let (nonce, ciphertext) = encrypt(&self.encryption_key, phrase_hash);
+        [&nonce[..], &ciphertext[..]].concat()
+    }
+
+    /**
+     * Decrypt a stored phrase hash
+     *
+     * @param sealed - the nonce, ciphertext, and tag as returned by encrypt
+     * @returns - the phrase hash, or None if the bytes are malformed or fail authentication
+     */
+    pub fn decrypt(&self, sealed: &[u8]) -> Option<[u8; 32]> {
+        let nonce: [u8; 12] = sealed.get(..12)?.try_into().ok()?;
+        let ciphertext: [u8; 48] = sealed.get(12..)?.try_into().ok()?;
+        decrypt(&self.encryption_key, &nonce, &ciphertext).ok()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_phrase_key() {
+        let key = PhraseKey::new([7u8; 32]);
+        let phrase_hash = [3u8; 32];
+        // the blind index is deterministic so it can be queried, and hides the hash
+        assert_eq!(key.index(&phrase_hash), key.index(&phrase_hash));
+        assert_ne!(key.index(&phrase_hash), phrase_hash);
+        assert_ne!(
+            key.index(&phrase_hash),
+            PhraseKey::new([8u8; 32]).index(&phrase_hash)
+        );
+        // the encryption round trips and rejects tampering
+        let mut sealed = key.encrypt(&phrase_hash);
+        assert_eq!(key.decrypt(&sealed), Some(phrase_hash));
+        sealed[20] ^= 1;
+        assert_eq!(key.decrypt(&sealed), None);
+    }
+}
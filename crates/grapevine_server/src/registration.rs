@@ -0,0 +1,86 @@
+use grapevine_common::crypto::new_challenge;
+use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/** How long an invite token can be redeemed after it is minted */
+pub const INVITE_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/** Server configuration controlling who can create new users */
+pub struct RegistrationConfig {
+    // whether anyone can create a user without an invite
+    pub open: bool,
+    // the key invite tokens are signed with
+    pub invite_secret: Vec<u8>,
+    // the key required to mint invites (minting is disabled if None)
+    pub admin_key: Option<String>,
+}
+
+/** The claims of an invite token, which only admits the username it was minted for */
+#[derive(Debug, Serialize, Deserialize)]
+struct InviteClaims {
+    sub: String,
+    exp: u64,
+}
+
+impl RegistrationConfig {
+    /**
+     * Read the registration config from the environment
+     * @notice if INVITE_SECRET is unset a random secret is used, so invites do not survive restarts
+     *
+     * @returns - the config from REGISTRATION_OPEN (default true), INVITE_SECRET, and ADMIN_KEY
+     */
+    pub fn from_env() -> Self {
+        let open = match std::env::var("REGISTRATION_OPEN") {
+            Ok(open) => open != "false",
+            Err(_) => true,
+        };
+        let invite_secret = match std::env::var("INVITE_SECRET") {
+            Ok(secret) => secret.into_bytes(),
+            Err(_) => new_challenge().to_vec(),
+        };
+        RegistrationConfig {
+            open,
+            invite_secret,
+            admin_key: std::env::var("ADMIN_KEY").ok(),
+        }
+    }
+
+    /**
+     * Mint an invite token that lets a user register while registration is closed
+     *
+     * @param username - the username the invite admits
+     * @returns - the signed invite token
+     */
+    pub fn mint_invite(&self, username: &str) -> String {
+        let expiry = SystemTime::now() + INVITE_TTL;
+        let claims = InviteClaims {
+            sub: username.to_string(),
+            exp: expiry.duration_since(UNIX_EPOCH).unwrap().as_secs(),
+        };
+        encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(&self.invite_secret),
+        )
+        .unwrap()
+    }
+
+    /**
+     * Check that an invite token was minted by this server for a username and has not expired
+     *
+     * @param token - the invite token included in the user creation request
+     * @param username - the username being registered
+     * @returns - true if the invite admits the username
+     */
+    pub fn verify_invite(&self, token: &str, username: &str) -> bool {
+        match decode::<InviteClaims>(
+            token,
+            &DecodingKey::from_secret(&self.invite_secret),
+            &Validation::default(),
+        ) {
+            Ok(data) => data.claims.sub == username,
+            Err(_) => false,
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use crate::degree_cache;
+use crate::mongo::GrapevineDB;
+use grapevine_common::errors::GrapevineServerError;
+use mongodb::bson::DateTime;
+use std::time::{Duration, SystemTime};
+
+/** How often superseded proofs past the retention period are cleaned up in the background */
+pub const CLEANUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/** Server configuration for how long superseded proofs are kept */
+pub struct RetentionConfig {
+    // how long a superseded proof is kept before it is removed (cleanup is disabled if None)
+    pub max_age: Option<Duration>,
+}
+
+impl RetentionConfig {
+    /**
+     * Read the retention config from the environment
+     *
+     * @returns - the config from PROOF_RETENTION_DAYS (cleanup is disabled if unset or invalid)
+     */
+    pub fn from_env() -> Self {
+        let max_age = std::env::var("PROOF_RETENTION_DAYS")
+            .ok()
+            .and_then(|days| days.parse::<u64>().ok())
+            .map(|days| Duration::from_secs(days * 24 * 60 * 60));
+        RetentionConfig { max_age }
+    }
+}
+
+/**
+ * Remove every superseded proof older than the retention period
+ *
+ * @param db - the database to remove superseded proofs from
+ * @param max_age - how long a superseded proof is kept after it was created
+ * @returns - the number of proofs removed
+ */
+pub async fn cleanup(db: &GrapevineDB, max_age: Duration) -> Result<u64, GrapevineServerError> {
+    let cutoff = SystemTime::now()
+        .checked_sub(max_age)
+        .unwrap_or(SystemTime::UNIX_EPOCH);
+    let removed = db.remove_superseded_proofs(DateTime::from(cutoff)).await?;
+    // relinked chains can change which proofs are the lowest degree for a connection
+    if removed > 0 {
+        degree_cache::invalidate_all(db);
+    }
+    Ok(removed)
+}
+
+/**
+ * Periodically remove superseded proofs past the retention period every CLEANUP_INTERVAL
+ * @notice returns immediately if no retention period is configured
+ *
+ * @param config - the retention config to clean up with
+ */
+pub async fn cleanup_task(config: RetentionConfig) {
+    let max_age = match config.max_age {
+        Some(max_age) => max_age,
+        None => return,
+    };
+    let db = GrapevineDB::init().await;
+    let mut interval = tokio::time::interval(CLEANUP_INTERVAL);
+    loop {
+        interval.tick().await;
+        match cleanup(&db, max_age).await {
+            Ok(removed) => tracing::info!("Removed {} superseded proofs", removed),
+            Err(e) => tracing::warn!("Error removing superseded proofs: {:?}", e),
+        }
+    }
+}
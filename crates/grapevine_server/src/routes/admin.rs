@@ -0,0 +1,512 @@
+use crate::catchers::{GrapevineResponse, Negotiated};
+use crate::degree_cache;
+use crate::guards::AdminGuard;
+use crate::mongo::GrapevineDB;
+use crate::registration::RegistrationConfig;
+use crate::routes::proof::deserialize_request;
+use crate::uploads::MAX_PROOF_SIZE;
+use crate::utils::{
+    verify_proofs_with_timeout, CIRCUIT_INFO, MAX_RELATIONSHIPS, VERIFICATION_TIMEOUT,
+};
+use crate::verification::ProofBackend;
+use grapevine_circuits::outputs::{degree_iterations, PublicOutputs};
+use grapevine_circuits::utils::decompressed_size;
+use grapevine_common::errors::GrapevineServerError;
+use grapevine_common::http::responses::{AuthHashReuse, ChainExport, ChainImport, ParamsMigration};
+use grapevine_common::models::{proof::DegreeProof, relationship::Relationship, user::User};
+use grapevine_common::nonce::Nonce;
+use grapevine_common::utils::fr_from_hex;
+use grapevine_common::MAX_CHAIN_IMPORT;
+use mongodb::bson::{oid::ObjectId, DateTime};
+use rocket::{data::ToByteUnit, http::Status, tokio::io::AsyncReadExt, Data};
+use rocket::{serde::json::Json, State};
+use std::collections::{HashMap, HashSet};
+
+/// POST REQUESTS ///
+
+/**
+ * Record the active public params version on every proof stored without one
+ * @notice: run this before replacing public_params.json so proofs made under the outgoing params
+ *          are attributed to them. Once the new params are served, building on those proofs is
+ *          rejected and their creators must re-prove with the new artifacts
+ *
+ * @return - a ParamsMigration struct containing:
+ *         * version: the active params version that was recorded
+ *         * migrated: the number of proofs that had no params version
+ * @return status:
+ *         - 200 if success
+ *         - 401 if the admin key is missing or invalid
+ *         - 404 if the admin api is disabled (no admin key is configured)
+ *         - 500 if db fails or other unknown issue
+ */
+#[post("/migrate-params")]
+pub async fn migrate_params(
+    _admin: AdminGuard,
+    db: &State<GrapevineDB>,
+) -> Result<Json<ParamsMigration>, GrapevineResponse> {
+    let version = CIRCUIT_INFO.public_params_hash.clone();
+    match db.migrate_params_version(&version).await {
+        Ok(migrated) => Ok(Json(ParamsMigration { version, migrated })),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/**
+ * Move proofs stored before PHRASE_KEY was set behind the blind index
+ * @notice: proofs stored without a phrase key are only searchable by their plaintext phrase hash,
+ *          so run this once after first setting PHRASE_KEY. Frozen phrases are not re-indexed,
+ *          so phrases frozen before the key was set must be frozen again
+ *
+ * @return - the number of proofs re-indexed (0 if no phrase key is set)
+ * @return status:
+ *         - 200 if success
+ *         - 401 if the admin key is missing or invalid
+ *         - 404 if the admin api is disabled (no admin key is configured)
+ *         - 500 if db fails or other unknown issue
+ */
+#[post("/migrate-phrases")]
+pub async fn migrate_phrases(
+    _admin: AdminGuard,
+    db: &State<GrapevineDB>,
+) -> Result<Json<u64>, GrapevineResponse> {
+    match db.migrate_phrase_index().await {
+        Ok(migrated) => Ok(Json(migrated)),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/**
+ * Import a proof chain exported from another Grapevine server
+ * @notice: every proof is re-verified and the links between proofs are checked before anything
+ *          is written, so an import that fails validation leaves the database untouched. Users
+ *          in the chain that do not exist are created, and relationships that already exist are
+ *          kept as is
+ *
+ * @param data - binary serialized ChainExport as returned by /admin/export-chain/<phrase_hash>
+ * @return - a ChainImport struct containing:
+ *         * proofs: the new oid of each imported proof, in the order of the export
+ * @return status:
+ *         - 200 if success
+ *         - 400 if deserialization fails, a proof fails verification, or the chain is not linked
+ *         - 401 if the admin key is missing or invalid
+ *         - 404 if the admin api is disabled (no admin key is configured)
+ *         - 403 if the phrase has been frozen
+ *         - 409 if the phrase already exists or a username is taken by a different pubkey
+ *         - 413 if the chain exceeds MAX_CHAIN_IMPORT proofs
+ *         - 500 if db fails or other unknown issue
+ *         - 503 if no verifier frees up within the verification queue timeout
+ *         - 504 if proof verification does not finish within the verification timeout
+ */
+#[post("/import-chain", data = "<data>")]
+pub async fn import_chain(
+    _admin: AdminGuard,
+    data: Data<'_>,
+    db: &State<GrapevineDB>,
+    backend: &State<ProofBackend>,
+) -> Result<Json<ChainImport>, GrapevineResponse> {
+    let mut buffer = Vec::new();
+    let mut stream = data.open((*MAX_PROOF_SIZE * MAX_CHAIN_IMPORT).bytes());
+    if let Err(e) = stream.read_to_end(&mut buffer).await {
+        println!("Error reading request body: {:?}", e);
+        return Err(GrapevineResponse::TooLarge(format!(
+            "Request body execeeds {} bytes",
+            *MAX_PROOF_SIZE * MAX_CHAIN_IMPORT
+        )));
+    }
+    let chain = match deserialize_request::<ChainExport>(&buffer, "ChainExport") {
+        Ok(chain) => chain,
+        Err(e) => return Err(e.into()),
+    };
+    if chain.proofs.len() > MAX_CHAIN_IMPORT {
+        return Err(GrapevineServerError::BatchTooLarge(MAX_CHAIN_IMPORT).into());
+    }
+    // check the chain is linked before spending time verifying proofs
+    check_chain_links(&chain)?;
+    // verify every proof in parallel and that it outputs the phrase and auth hash it was exported
+    // with
+    let batch = chain
+        .proofs
+        .iter()
+        .map(|proof| (proof.proof.clone(), degree_iterations(proof.degree)))
+        .collect();
+    let verify_results =
+        match verify_proofs_with_timeout(backend, batch, *VERIFICATION_TIMEOUT).await {
+            Ok(verify_results) => verify_results,
+            Err(e) => return Err(e.into()),
+        };
+    for (proof, verify_res) in chain.proofs.iter().zip(verify_results) {
+        match verify_res.and_then(|res| PublicOutputs::from_verification(&res)) {
+            Ok(outputs)
+                if outputs.phrase_hash.to_bytes() == proof.phrase_hash
+                    && outputs.auth_hash.to_bytes() == proof.auth_hash => {}
+            Ok(_) => {
+                return Err(GrapevineServerError::ChainInvalid(format!(
+                    "proof {} outputs do not match its exported hashes",
+                    proof.oid
+                ))
+                .into())
+            }
+            Err(e) => {
+                println!("Proof verification failed: {:?}", e);
+                return Err(e.into());
+            }
+        }
+    }
+    // reject chains that would conflict with existing data before writing anything
+    if let Err(e) = check_chain_conflicts(&chain, db).await {
+        return Err(e.into());
+    }
+    match insert_chain(&chain, db).await {
+        Ok(proofs) => {
+            degree_cache::invalidate_all(db);
+            Ok(Json(ChainImport { proofs }))
+        }
+        Err(e) => {
+            degree_cache::invalidate_all(db);
+            Err(e.into())
+        }
+    }
+}
+
+/**
+ * Freeze a phrase that is known to be leaked or abused so no new proofs can be added for it
+ * @notice: existing proofs for the phrase remain readable, but no user can originate the phrase,
+ *          extend its chain, or import it from another server
+ *
+ * @param phrase_hash - the hex encoded hash of the phrase to freeze
+ * @return status:
+ *         - 200 if the phrase is frozen (including if it was already frozen)
+ *         - 400 if the phrase hash is not a hex encoded canonical field element
+ *         - 401 if the admin key is missing or invalid
+ *         - 404 if the admin api is disabled (no admin key is configured)
+ *         - 500 if db fails or other unknown issue
+ */
+#[post("/phrase/<phrase_hash>/freeze")]
+pub async fn freeze_phrase(
+    _admin: AdminGuard,
+    phrase_hash: String,
+    db: &State<GrapevineDB>,
+) -> Result<Status, GrapevineResponse> {
+    let phrase_hash = match fr_from_hex(&phrase_hash) {
+        Ok(phrase_hash) => phrase_hash.to_bytes(),
+        Err(e) => {
+            return Err(GrapevineServerError::SerdeError(format!("phrase hash: {}", e)).into())
+        }
+    };
+    match db.freeze_phrase(&phrase_hash).await {
+        Ok(_) => Ok(Status::Ok),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/**
+ * Mint an invite token that lets a username register while registration is closed
+ * @notice: the invite is bound to the username and expires after registration::INVITE_TTL
+ *
+ * @param username - the username the invite admits
+ * @return - the invite token to include in the CreateUserRequest
+ * @return status:
+ *         - 201 if success
+ *         - 401 if the admin key is missing or invalid
+ *         - 404 if the admin api is disabled (no admin key is configured)
+ */
+#[post("/invite/<username>")]
+pub async fn mint_invite(
+    _admin: AdminGuard,
+    username: String,
+    registration: &State<RegistrationConfig>,
+) -> GrapevineResponse {
+    GrapevineResponse::Created(registration.mint_invite(&username))
+}
+
+/// GET REQUESTS ///
+
+/**
+ * Export a proof chain with the users and relationships linking it for import into another server
+ * @notice: the export contains every proof and encrypted auth secret in the chain
+ *
+ * @param phrase_hash - the hex encoded hash of the phrase at the start of the chain
+ * @return - a ChainExport struct (bincode if requested with Accept: application/octet-stream)
+ *         containing:
+ *         * users: the username and pubkey of every proof creator
+ *         * relationships: the relationships each proof was built through
+ *         * proofs: every proof in the chain, ordered so each follows the proof it builds on
+ * @return status:
+ *         - 200 if success
+ *         - 400 if the phrase hash is not a hex encoded canonical field element
+ *         - 401 if the admin key is missing or invalid
+ *         - 404 if the admin api is disabled (no admin key is configured) or no proofs exist for
+ *           the phrase
+ *         - 500 if db fails or other unknown issue
+ */
+#[get("/export-chain/<phrase_hash>")]
+pub async fn export_chain(
+    _admin: AdminGuard,
+    phrase_hash: String,
+    db: &State<GrapevineDB>,
+) -> Result<Negotiated<ChainExport>, GrapevineResponse> {
+    let phrase_hash = match fr_from_hex(&phrase_hash) {
+        Ok(phrase_hash) => phrase_hash.to_bytes(),
+        Err(e) => {
+            return Err(GrapevineServerError::SerdeError(format!("phrase hash: {}", e)).into())
+        }
+    };
+    match db.export_chain(&phrase_hash).await {
+        Ok(Some(chain)) => Ok(Negotiated(chain)),
+        Ok(None) => Err(GrapevineServerError::NoAvailableProof(hex::encode(phrase_hash)).into()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/**
+ * Report auth hashes shared by the degree 1 proofs of different phrases
+ * @notice: a degree 1 auth hash is derived from the originator's auth secret, so reuse across
+ *          phrases suggests one key seeding many phrases. Usernames are reported even for
+ *          anonymously originated phrases
+ *
+ * @return - an array of AuthHashReuse structs containing:
+ *         * auth_hash: the hex encoded auth hash seen on more than one phrase
+ *         * origins: the oid, creator, and phrase hash of each degree 1 proof with the auth hash
+ * @return status:
+ *         - 200 if success (an empty array if no auth hash is reused)
+ *         - 401 if the admin key is missing or invalid
+ *         - 404 if the admin api is disabled (no admin key is configured)
+ *         - 500 if db fails or other unknown issue
+ */
+#[get("/auth-hash-reuse")]
+pub async fn auth_hash_reuse(
+    _admin: AdminGuard,
+    db: &State<GrapevineDB>,
+) -> Result<Json<Vec<AuthHashReuse>>, GrapevineResponse> {
+    match db.find_auth_hash_reuse().await {
+        Ok(reused) => Ok(Json(reused)),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/**
+ * Check that an exported chain is internally consistent
+ *   - every proof is for the same phrase and only the first proof is degree 1
+ *   - every later proof builds on an earlier proof in the chain one degree below it
+ *   - every user is exported once, and each proof has the relationship it was built through
+ *
+ * @param chain - the exported chain to check
+ * @returns - ChainInvalid describing the first inconsistency found
+ */
+fn check_chain_links(chain: &ChainExport) -> Result<(), GrapevineServerError> {
+    let invalid = |msg: String| Err(GrapevineServerError::ChainInvalid(msg));
+    let first = match chain.proofs.first() {
+        Some(first) if first.degree == 1 && first.preceding.is_none() => first,
+        _ => return invalid(String::from("chain must start with a degree 1 proof")),
+    };
+    let usernames: HashSet<&String> = chain.users.iter().map(|user| &user.username).collect();
+    if usernames.len() != chain.users.len() {
+        return invalid(String::from("users must be unique"));
+    }
+    for relationship in chain.relationships.iter() {
+        if !usernames.contains(&relationship.sender) || !usernames.contains(&relationship.recipient)
+        {
+            return invalid(String::from("relationship participants must be exported"));
+        }
+    }
+    let relationships: HashSet<(&String, &String)> = chain
+        .relationships
+        .iter()
+        .map(|relationship| (&relationship.sender, &relationship.recipient))
+        .collect();
+    // the degree and creator of each proof checked so far
+    let mut seen: HashMap<&String, (u8, &String)> = HashMap::new();
+    for proof in chain.proofs.iter() {
+        if proof.phrase_hash != first.phrase_hash {
+            return invalid(format!("proof {} is for a different phrase", proof.oid));
+        }
+        if !usernames.contains(&proof.username) {
+            return invalid(format!("creator of proof {} is not exported", proof.oid));
+        }
+        if !seen.is_empty() {
+            let preceding = match &proof.preceding {
+                Some(preceding) => preceding,
+                None => return invalid(format!("proof {} does not build on a proof", proof.oid)),
+            };
+            match seen.get(preceding) {
+                Some((degree, sender)) if degree.checked_add(1) == Some(proof.degree) => {
+                    if !relationships.contains(&(*sender, &proof.username)) {
+                        return invalid(format!(
+                            "relationship from {} to {} is not exported",
+                            sender, proof.username
+                        ));
+                    }
+                }
+                _ => {
+                    return invalid(format!(
+                        "proof {} does not build on an earlier proof one degree below it",
+                        proof.oid
+                    ))
+                }
+            }
+        }
+        if seen
+            .insert(&proof.oid, (proof.degree, &proof.username))
+            .is_some()
+        {
+            return invalid(format!("proof {} is exported more than once", proof.oid));
+        }
+    }
+    Ok(())
+}
+
+/**
+ * Check that importing a chain would not conflict with existing data
+ *
+ * @param chain - the exported chain to import
+ * @param db - the database the chain is being imported into
+ * @returns - PhraseFrozen if the phrase has been frozen, PhraseAlreadyOriginated if the phrase
+ *            exists, or UsernameExists if a username in the chain is used by a different pubkey
+ */
+async fn check_chain_conflicts(
+    chain: &ChainExport,
+    db: &GrapevineDB,
+) -> Result<(), GrapevineServerError> {
+    let phrase_hash = chain.proofs[0].phrase_hash;
+    if db.phrase_frozen(&phrase_hash).await? {
+        return Err(GrapevineServerError::PhraseFrozen(hex::encode(phrase_hash)));
+    }
+    if db.phrase_originated(&phrase_hash).await? {
+        return Err(GrapevineServerError::PhraseAlreadyOriginated(hex::encode(
+            phrase_hash,
+        )));
+    }
+    for user in chain.users.iter() {
+        if let Some(existing) = db.get_user(&user.username).await {
+            if existing.pubkey != Some(user.pubkey) {
+                return Err(GrapevineServerError::UsernameExists(user.username.clone()));
+            }
+        }
+    }
+    Ok(())
+}
+
+/** The documents an import has written so far, removed again if a later write fails */
+#[derive(Default)]
+struct ChainWrites {
+    users: Vec<ObjectId>,
+    relationships: Vec<Relationship>,
+    proofs: Vec<ObjectId>,
+}
+
+/**
+ * Write a checked chain to the database, creating any users and relationships it needs
+ * @dev if any write fails, the documents already written are removed so the import can be
+ *      retried rather than conflicting with its own partial writes
+ *
+ * @param chain - the exported chain to import
+ * @param db - the database the chain is being imported into
+ * @returns - the new oid of each imported proof
+ */
+async fn insert_chain(
+    chain: &ChainExport,
+    db: &GrapevineDB,
+) -> Result<Vec<String>, GrapevineServerError> {
+    let mut writes = ChainWrites::default();
+    let res = write_chain(chain, db, &mut writes).await;
+    if res.is_err() {
+        // undo in reverse order so nothing is left referencing a removed document
+        let mut undo = db.discard_proofs(&writes.proofs).await;
+        for relationship in writes.relationships.iter() {
+            undo = undo.and(db.delete_relationship(relationship).await);
+        }
+        undo = undo.and(db.discard_users(&writes.users).await);
+        if let Err(e) = undo {
+            println!("Error undoing partial chain import: {:?}", e);
+        }
+    }
+    res
+}
+
+/**
+ * Write the users, relationships, and proofs of a chain, recording each document written
+ *
+ * @param chain - the exported chain to import
+ * @param db - the database the chain is being imported into
+ * @param writes - the documents written so far
+ * @returns - the new oid of each imported proof
+ */
+async fn write_chain(
+    chain: &ChainExport,
+    db: &GrapevineDB,
+    writes: &mut ChainWrites,
+) -> Result<Vec<String>, GrapevineServerError> {
+    // get or create every user in the chain
+    let mut users: HashMap<&String, ObjectId> = HashMap::new();
+    for user in chain.users.iter() {
+        let oid = match db.get_user(&user.username).await {
+            Some(existing) => existing.id.unwrap(),
+            None => {
+                let oid = db
+                    .create_user(User {
+                        id: None,
+                        nonce: Some(Nonce::default()),
+                        username: Some(user.username.clone()),
+                        pubkey: Some(user.pubkey),
+                        relationships: Some(vec![]),
+                        degree_proofs: Some(vec![]),
+                        last_active: None,
+                        deactivated: None,
+                        sessions: None,
+                    })
+                    .await?;
+                writes.users.push(oid);
+                oid
+            }
+        };
+        users.insert(&user.username, oid);
+    }
+    // add the relationships the chain was built through unless they already exist
+    for relationship in chain.relationships.iter() {
+        let sender = users[&relationship.sender];
+        let recipient = users[&relationship.recipient];
+        if db.relationship_exists(&sender, &recipient).await? {
+            continue;
+        }
+        let mut relationship_doc = Relationship {
+            id: None,
+            sender: Some(sender),
+            recipient: Some(recipient),
+            ephemeral_key: Some(relationship.ephemeral_key),
+            ciphertext: Some(relationship.ciphertext),
+            nonce: relationship.nonce,
+            created_at: Some(DateTime::now()),
+        };
+        let oid = db
+            .add_relationship(&relationship_doc, *MAX_RELATIONSHIPS)
+            .await?;
+        relationship_doc.id = Some(oid);
+        writes.relationships.push(relationship_doc);
+    }
+    // add the proofs in order, relinking each to the new oid of the proof it builds on
+    let mut oids: HashMap<&String, ObjectId> = HashMap::new();
+    let mut imported = vec![];
+    for proof in chain.proofs.iter() {
+        let user = users[&proof.username];
+        let proof_doc = DegreeProof {
+            id: None,
+            inactive: Some(false),
+            phrase_hash: Some(proof.phrase_hash),
+            auth_hash: Some(proof.auth_hash),
+            user: Some(user),
+            degree: Some(proof.degree),
+            proof_size: decompressed_size(&proof.proof).ok(),
+            params_version: Some(CIRCUIT_INFO.public_params_hash.clone()),
+            created_at: Some(DateTime::now()),
+            proof: Some(proof.proof.clone()),
+            preceding: proof.preceding.as_ref().map(|preceding| oids[preceding]),
+            proceeding: Some(vec![]),
+            anonymous: None,
+        };
+        let oid = db.add_proof(&user, &proof_doc).await?;
+        writes.proofs.push(oid);
+        oids.insert(&proof.oid, oid);
+        imported.push(oid.to_hex());
+    }
+    Ok(imported)
+}
@@ -0,0 +1,21 @@
+use crate::utils::CIRCUIT_INFO;
+use grapevine_common::http::responses::CircuitInfo;
+use rocket::serde::json::Json;
+
+/// GET REQUESTS ///
+
+/**
+ * Return the hashes of the circuit artifacts used by the server to verify proofs
+ * @notice: clients should compare these against their local artifacts before proving
+ *
+ * @return - a CircuitInfo struct containing:
+ *         * r1cs_hash: the sha256 hash of the r1cs file
+ *         * public_params_hash: the sha256 hash of the public params file
+ *         * version: the version of the grapevine circuits
+ * @return status:
+ *         - 200 if successful retrieval
+ */
+#[get("/info")]
+pub async fn get_circuit_info() -> Json<CircuitInfo> {
+    Json(CIRCUIT_INFO.clone())
+}
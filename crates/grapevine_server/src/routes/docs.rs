@@ -0,0 +1,20 @@
+use crate::openapi::SPEC;
+use rocket::serde::json::Json;
+use serde_json::Value;
+
+/// GET REQUESTS ///
+
+/**
+ * Return the OpenAPI document describing every route and the schema of every request and
+ * response body
+ * @notice: bincode bodies are documented as binary with the schema they serialize under
+ *          x-bincode
+ *
+ * @return - the OpenAPI 3 document
+ * @return status:
+ *         - 200 if successful retrieval
+ */
+#[get("/openapi.json")]
+pub async fn get_openapi_spec() -> Json<Value> {
+    Json(SPEC.clone())
+}
@@ -1,21 +1,80 @@
 use lazy_static::lazy_static;
 use rocket::route::Route;
+mod admin;
+mod circuit;
+mod docs;
+mod phrase;
 mod proof;
+mod server;
+mod stats;
 mod user;
+mod verify;
 
 lazy_static! {
     pub(crate) static ref USER_ROUTES: Vec<Route> = routes![
         user::create_user,
         user::add_relationship,
+        user::add_relationship_batch,
+        user::update_relationship,
+        user::delete_relationship,
+        user::withdraw_relationship,
+        user::get_challenge,
         user::get_user,
+        user::get_full_user,
         user::get_nonce,
         user::get_pubkey,
-        user::get_all_degrees
+        user::get_signed_claim,
+        user::get_user_by_pubkey,
+        user::get_relationship,
+        user::diagnose_relationship,
+        user::get_relationship_summaries,
+        user::get_received_secrets,
+        user::create_session,
+        user::get_sessions,
+        user::revoke_session,
+        user::get_all_degrees,
+        user::get_degrees_at,
+        user::get_degrees_since,
+        user::get_phrase_path,
+        user::get_connection_graph,
+        user::get_dashboard
     ];
+    pub(crate) static ref USERS_ROUTES: Vec<Route> = routes![user::get_active_users];
+    pub(crate) static ref RELATIONSHIP_ROUTES: Vec<Route> = routes![user::relationship_exists];
     pub(crate) static ref PROOF_ROUTES: Vec<Route> = routes![
         proof::create_phrase,
+        proof::create_phrase_batch,
+        proof::create_anonymous_phrase,
+        proof::create_knowledge_proof,
         proof::degree_proof,
+        proof::verify_proof,
+        proof::init_upload,
+        proof::upload_chunk,
+        proof::finalize_upload,
         proof::get_available_proofs,
+        proof::count_available_proofs,
         proof::get_proof_with_params,
+        proof::get_auto_params,
+        proof::get_proof_chain,
+        proof::get_proof_children,
+        proof::get_proof_bytes,
+        proof::get_proof_metadata,
+    ];
+    pub(crate) static ref PHRASE_ROUTES: Vec<Route> = routes![phrase::get_phrase_originator];
+    pub(crate) static ref STATS_ROUTES: Vec<Route> =
+        routes![stats::get_global_stats, stats::get_top_connectors];
+    pub(crate) static ref CIRCUIT_ROUTES: Vec<Route> = routes![circuit::get_circuit_info];
+    pub(crate) static ref SERVER_ROUTES: Vec<Route> = routes![server::get_server_pubkey];
+    pub(crate) static ref DOCS_ROUTES: Vec<Route> = routes![docs::get_openapi_spec];
+    pub(crate) static ref VERIFY_ROUTES: Vec<Route> =
+        routes![verify::verify_claim, verify::verify_signature];
+    pub(crate) static ref ADMIN_ROUTES: Vec<Route> = routes![
+        admin::migrate_params,
+        admin::migrate_phrases,
+        admin::import_chain,
+        admin::freeze_phrase,
+        admin::mint_invite,
+        admin::export_chain,
+        admin::auth_hash_reuse
     ];
 }
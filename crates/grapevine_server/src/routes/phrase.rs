@@ -0,0 +1,40 @@
+use crate::catchers::GrapevineResponse;
+use crate::mongo::GrapevineDB;
+use grapevine_common::errors::GrapevineServerError;
+use grapevine_common::http::responses::PhraseOriginator;
+use grapevine_common::utils::fr_from_hex;
+use rocket::{serde::json::Json, State};
+
+/// GET REQUESTS ///
+
+/**
+ * Return the user who created a phrase and the degree 1 proof they created it with
+ *
+ * @param phrase_hash - the hex encoded poseidon hash of the phrase
+ * @return - a PhraseOriginator struct containing:
+ *             * username: the username of the user who created the phrase (None if the phrase
+ *               was originated anonymously)
+ *             * oid: the oid of the degree 1 proof for the phrase
+ * @return status:
+ *             * 200 if success
+ *             * 400 if the phrase hash is not a hex encoded canonical field element
+ *             * 404 if no degree 1 proof exists for the phrase
+ *             * 500 if db fails or other unknown issue
+ */
+#[get("/<phrase_hash>/originator")]
+pub async fn get_phrase_originator(
+    phrase_hash: String,
+    db: &State<GrapevineDB>,
+) -> Result<Json<PhraseOriginator>, GrapevineResponse> {
+    let phrase_hash = match fr_from_hex(&phrase_hash) {
+        Ok(phrase_hash) => phrase_hash.to_bytes(),
+        Err(e) => {
+            return Err(GrapevineServerError::SerdeError(format!("phrase hash: {}", e)).into())
+        }
+    };
+    match db.get_phrase_originator(&phrase_hash).await {
+        Ok(Some(originator)) => Ok(Json(originator)),
+        Ok(None) => Err(GrapevineServerError::PhraseNotFound(hex::encode(phrase_hash)).into()),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -1,207 +1,427 @@
-use crate::catchers::ErrorMessage;
+use crate::degree_cache;
 use crate::mongo::GrapevineDB;
-use crate::utils::PUBLIC_PARAMS;
-use crate::{catchers::GrapevineResponse, guards::AuthenticatedUser};
-use grapevine_circuits::{nova::verify_nova_proof, utils::decompress_proof};
+use crate::signing::ServerKey;
+use crate::uploads::{self, UploadKind, MAX_PROOF_SIZE};
+use crate::utils::{
+    verify_proof_with_timeout, verify_proofs_with_timeout, CIRCUIT_INFO, VERIFICATION_TIMEOUT,
+};
+use crate::verification::ProofBackend;
+use crate::webhook::{ProofNotification, WebhookConfig};
+use crate::{
+    catchers::{GrapevineResponse, Negotiated, ProofSized, Signed, Tagged},
+    guards::{AuthenticatedUser, RateLimited},
+};
+use bincode::{ErrorKind, Options};
+use grapevine_circuits::outputs::{degree_iterations, PublicOutputs};
+use grapevine_circuits::utils::decompressed_size;
+use grapevine_common::crypto::proving_data_hash;
 use grapevine_common::errors::GrapevineServerError;
+use grapevine_common::utils::{fr_from_hex, fr_to_hex};
 use grapevine_common::{
-    http::requests::{DegreeProofRequest, NewPhraseRequest},
+    http::{
+        requests::{
+            AutoParamsRequest, DegreeProofRequest, NewPhraseBatchRequest, NewPhraseRequest,
+            VerifyProofRequest,
+        },
+        responses::{ChainProof, ProofChild, ProofMetadata, ProofVerification},
+    },
     models::proof::{DegreeProof, ProvingData},
+    Fr, MAX_PHRASE_BATCH,
 };
-use mongodb::bson::oid::ObjectId;
+use mongodb::bson::{oid::ObjectId, DateTime};
 use rocket::{
-    data::ToByteUnit, http::Status, serde::json::Json, tokio::io::AsyncReadExt, Data, State,
+    data::ToByteUnit,
+    http::{ContentType, Status},
+    serde::json::Json,
+    tokio::io::AsyncReadExt,
+    Data, State,
 };
+use serde::de::DeserializeOwned;
 use std::str::FromStr;
 
 // /// POST REQUESTS ///
 
 /**
  * Create a new phrase and (a degree 1 proof) and add it to the database
+ * @notice: the proof is always attributed to the user authenticated by the X-Username and
+ *          X-Authorization headers. The request body carries no username that could differ.
+ * @notice: phrase hashes are content derived, so a phrase can only be originated once. Any
+ *          later attempt to create the same phrase is rejected rather than merged, and the user
+ *          must instead prove a degree of separation from the existing chain
  *
  * @param data - binary serialized NewPhraseRequest containing:
- *             * username: the username of the user creating the phrase
  *             * proof: the gzip-compressed fold proof
  *        
  * @return status:
  *             * 201 if success
- *             * 400 if proof verification failed, deserialization fails, or proof decompression
- *               fails
+ *             * 400 if the proof is empty or too short to decompress (EmptyProof), proof
+ *               verification failed, deserialization fails, or proof decompression fails
  *             * 401 if signature mismatch or nonce mismatch
+ *             * 403 if the phrase has been frozen
  *             * 404 if user not found
+ *             * 409 if the phrase has already been created or the proof was already submitted
  *             * 500 if db fails or other unknown issue
+ *             * 503 if no verifier frees up within the verification queue timeout
+ *             * 504 if proof verification does not finish within the verification timeout
  */
 #[post("/create", data = "<data>")]
 pub async fn create_phrase(
     user: AuthenticatedUser,
     data: Data<'_>,
     db: &State<GrapevineDB>,
+    webhook: &State<WebhookConfig>,
+    backend: &State<ProofBackend>,
 ) -> Result<Status, GrapevineResponse> {
     // stream in data
     // todo: implement FromData trait on NewPhraseRequest
     let mut buffer = Vec::new();
-    let mut stream = data.open(2.mebibytes()); // Adjust size limit as needed
-                                               // @TODO: Stream in excess of 2 megabytes not actually throwing error
+    let mut stream = data.open((*MAX_PROOF_SIZE).bytes()); // Adjust size limit as needed
+                                                           // @TODO: Stream in excess of 2 megabytes not actually throwing error
     if let Err(e) = stream.read_to_end(&mut buffer).await {
         println!("Error reading request body: {:?}", e);
-        return Err(GrapevineResponse::TooLarge(
-            "Request body execeeds 2 MiB".to_string(),
-        ));
+        return Err(GrapevineResponse::TooLarge(format!(
+            "Request body execeeds {} bytes",
+            *MAX_PROOF_SIZE
+        )));
     }
-    let request = match bincode::deserialize::<NewPhraseRequest>(&buffer) {
-        Ok(req) => req,
-        Err(e) => {
-            println!(
-                "Error deserializing body from binary to NewPhraseRequest: {:?}",
-                e
-            );
-            return Err(GrapevineResponse::BadRequest(ErrorMessage(
-                Some(GrapevineServerError::SerdeError(String::from(
-                    "NewPhraseRequest",
-                ))),
-                None,
-            )));
-        }
-    };
-    // @TODO: No decompression error set up in case invalid
-    let decompressed_proof = decompress_proof(&request.proof);
-    // verify the proof
-    let verify_res = verify_nova_proof(&decompressed_proof, &*PUBLIC_PARAMS, 2);
-    let (phrase_hash, auth_hash) = match verify_res {
-        Ok(res) => {
-            let phrase_hash = res.0[1];
-            let auth_hash = res.0[2];
-            // todo: use request guard to check username against proven username
-            (phrase_hash.to_bytes(), auth_hash.to_bytes())
-        }
-        Err(e) => {
-            println!("Proof verification failed: {:?}", e);
-            return Err(GrapevineResponse::BadRequest(ErrorMessage(
-                Some(GrapevineServerError::DegreeProofVerificationFailed),
-                None,
-            )));
-        }
-    };
-    // get user doc
-    let user = db.get_user(&user.0).await.unwrap();
-    // build DegreeProof model
-    let proof_doc = DegreeProof {
-        id: None,
-        inactive: Some(false),
-        phrase_hash: Some(phrase_hash),
-        auth_hash: Some(auth_hash),
-        user: Some(user.id.unwrap()),
-        degree: Some(1),
-        proof: Some(request.proof.clone()),
-        preceding: None,
-        proceeding: Some(vec![]),
-    };
+    add_phrase(&user.0, &buffer, false, db, webhook, backend).await
+}
 
-    match db.add_proof(&user.id.unwrap(), &proof_doc).await {
-        Ok(_) => Ok(Status::Created),
-        Err(e) => {
-            println!("Error adding proof: {:?}", e);
-            Err(GrapevineResponse::InternalError(ErrorMessage(
-                Some(GrapevineServerError::MongoError(String::from(
-                    "Failed to add proof to db",
-                ))),
-                None,
-            )))
-        }
+/**
+ * Create a new phrase (and a degree 1 proof) without revealing who originated it
+ * @notice: the NonceGuard still needs an identity, so originators should use a burner key flow:
+ *          register a one-time account with a fresh username and key, send relationships from it
+ *          to the users the phrase should reach, then originate the phrase from it. The proof
+ *          is still verified and linked to the burner so recipients can build on it, but the
+ *          originator is withheld from /phrase/<phrase_hash>/originator, /proof/<oid>, and the
+ *          proof webhook
+ *
+ * @param data - binary serialized NewPhraseRequest containing:
+ *             * proof: the gzip-compressed fold proof
+ * @return status:
+ *             * 201 if success
+ *             * 400 if the proof is empty or too short to decompress (EmptyProof), proof
+ *               verification failed, deserialization fails, or proof decompression fails
+ *             * 401 if signature mismatch or nonce mismatch
+ *             * 403 if the phrase has been frozen
+ *             * 404 if user not found
+ *             * 409 if the phrase has already been created or the proof was already submitted
+ *             * 500 if db fails or other unknown issue
+ *             * 503 if no verifier frees up within the verification queue timeout
+ *             * 504 if proof verification does not finish within the verification timeout
+ */
+#[post("/create/anonymous", data = "<data>")]
+pub async fn create_anonymous_phrase(
+    user: AuthenticatedUser,
+    data: Data<'_>,
+    db: &State<GrapevineDB>,
+    webhook: &State<WebhookConfig>,
+    backend: &State<ProofBackend>,
+) -> Result<Status, GrapevineResponse> {
+    let mut buffer = Vec::new();
+    let mut stream = data.open((*MAX_PROOF_SIZE).bytes());
+    if let Err(_) = stream.read_to_end(&mut buffer).await {
+        return Err(GrapevineResponse::TooLarge(format!(
+            "Request body execeeds {} bytes",
+            *MAX_PROOF_SIZE
+        )));
     }
+    add_phrase(&user.0, &buffer, true, db, webhook, backend).await
+}
+
+/**
+ * Prove knowledge of a phrase without joining its chain, storing the proof at degree 0
+ * @notice: a degree 1 proof seeds the chain by accumulating the originator's auth secret so their
+ *          relationships can build on it. A knowledge proof is the same single step proof, but it
+ *          is never offered as a proof to build from, so the user can show they know the phrase
+ *          (i.e. to /user/<username>/within) whether or not it was already originated
+ * @dev the output auth hash commits to the prover's username and auth secret, so a proof copied
+ *      from another user carries an auth hash that is already stored and is rejected as replayed
+ *
+ * @param data - binary serialized NewPhraseRequest containing:
+ *             * proof: the gzip-compressed fold proof
+ * @return status:
+ *             * 201 if success
+ *             * 400 if the proof is empty or too short to decompress (EmptyProof), proof
+ *               verification failed, the proof is not a single step proof, deserialization
+ *               fails, or proof decompression fails
+ *             * 401 if signature mismatch or nonce mismatch
+ *             * 403 if the phrase has been frozen
+ *             * 404 if user not found
+ *             * 409 if the user already has a proof for the phrase or the proof was already
+ *               submitted
+ *             * 500 if db fails or other unknown issue
+ *             * 503 if no verifier frees up within the verification queue timeout
+ *             * 504 if proof verification does not finish within the verification timeout
+ */
+#[post("/create/knowledge", data = "<data>")]
+pub async fn create_knowledge_proof(
+    user: AuthenticatedUser,
+    data: Data<'_>,
+    db: &State<GrapevineDB>,
+    webhook: &State<WebhookConfig>,
+    backend: &State<ProofBackend>,
+) -> Result<Status, GrapevineResponse> {
+    let mut buffer = Vec::new();
+    let mut stream = data.open((*MAX_PROOF_SIZE).bytes());
+    if let Err(_) = stream.read_to_end(&mut buffer).await {
+        return Err(GrapevineResponse::TooLarge(format!(
+            "Request body execeeds {} bytes",
+            *MAX_PROOF_SIZE
+        )));
+    }
+    add_knowledge_proof(&user.0, &buffer, db, webhook, backend).await
 }
 
 /**
  * Build from a previous degree of connection proof and add it to the database
  *
  * @param data - binary serialized DegreeProofRequest containing:
- *             * username: the username of the user adding a proof of degree of connection
  *             * proof: the gzip-compressed fold proof
  *             * previous: the stringified OID of the previous proof to continue IVC from
  *             * degree: the separation degree of the given proof
  * @return status:
  *             * 201 if successful proof update
- *             * 400 if proof verification failed, deserialization fails, or proof decompression
- *               fails
+ *             * 400 if the proof is empty or too short to decompress, proof verification failed,
+ *               the proof does not output the claimed degree, deserialization fails, or proof
+ *               decompression fails
  *             * 401 if signature mismatch or nonce mismatch
+ *             * 403 if the phrase of the previous proof has been frozen, or the previous proof's
+ *               creator has no relationship with the user
  *             * 404 if user or previous proof not found not found
+ *             * 409 if the user already has a proof continuing from the previous proof
  *             * 500 if db fails or other unknown issue
+ *             * 503 if no verifier frees up within the verification queue timeout
+ *             * 504 if proof verification does not finish within the verification timeout
  */
 #[post("/continue", data = "<data>")]
 pub async fn degree_proof(
     user: AuthenticatedUser,
     data: Data<'_>,
     db: &State<GrapevineDB>,
+    webhook: &State<WebhookConfig>,
+    backend: &State<ProofBackend>,
 ) -> Result<Status, GrapevineResponse> {
     // stream in data
     // todo: implement FromData trait on DegreeProofRequest
     let mut buffer = Vec::new();
-    let mut stream = data.open(2.mebibytes()); // Adjust size limit as needed
+    let mut stream = data.open((*MAX_PROOF_SIZE).bytes()); // Adjust size limit as needed
+    if let Err(_) = stream.read_to_end(&mut buffer).await {
+        return Err(GrapevineResponse::TooLarge(format!(
+            "Request body execeeds {} bytes",
+            *MAX_PROOF_SIZE
+        )));
+    }
+    add_degree_proof(&user.0, &buffer, db, webhook, backend).await
+}
+
+/**
+ * Create several new phrases (each with a degree 1 proof) in one request
+ * @notice: the circuit folds a single phrase per proof, so a batch is a set of independent degree 1
+ *          proofs that are verified together and only added if every proof is accepted
+ *
+ * @param data - binary serialized NewPhraseBatchRequest containing:
+ *             * proofs: the gzip-compressed fold proof for each phrase (at most MAX_PHRASE_BATCH)
+ * @return status:
+ *             * 201 if success
+ *             * 400 if any proof is empty or too short to decompress, any proof verification
+ *               failed, deserialization fails, the batch is empty, or proof decompression fails
+ *             * 401 if signature mismatch or nonce mismatch
+ *             * 403 if any phrase has been frozen
+ *             * 404 if user not found
+ *             * 409 if any phrase has already been created or appears twice in the batch, or
+ *               any proof was already submitted
+ *             * 413 if the batch contains more than MAX_PHRASE_BATCH proofs
+ *             * 500 if db fails or other unknown issue
+ *             * 503 if no verifier frees up within the verification queue timeout
+ *             * 504 if proof verification does not finish within the verification timeout
+ */
+#[post("/create/batch", data = "<data>")]
+pub async fn create_phrase_batch(
+    user: AuthenticatedUser,
+    data: Data<'_>,
+    db: &State<GrapevineDB>,
+    webhook: &State<WebhookConfig>,
+    backend: &State<ProofBackend>,
+) -> Result<Status, GrapevineResponse> {
+    let mut buffer = Vec::new();
+    let mut stream = data.open((*MAX_PROOF_SIZE * MAX_PHRASE_BATCH).bytes());
+    if let Err(_) = stream.read_to_end(&mut buffer).await {
+        return Err(GrapevineResponse::TooLarge(format!(
+            "Request body execeeds {} bytes",
+            *MAX_PROOF_SIZE * MAX_PHRASE_BATCH
+        )));
+    }
+    let request =
+        match deserialize_request::<NewPhraseBatchRequest>(&buffer, "NewPhraseBatchRequest") {
+            Ok(req) => req,
+            Err(e) => return Err(e.into()),
+        };
+    if request.proofs.len() == 0 {
+        return Err(GrapevineServerError::SerdeError(String::from(
+            "NewPhraseBatchRequest: batch contains no proofs",
+        ))
+        .into());
+    }
+    if request.proofs.len() > MAX_PHRASE_BATCH {
+        return Err(GrapevineServerError::BatchTooLarge(request.proofs.len()).into());
+    }
+    let outputs = verify_phrase_proofs(&request.proofs, db, backend).await?;
+    insert_phrase_proofs(&user.0, request.proofs, outputs, false, db, webhook).await
+}
+
+/**
+ * Check whether a proof would pass verification without adding it to the database
+ * @notice: rate limited by client as verification is CPU heavy, and does not consume a nonce
+ *
+ * @param data - binary serialized VerifyProofRequest containing:
+ *             * proof: the gzip-compressed fold proof
+ *             * degree: the separation degree the proof claims
+ * @return - a ProofVerification struct containing:
+ *             * verified: whether the proof verified for the claimed degree
+ *             * phrase_hash: the phrase hash output by the proof if verified
+ *             * auth_hash: the auth hash output by the proof if verified
+ *             * error: why the proof failed if not verified
+ * @return status:
+ *             * 200 if the proof was checked, whether or not it verified
+ *             * 400 if deserialization fails or the proof is empty or too short to decompress
+ *             * 429 if the client has made too many verification requests
+ *             * 503 if no verifier frees up within the verification queue timeout
+ *             * 504 if verification does not finish within the verification timeout
+ */
+#[post("/verify", data = "<data>")]
+pub async fn verify_proof(
+    _limit: RateLimited,
+    data: Data<'_>,
+    backend: &State<ProofBackend>,
+) -> Result<Json<ProofVerification>, GrapevineResponse> {
+    let mut buffer = Vec::new();
+    let mut stream = data.open((*MAX_PROOF_SIZE).bytes());
     if let Err(_) = stream.read_to_end(&mut buffer).await {
-        return Err(GrapevineResponse::TooLarge(
-            "Request body execeeds 2 MiB".to_string(),
-        ));
+        return Err(GrapevineResponse::TooLarge(format!(
+            "Request body execeeds {} bytes",
+            *MAX_PROOF_SIZE
+        )));
     }
-    let request = match bincode::deserialize::<DegreeProofRequest>(&buffer) {
+    let request = match deserialize_request::<VerifyProofRequest>(&buffer, "VerifyProofRequest") {
         Ok(req) => req,
-        Err(_) => {
-            return Err(GrapevineResponse::BadRequest(ErrorMessage(
-                Some(GrapevineServerError::SerdeError(String::from(
-                    "DegreeProofRequest",
-                ))),
-                None,
-            )))
-        }
-    };
-    let decompressed_proof = decompress_proof(&request.proof);
-    // verify the proof
-    let verify_res = verify_nova_proof(
-        &decompressed_proof,
-        &*PUBLIC_PARAMS,
-        (request.degree * 2) as usize,
-    );
-    let (phrase_hash, auth_hash) = match verify_res {
-        Ok(res) => {
-            let phrase_hash = res.0[1];
-            let auth_hash = res.0[2];
-            (phrase_hash.to_bytes(), auth_hash.to_bytes())
-        }
-        Err(e) => {
-            println!("Proof verification failed: {:?}", e);
-            return Err(GrapevineResponse::BadRequest(ErrorMessage(
-                Some(GrapevineServerError::DegreeProofVerificationFailed),
-                None,
-            )));
-        }
-    };
-    // get user doc
-    let user = db.get_user(&user.0).await.unwrap();
-    // @TODO: needs to delete a previous proof by same user on same phrase hash if exists, including removing from last proof's previous field
-    // build DegreeProof struct
-    let proof_doc = DegreeProof {
-        id: None,
-        inactive: Some(false),
-        phrase_hash: Some(phrase_hash),
-        auth_hash: Some(auth_hash),
-        user: Some(user.id.unwrap()),
-        degree: Some(request.degree),
-        proof: Some(request.proof.clone()),
-        preceding: Some(ObjectId::from_str(&request.previous).unwrap()),
-        proceeding: Some(vec![]),
+        Err(e) => return Err(e.into()),
     };
+    let iterations = degree_iterations(request.degree);
+    let verify_res =
+        match verify_proof_with_timeout(backend, request.proof, iterations, *VERIFICATION_TIMEOUT)
+            .await
+        {
+            Ok(verify_res) => verify_res,
+            Err(e) => return Err(e.into()),
+        };
+    match verify_res.and_then(|res| PublicOutputs::from_verification(&res)) {
+        Ok(outputs) => Ok(Json(ProofVerification {
+            verified: true,
+            phrase_hash: Some(outputs.phrase_hash.to_bytes()),
+            auth_hash: Some(outputs.auth_hash.to_bytes()),
+            error: None,
+        })),
+        Err(e) => Ok(Json(ProofVerification {
+            verified: false,
+            phrase_hash: None,
+            auth_hash: None,
+            error: Some(e.to_string()),
+        })),
+    }
+}
 
-    // add proof to db and update references
-    match db.add_proof(&user.id.unwrap(), &proof_doc).await {
-        Ok(_) => Ok(Status::Created),
-        Err(e) => {
-            println!("Error adding proof: {:?}", e);
-            Err(GrapevineResponse::InternalError(ErrorMessage(
-                Some(GrapevineServerError::MongoError(String::from(
-                    "Failed to add proof to db",
-                ))),
-                None,
+/**
+ * Begin a chunked upload of a proof request body too large to reliably send in one request
+ *
+ * @param kind - the route the reassembled body is handled by ("phrase" for /create or "degree"
+ *               for /continue)
+ * @return - the id of the upload to send chunks to
+ * @return status:
+ *             * 201 if success
+ *             * 401 if signature mismatch or nonce mismatch
+ *             * 429 if the user or server already has the maximum number of uploads in progress
+ */
+#[post("/upload/init?<kind>")]
+pub async fn init_upload(user: AuthenticatedUser, kind: UploadKind) -> GrapevineResponse {
+    match uploads::start(&user.0, kind) {
+        Ok(id) => GrapevineResponse::Created(id),
+        Err(e) => e.into(),
+    }
+}
+
+/**
+ * Store one chunk of an in-progress upload. Chunks can be sent in any order and a chunk sent
+ * again at the same index replaces the previous one, so a failed chunk can simply be retried
+ *
+ * @param id - the id returned by /upload/init
+ * @param index - the zero-based position of the chunk within the body
+ * @param data - the bytes of the chunk
+ * @return status:
+ *             * 200 if success
+ *             * 401 if signature mismatch or nonce mismatch
+ *             * 404 if the upload does not exist, has expired, or belongs to another user
+ *             * 413 if the chunk or upload exceeds the maximum proof size, or the upload exceeds
+ *               the maximum number of chunks
+ */
+#[put("/upload/<id>/<index>", data = "<data>")]
+pub async fn upload_chunk(
+    user: AuthenticatedUser,
+    id: String,
+    index: u32,
+    data: Data<'_>,
+) -> Result<Status, GrapevineResponse> {
+    let buffer = match data.open((*MAX_PROOF_SIZE).bytes()).into_bytes().await {
+        Ok(buffer) if buffer.is_complete() => buffer.into_inner(),
+        Ok(_) => {
+            return Err(GrapevineResponse::TooLarge(format!(
+                "Request body exceeds {} bytes",
+                *MAX_PROOF_SIZE
             )))
         }
+        Err(e) => return Err(GrapevineServerError::SerdeError(e.to_string()).into()),
+    };
+    match uploads::store_chunk(&user.0, &id, index, buffer) {
+        Ok(_) => Ok(Status::Ok),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/**
+ * Reassemble the chunks of an upload and handle the body as the route chosen at /upload/init
+ *
+ * @param id - the id returned by /upload/init
+ * @return status:
+ *             * 201 if success
+ *             * 400 if a chunk is missing (the upload is kept so the chunk can still be sent),
+ *               the proof is empty or too short to decompress, proof verification failed,
+ *               deserialization fails, or proof decompression fails
+ *             * 401 if signature mismatch or nonce mismatch
+ *             * 403 if the phrase has been frozen, or the previous proof's creator has no
+ *               relationship with the user
+ *             * 404 if the upload does not exist, has expired, or belongs to another user
+ *             * 409 if the phrase has already been created, or the user already has a proof
+ *               continuing from the previous proof
+ *             * 413 if the upload exceeds the maximum proof size
+ *             * 500 if db fails or other unknown issue
+ *             * 503 if no verifier frees up within the verification queue timeout
+ *             * 504 if proof verification does not finish within the verification timeout
+ */
+#[post("/upload/<id>/finalize")]
+pub async fn finalize_upload(
+    user: AuthenticatedUser,
+    id: String,
+    db: &State<GrapevineDB>,
+    webhook: &State<WebhookConfig>,
+    backend: &State<ProofBackend>,
+) -> Result<Status, GrapevineResponse> {
+    let (kind, buffer) = match uploads::finalize(&user.0, &id) {
+        Ok(upload) => upload,
+        Err(e) => return Err(e.into()),
+    };
+    match kind {
+        UploadKind::Phrase => add_phrase(&user.0, &buffer, false, db, webhook, backend).await,
+        UploadKind::Degree => add_degree_proof(&user.0, &buffer, db, webhook, backend).await,
     }
 }
 
@@ -210,6 +430,8 @@ pub async fn degree_proof(
 /**
  * Return a list of all available (new) degree proofs from existing connections that a user can
  * build from
+ * @notice: served from a materialized view that is invalidated on writes and recomputed every
+ *          degree_cache::REFRESH_INTERVAL, so may briefly lag writes made by another server
  *
  * @param username - the username to look up the available proofs for
  * @return - a vector of stringified OIDs of available proofs to use with get_proof_with_params
@@ -224,12 +446,41 @@ pub async fn degree_proof(
 pub async fn get_available_proofs(
     user: AuthenticatedUser,
     db: &State<GrapevineDB>,
-) -> Result<Json<Vec<String>>, Status> {
-    Ok(Json(db.find_available_degrees(user.0).await))
+) -> Result<Json<Vec<String>>, GrapevineResponse> {
+    Ok(Json(degree_cache::get_available_degrees(db, &user.0).await))
+}
+
+/**
+ * Returns how many proofs a user can build from, without the oids returned by /proof/available
+ *
+ * @param username - the username to count the available proofs of (must be the authenticated user)
+ * @return - the number of available proofs
+ * @return status:
+ *         - 200 if successful retrieval
+ *         - 401 if signature mismatch or nonce mismatch
+ *         - 403 if the authenticated user is not the user being counted for
+ *         - 500 if db fails or other unknown issue
+ */
+#[get("/<username>/available/count")]
+pub async fn count_available_proofs(
+    user: AuthenticatedUser,
+    username: String,
+    db: &State<GrapevineDB>,
+) -> Result<Json<u64>, GrapevineResponse> {
+    if user.0 != username {
+        return Err(GrapevineServerError::AvailableNotPermitted(username).into());
+    }
+    match db.count_available_degrees(username).await {
+        Ok(count) => Ok(Json(count)),
+        Err(e) => Err(e.into()),
+    }
 }
 
 /**
  * Returns all the information needed to construct a proof of degree of separation from a given user
+ * @notice: the server only stores the auth secret as encrypted client-side by the relationship
+ *          sender, so it cannot re-encrypt it to a requester whose decryption fails. Recovery
+ *          requires the sender to replace the ciphertext with PUT /user/relationship/<username>
  *
  * @param oid - the ObjectID of the proof to retrieve
  * @param username - the username to retrieve encrypted auth secret for when proving relationship
@@ -240,9 +491,17 @@ pub async fn get_available_proofs(
  *         * ephemeral_key: the ephemeral pubkey that can be combined with the requesting user's
  *           private key to derive returned proof creator's auth secret decryption key
  *         * ciphertext: the encrypted auth secret
+ *         * nonce: the aes-gcm nonce of the ciphertext (None if legacy aes-cbc)
+ *         serialized with bincode if the Accept header prefers application/octet-stream, or as
+ *         json otherwise. The X-Grapevine-Proof-Size header reports the decompressed proof size,
+ *         and X-Grapevine-Signature carries the server's signature over the proving data, which
+ *         clients check against /server/pubkey with crypto::verify_proving_data. The ETag header
+ *         tags the proving data so clients can refetch with If-None-Match
  * @return status:
  *         - 200 if successful retrieval
+ *         - 304 if If-None-Match lists the ETag of the current proving data
  *         - 401 if signature mismatch or nonce mismatch
+ *         - 403 if the proof creator has no relationship with the user
  *         - 404 if username or proof not found
  *         - 500 if db fails or other unknown issue
  */
@@ -252,35 +511,670 @@ pub async fn get_proof_with_params(
     user: AuthenticatedUser,
     oid: String,
     db: &State<GrapevineDB>,
-) -> Result<Json<ProvingData>, GrapevineResponse> {
-    let oid = ObjectId::from_str(&oid).unwrap();
+    server_key: &State<ServerKey>,
+) -> Result<Tagged<Signed<ProofSized<Negotiated<ProvingData>>>>, GrapevineResponse> {
+    let oid = match ObjectId::from_str(&oid) {
+        Ok(oid) => oid,
+        Err(_) => return Err(GrapevineServerError::ProofNotFound(oid).into()),
+    };
     match db.get_proof_and_data(user.0, oid).await {
-        Some(data) => Ok(Json(data)),
-        None => Err(GrapevineResponse::NotFound(format!(
-            "No proof found with oid {}",
-            oid
-        ))),
+        Ok((data, size)) => {
+            // proofs are immutable, so the proving data only changes if the auth secret is replaced
+            let etag = proving_data_hash(&data);
+            let signature = server_key.sign_proving_data(&data).compress();
+            Ok(Tagged(
+                Signed(ProofSized(Negotiated(data), size), signature),
+                etag,
+            ))
+        }
+        Err(e) => Err(e.into()),
     }
 }
 
 /**
- * Return a list of all proofs linked to a given phrase hash
+ * Returns the proving data for the lowest degree proof the user can build from in a phrase's chain
+ * @notice: combines /proof/available and /proof/params/<oid> into one request
  *
+ * @param data - the AutoParamsRequest containing:
+ *             * phrase_hash: the hash of the phrase to build a proof for
+ * @return - a ProvingData struct as returned by /proof/params/<oid>, signed the same way
+ * @return status:
+ *         - 200 if successful retrieval
+ *         - 401 if signature mismatch or nonce mismatch
+ *         - 404 if the user has no available proof for the phrase
+ *         - 500 if db fails or other unknown issue
+ */
+#[post("/autoparams", format = "json", data = "<request>")]
+pub async fn get_auto_params(
+    user: AuthenticatedUser,
+    request: Json<AutoParamsRequest>,
+    db: &State<GrapevineDB>,
+    server_key: &State<ServerKey>,
+) -> Result<Signed<ProofSized<Negotiated<ProvingData>>>, GrapevineResponse> {
+    let oid = match db
+        .find_best_available_degree(user.0.clone(), &request.phrase_hash)
+        .await
+    {
+        Ok(Some(oid)) => oid,
+        Ok(None) => {
+            return Err(
+                GrapevineServerError::NoAvailableProof(hex::encode(request.phrase_hash)).into(),
+            )
+        }
+        Err(e) => return Err(e.into()),
+    };
+    match db.get_proof_and_data(user.0, oid).await {
+        Ok((data, size)) => {
+            let signature = server_key.sign_proving_data(&data).compress();
+            Ok(Signed(ProofSized(Negotiated(data), size), signature))
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/**
+ * Return the proofs built directly on top of a given proof for navigating down a proof chain
+ * @dev ranked below /params/<oid> and /chain/<phrase_hash>, which also match /params/children
+ *      and /chain/children
  *
- * @param phrase hash - the hash of the phrase creating the proof chain
- * @return - a vector of stringified OIDs of proofs within the given chain
+ * @param oid - the ObjectID of the parent proof
+ * @return - a vector of ProofChild structs containing:
+ *         * oid: the stringified ObjectID of the child proof
+ *         * username: the username of the child proof creator
+ *         * degree: the separation degree of the child proof
+ *         (empty if the proof is a leaf)
  * @return status:
  *         - 200 if successful retrieval
  *         - 401 if signature mismatch or nonce mismatch
- *         - 404 if user not found
+ *         - 404 if proof not found
+ *         - 500 if db fails or other unknown issue
+ */
+#[get("/<oid>/children", rank = 2)]
+pub async fn get_proof_children(
+    _user: AuthenticatedUser,
+    oid: String,
+    db: &State<GrapevineDB>,
+) -> Result<Json<Vec<ProofChild>>, GrapevineResponse> {
+    let oid = match ObjectId::from_str(&oid) {
+        Ok(oid) => oid,
+        Err(_) => return Err(GrapevineServerError::ProofNotFound(oid).into()),
+    };
+    match db.get_proof_children(&oid).await {
+        Ok(Some(children)) => Ok(Json(children)),
+        Ok(None) => Err(GrapevineServerError::ProofNotFound(oid.to_hex()).into()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/**
+ * Return the compressed proof bytes alone, without the encryption material of /params/<oid>
+ * @notice: lets a verifier who already holds the auth secret fetch and cache proofs separately
+ *          from their metadata. Gated by the same relationship as /params/<oid>
+ * @dev ranked below /params/<oid> and /chain/<phrase_hash>, which also match /params/bytes and
+ *      /chain/bytes
+ *
+ * @param oid - the ObjectID of the proof to retrieve
+ * @return - the gzip-compressed fold proof as application/octet-stream
+ * @return status:
+ *         - 200 if successful retrieval
+ *         - 401 if signature mismatch or nonce mismatch
+ *         - 403 if the proof creator has no relationship with the user
+ *         - 404 if username or proof not found
+ *         - 500 if db fails or other unknown issue
+ */
+#[get("/<oid>/bytes", rank = 2)]
+pub async fn get_proof_bytes(
+    user: AuthenticatedUser,
+    oid: String,
+    db: &State<GrapevineDB>,
+) -> Result<(ContentType, Vec<u8>), GrapevineResponse> {
+    let oid = match ObjectId::from_str(&oid) {
+        Ok(oid) => oid,
+        Err(_) => return Err(GrapevineServerError::ProofNotFound(oid).into()),
+    };
+    match db.get_proof_bytes(user.0, oid).await {
+        Ok(bytes) => Ok((ContentType::Binary, bytes)),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/**
+ * Return the non-sensitive metadata of any proof
+ * @notice: no relationship with the proof creator is required since the encrypted auth secret is
+ *          not returned. Use /proof/params/<oid> to get the data needed to build on the proof
+ * @dev ranked below /params/<oid> and /available, which share the same segment shape
+ *
+ * @param oid - the ObjectID of the proof
+ * @return - a ProofMetadata struct containing:
+ *         * oid: the stringified ObjectID of the proof
+ *         * degree: the separation degree of the proof
+ *         * phrase_hash: the hash of the phrase the proof chain is linked by
+ *         * username: the username of the proof creator (None if the proof anonymously
+ *           originated its phrase)
+ *         * created_at: when the proof was created in milliseconds since the unix epoch
+ * @return status:
+ *         - 200 if successful retrieval
+ *         - 404 if proof not found
+ *         - 500 if db fails or other unknown issue
+ */
+#[get("/<oid>", rank = 2)]
+pub async fn get_proof_metadata(
+    oid: String,
+    db: &State<GrapevineDB>,
+) -> Result<Json<ProofMetadata>, GrapevineResponse> {
+    let oid = match ObjectId::from_str(&oid) {
+        Ok(oid) => oid,
+        Err(_) => return Err(GrapevineServerError::ProofNotFound(oid).into()),
+    };
+    match db.get_proof_metadata(&oid).await {
+        Ok(Some(metadata)) => Ok(Json(metadata)),
+        Ok(None) => Err(GrapevineServerError::ProofNotFound(oid.to_hex()).into()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/**
+ * Return a list of all proofs linked to a given phrase hash
+ *
+ * @param phrase hash - the hex encoded hash of the phrase creating the proof chain
+ * @return - the oid and degree of each proof within the given chain, by ascending degree
+ *           (empty if the phrase has no proofs)
+ * @return status:
+ *         - 200 if successful retrieval
+ *         - 400 if the phrase hash is not a hex encoded canonical field element
  *         - 500 if db fails or other unknown issue
  */
 #[get("/chain/<phrase_hash>")]
 pub async fn get_proof_chain(
     phrase_hash: String,
     db: &State<GrapevineDB>,
-) -> Result<Json<Vec<DegreeProof>>, Status> {
-    Ok(Json(db.get_proof_chain(&phrase_hash).await))
+) -> Result<Json<Vec<ChainProof>>, GrapevineResponse> {
+    let phrase_hash = match fr_from_hex(&phrase_hash) {
+        Ok(phrase_hash) => phrase_hash.to_bytes(),
+        Err(e) => {
+            return Err(GrapevineServerError::SerdeError(format!("phrase hash: {}", e)).into())
+        }
+    };
+    match db.get_proof_chain(&phrase_hash).await {
+        Ok(proofs) => Ok(Json(proofs)),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/**
+ * Verify a NewPhraseRequest body and add its degree 1 proof to the database
+ *
+ * @param username - the authenticated user the proof is attributed to
+ * @param buffer - the binary serialized NewPhraseRequest
+ * @param anonymous - whether to withhold the user as the originator of the phrase
+ * @param webhook - the webhook notified of the added proof
+ * @param backend - the proof system to verify the proof with
+ * @returns - status 201 if the proof was added, or the error response otherwise
+ */
+async fn add_phrase(
+    username: &String,
+    buffer: &[u8],
+    anonymous: bool,
+    db: &GrapevineDB,
+    webhook: &WebhookConfig,
+    backend: &ProofBackend,
+) -> Result<Status, GrapevineResponse> {
+    let request = match deserialize_request::<NewPhraseRequest>(buffer, "NewPhraseRequest") {
+        Ok(req) => req,
+        Err(e) => return Err(e.into()),
+    };
+    let proofs = vec![request.proof];
+    let outputs = verify_phrase_proofs(&proofs, db, backend).await?;
+    insert_phrase_proofs(username, proofs, outputs, anonymous, db, webhook).await
+}
+
+/**
+ * Verify a NewPhraseRequest body and add its proof to the database as a degree 0 knowledge proof
+ *
+ * @param username - the authenticated user the proof is attributed to
+ * @param buffer - the binary serialized NewPhraseRequest
+ * @param webhook - the webhook notified of the added proof
+ * @param backend - the proof system to verify the proof with
+ * @returns - status 201 if the proof was added, or the error response otherwise
+ */
+async fn add_knowledge_proof(
+    username: &String,
+    buffer: &[u8],
+    db: &GrapevineDB,
+    webhook: &WebhookConfig,
+    backend: &ProofBackend,
+) -> Result<Status, GrapevineResponse> {
+    let request = match deserialize_request::<NewPhraseRequest>(buffer, "NewPhraseRequest") {
+        Ok(req) => req,
+        Err(e) => return Err(e.into()),
+    };
+    // decompress and verify the proof off of the async workers
+    let verify_res = match verify_proof_with_timeout(
+        backend,
+        request.proof.clone(),
+        degree_iterations(0),
+        *VERIFICATION_TIMEOUT,
+    )
+    .await
+    {
+        Ok(verify_res) => verify_res,
+        Err(e) => return Err(e.into()),
+    };
+    let (phrase_hash, auth_hash) =
+        match verify_res.and_then(|res| PublicOutputs::from_verification(&res)) {
+            // the circuit counts the prover's own step, so a single step proof outputs degree 1
+            Ok(outputs) if outputs.degree == Fr::from(1) => {
+                (outputs.phrase_hash.to_bytes(), outputs.auth_hash.to_bytes())
+            }
+            Ok(_) => return Err(GrapevineServerError::DegreeMismatch(0).into()),
+            Err(e) => {
+                println!("Proof verification failed: {:?}", e);
+                return Err(e.into());
+            }
+        };
+    // reject the proof if an operator has frozen this phrase
+    match db.phrase_frozen(&phrase_hash).await {
+        Ok(false) => (),
+        Ok(true) => return Err(GrapevineServerError::PhraseFrozen(hex::encode(phrase_hash)).into()),
+        Err(e) => return Err(e.into()),
+    };
+    // a user already in the chain (or already holding a knowledge proof) has shown they know it
+    match db.has_proof_within(username, &phrase_hash, u8::MAX).await {
+        Ok(false) => (),
+        Ok(true) => {
+            return Err(GrapevineServerError::PhraseAlreadyKnown(hex::encode(phrase_hash)).into())
+        }
+        Err(e) => return Err(e.into()),
+    };
+    // a proof whose auth hash is already stored was copied rather than made by this user
+    match db.auth_hash_stored(&auth_hash).await {
+        Ok(false) => (),
+        Ok(true) => return Err(GrapevineServerError::ProofReplayed(hex::encode(auth_hash)).into()),
+        Err(e) => return Err(e.into()),
+    };
+    let user = db.get_user(username).await.unwrap();
+    let proof_doc = DegreeProof {
+        id: None,
+        inactive: Some(false),
+        phrase_hash: Some(phrase_hash),
+        auth_hash: Some(auth_hash),
+        user: Some(user.id.unwrap()),
+        degree: Some(0),
+        proof_size: decompressed_size(&request.proof).ok(),
+        params_version: Some(CIRCUIT_INFO.public_params_hash.clone()),
+        created_at: Some(DateTime::now()),
+        proof: Some(request.proof),
+        preceding: None,
+        proceeding: Some(vec![]),
+        anonymous: None,
+    };
+    match db.add_proof(&user.id.unwrap(), &proof_doc).await {
+        Ok(oid) => webhook.notify(ProofNotification::new(
+            &phrase_hash,
+            0,
+            Some(username),
+            &oid,
+        )),
+        Err(e) => {
+            println!("Error adding proof: {:?}", e);
+            return Err(GrapevineServerError::MongoError(String::from(
+                "Failed to add proof to db",
+            ))
+            .into());
+        }
+    }
+    degree_cache::invalidate_all(db);
+    Ok(Status::Created)
+}
+
+/**
+ * Verify a set of degree 1 proofs and extract the phrase outputs of each
+ * @dev every proof is checked before any is accepted, so a batch is rejected as a whole if a
+ *      single proof fails, two proofs originate the same phrase, or a proof's auth hash is
+ *      already stored (i.e. the same bytes were submitted as a knowledge proof)
+ *
+ * @param proofs - the gzip-compressed fold proofs to verify
+ * @param backend - the proof system to verify the proofs with
+ * @returns - the (phrase_hash, auth_hash) output of each proof in the order given, or the error
+ *            response for the first proof that cannot be accepted
+ */
+async fn verify_phrase_proofs(
+    proofs: &Vec<Vec<u8>>,
+    db: &GrapevineDB,
+    backend: &ProofBackend,
+) -> Result<Vec<([u8; 32], [u8; 32])>, GrapevineResponse> {
+    // decompress and verify the proofs in parallel off of the async workers
+    let batch = proofs
+        .iter()
+        .map(|proof| (proof.clone(), degree_iterations(1)))
+        .collect();
+    let verify_results =
+        match verify_proofs_with_timeout(backend, batch, *VERIFICATION_TIMEOUT).await {
+            Ok(verify_results) => verify_results,
+            Err(e) => return Err(e.into()),
+        };
+    let mut outputs: Vec<([u8; 32], [u8; 32])> = Vec::new();
+    for verify_res in verify_results {
+        let (phrase_hash, auth_hash) =
+            match verify_res.and_then(|res| PublicOutputs::from_verification(&res)) {
+                Ok(outputs) => (outputs.phrase_hash, outputs.auth_hash),
+                Err(e) => {
+                    println!("Proof verification failed: {:?}", e);
+                    return Err(e.into());
+                }
+            };
+        let phrase_hash_bytes = phrase_hash.to_bytes();
+        // reject the proof if an operator has frozen this phrase
+        match db.phrase_frozen(&phrase_hash_bytes).await {
+            Ok(false) => (),
+            Ok(true) => {
+                return Err(GrapevineServerError::PhraseFrozen(fr_to_hex(&phrase_hash)).into())
+            }
+            Err(e) => return Err(e.into()),
+        };
+        // reject the proof if another degree 1 proof already originated this phrase
+        let duplicate = outputs.iter().any(|output| output.0 == phrase_hash_bytes);
+        match db.phrase_originated(&phrase_hash_bytes).await {
+            Ok(false) if !duplicate => (),
+            Ok(_) => {
+                return Err(
+                    GrapevineServerError::PhraseAlreadyOriginated(fr_to_hex(&phrase_hash)).into(),
+                )
+            }
+            Err(e) => return Err(e.into()),
+        };
+        // a knowledge proof is the same single step proof, so its bytes cannot also originate
+        let auth_hash_bytes = auth_hash.to_bytes();
+        match db.auth_hash_stored(&auth_hash_bytes).await {
+            Ok(false) => (),
+            Ok(true) => {
+                return Err(GrapevineServerError::ProofReplayed(fr_to_hex(&auth_hash)).into())
+            }
+            Err(e) => return Err(e.into()),
+        };
+        outputs.push((phrase_hash_bytes, auth_hash_bytes));
+    }
+    Ok(outputs)
+}
+
+/**
+ * Add verified degree 1 proofs to the database
+ * @dev the batch is all or nothing, so if any proof fails to be added the proofs already added
+ *      are discarded and no webhook notifications are sent
+ *
+ * @param username - the authenticated user the proofs are attributed to
+ * @param proofs - the gzip-compressed fold proofs
+ * @param outputs - the (phrase_hash, auth_hash) output of each proof from verify_phrase_proofs
+ * @param anonymous - whether to withhold the user as the originator of the phrases
+ * @param webhook - the webhook notified of each added proof
+ * @returns - status 201 if every proof was added, or the error response otherwise
+ */
+async fn insert_phrase_proofs(
+    username: &String,
+    proofs: Vec<Vec<u8>>,
+    outputs: Vec<([u8; 32], [u8; 32])>,
+    anonymous: bool,
+    db: &GrapevineDB,
+    webhook: &WebhookConfig,
+) -> Result<Status, GrapevineResponse> {
+    // get user doc
+    let user = match db.get_user(username).await {
+        Some(user) => user,
+        None => return Err(GrapevineServerError::UserNotFound(username.clone()).into()),
+    };
+    let mut added: Vec<(ObjectId, [u8; 32])> = Vec::new();
+    for (proof, (phrase_hash, auth_hash)) in proofs.into_iter().zip(outputs) {
+        // build DegreeProof model
+        let proof_doc = DegreeProof {
+            id: None,
+            inactive: Some(false),
+            phrase_hash: Some(phrase_hash),
+            auth_hash: Some(auth_hash),
+            user: Some(user.id.unwrap()),
+            degree: Some(1),
+            proof_size: decompressed_size(&proof).ok(),
+            params_version: Some(CIRCUIT_INFO.public_params_hash.clone()),
+            created_at: Some(DateTime::now()),
+            proof: Some(proof),
+            preceding: None,
+            proceeding: Some(vec![]),
+            anonymous: Some(anonymous),
+        };
+
+        match db.add_proof(&user.id.unwrap(), &proof_doc).await {
+            Ok(oid) => added.push((oid, phrase_hash)),
+            Err(e) => {
+                println!("Error adding proof: {:?}", e);
+                let oids: Vec<ObjectId> = added.iter().map(|(oid, _)| *oid).collect();
+                if let Err(e) = db.discard_proofs(&oids).await {
+                    println!("Error discarding batch proofs: {:?}", e);
+                }
+                degree_cache::invalidate_all(db);
+                return Err(GrapevineServerError::MongoError(String::from(
+                    "Failed to add proof to db",
+                ))
+                .into());
+            }
+        }
+    }
+    let username = (!anonymous).then(|| username.as_str());
+    for (oid, phrase_hash) in added {
+        webhook.notify(ProofNotification::new(&phrase_hash, 1, username, &oid));
+    }
+    degree_cache::invalidate_all(db);
+    Ok(Status::Created)
+}
+
+/**
+ * Verify a DegreeProofRequest body and add its proof to the database
+ *
+ * @param username - the authenticated user the proof is attributed to
+ * @param buffer - the binary serialized DegreeProofRequest
+ * @param webhook - the webhook notified of the added proof
+ * @param backend - the proof system to verify the proof with
+ * @returns - status 201 if the proof was added, or the error response otherwise
+ */
+async fn add_degree_proof(
+    username: &String,
+    buffer: &[u8],
+    db: &GrapevineDB,
+    webhook: &WebhookConfig,
+    backend: &ProofBackend,
+) -> Result<Status, GrapevineResponse> {
+    let request = match deserialize_request::<DegreeProofRequest>(buffer, "DegreeProofRequest") {
+        Ok(req) => req,
+        Err(e) => return Err(e.into()),
+    };
+    // reject proofs built on a proof made with outdated public params before verifying
+    // @dev proofs without a params version predate versioning and are assumed current
+    let previous = match ObjectId::from_str(&request.previous) {
+        Ok(previous) => previous,
+        Err(_) => return Err(GrapevineServerError::ProofNotFound(request.previous).into()),
+    };
+    let preceding = match db.get_proof(&previous).await {
+        Ok(Some(proof)) => match proof.params_version {
+            Some(ref version) if *version != CIRCUIT_INFO.public_params_hash => {
+                return Err(GrapevineServerError::ParamsVersionMismatch(previous.to_hex()).into())
+            }
+            _ => proof,
+        },
+        Ok(None) => return Err(GrapevineServerError::ProofNotFound(previous.to_hex()).into()),
+        Err(e) => return Err(e.into()),
+    };
+    // reject proofs extending a phrase an operator has frozen before verifying
+    if let Some(phrase_hash) = preceding.phrase_hash {
+        match db.phrase_frozen(&phrase_hash).await {
+            Ok(false) => (),
+            Ok(true) => {
+                return Err(GrapevineServerError::PhraseFrozen(hex::encode(phrase_hash)).into())
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+    // get user doc
+    let user = db.get_user(username).await.unwrap();
+    // reject proofs continuing from a creator who never shared their auth secret with the user
+    // @dev the same relationship gates /proof/params/<oid>, so an oid alone is not enough
+    match db
+        .relationship_exists(&preceding.user.unwrap(), &user.id.unwrap())
+        .await
+    {
+        Ok(true) => (),
+        Ok(false) => return Err(GrapevineServerError::ProofNotEntitled(previous.to_hex()).into()),
+        Err(e) => return Err(e.into()),
+    }
+    // reject resubmissions of a continuation the user already made before verifying
+    match db.proof_continued_by(&user.id.unwrap(), &previous).await {
+        Ok(false) => (),
+        Ok(true) => return Err(GrapevineServerError::StaleProof(previous.to_hex()).into()),
+        Err(e) => return Err(e.into()),
+    }
+    // decompress and verify the proof off of the async workers
+    let iterations = degree_iterations(request.degree);
+    let verify_res = match verify_proof_with_timeout(
+        backend,
+        request.proof.clone(),
+        iterations,
+        *VERIFICATION_TIMEOUT,
+    )
+    .await
+    {
+        Ok(verify_res) => verify_res,
+        Err(e) => return Err(e.into()),
+    };
+    let (phrase_hash, auth_hash) =
+        match verify_res.and_then(|res| PublicOutputs::from_verification(&res)) {
+            Ok(outputs) => {
+                // the stored degree must be the degree the proof outputs, not just the one claimed
+                if outputs.degree != Fr::from(request.degree as u64) {
+                    return Err(GrapevineServerError::DegreeMismatch(request.degree).into());
+                }
+                (outputs.phrase_hash.to_bytes(), outputs.auth_hash.to_bytes())
+            }
+            Err(e) => {
+                println!("Proof verification failed: {:?}", e);
+                return Err(e.into());
+            }
+        };
+    // reject proofs whose outputs could not follow the proof they name
+    if let Err(e) = check_outputs_follow(&preceding, request.degree, &phrase_hash, &auth_hash) {
+        return Err(e.into());
+    }
+    // @TODO: needs to delete a previous proof by same user on same phrase hash if exists, including removing from last proof's previous field
+    // build DegreeProof struct
+    let proof_doc = DegreeProof {
+        id: None,
+        inactive: Some(false),
+        phrase_hash: Some(phrase_hash),
+        auth_hash: Some(auth_hash),
+        user: Some(user.id.unwrap()),
+        degree: Some(request.degree),
+        proof: Some(request.proof.clone()),
+        proof_size: decompressed_size(&request.proof).ok(),
+        params_version: Some(CIRCUIT_INFO.public_params_hash.clone()),
+        created_at: Some(DateTime::now()),
+        preceding: Some(previous),
+        proceeding: Some(vec![]),
+        anonymous: None,
+    };
+
+    // add proof to db and update references
+    match db.add_proof(&user.id.unwrap(), &proof_doc).await {
+        Ok(oid) => {
+            degree_cache::invalidate_all(db);
+            webhook.notify(ProofNotification::new(
+                &phrase_hash,
+                request.degree,
+                Some(username),
+                &oid,
+            ));
+            Ok(Status::Created)
+        }
+        Err(e) => {
+            println!("Error adding proof: {:?}", e);
+            Err(GrapevineServerError::MongoError(String::from("Failed to add proof to db")).into())
+        }
+    }
+}
+
+/**
+ * Check that the outputs of a verified degree proof are consistent with following a stored proof
+ * @dev this is not a chain check: the circuit only outputs the final degree, phrase hash and
+ *      auth hash, none of which commit to the step it was folded from. A valid proof of the
+ *      phrase at the right degree passes no matter which proof it was built on. What can be
+ *      checked is that the phrase hash carries through unchanged, the degree advances by one,
+ *      and the auth hash differs from the stored proof's
+ *
+ * @param preceding - the stored proof the new proof claims to build on
+ * @param degree - the degree output by the new proof
+ * @param phrase_hash - the phrase hash output by the new proof
+ * @param auth_hash - the auth hash output by the new proof
+ * @returns - ChainInvalid describing the first inconsistency found
+ */
+fn check_outputs_follow(
+    preceding: &DegreeProof,
+    degree: u8,
+    phrase_hash: &[u8; 32],
+    auth_hash: &[u8; 32],
+) -> Result<(), GrapevineServerError> {
+    let oid = preceding.id.unwrap().to_hex();
+    if preceding.degree == Some(0) {
+        return Err(GrapevineServerError::ChainInvalid(format!(
+            "proof {} proves knowledge of the phrase outside of its chain",
+            oid
+        )));
+    }
+    if preceding.phrase_hash.as_ref() != Some(phrase_hash) {
+        return Err(GrapevineServerError::ChainInvalid(format!(
+            "proof is for a different phrase than proof {}",
+            oid
+        )));
+    }
+    if preceding.degree.and_then(|degree| degree.checked_add(1)) != Some(degree) {
+        return Err(GrapevineServerError::ChainInvalid(format!(
+            "proof is not one degree above proof {}",
+            oid
+        )));
+    }
+    if preceding.auth_hash.as_ref() == Some(auth_hash) {
+        return Err(GrapevineServerError::ChainInvalid(format!(
+            "proof does not accumulate a new auth secret onto proof {}",
+            oid
+        )));
+    }
+    Ok(())
+}
+
+/**
+ * Deserialize a bincode request body, distinguishing truncated bodies from schema mismatches
+ * @dev trailing bytes are rejected so a body serialized from a different struct is not accepted
+ *
+ * @param buffer - the streamed request body
+ * @param name - the name of the expected request struct
+ * @returns - the deserialized request, or a SerdeError describing why it could not be read
+ */
+pub(crate) fn deserialize_request<T: DeserializeOwned>(
+    buffer: &[u8],
+    name: &str,
+) -> Result<T, GrapevineServerError> {
+    let options = bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .reject_trailing_bytes();
+    options.deserialize::<T>(buffer).map_err(|e| {
+        println!("Error deserializing body from binary to {}: {:?}", name, e);
+        match *e {
+            ErrorKind::Io(ref io) if io.kind() == std::io::ErrorKind::UnexpectedEof => {
+                GrapevineServerError::SerdeError(format!(
+                    "{}: unexpected end of input, body is truncated or exceeds the maximum proof size",
+                    name
+                ))
+            }
+            _ => GrapevineServerError::SerdeError(format!(
+                "{}: body does not match the expected schema ({})",
+                name, e
+            )),
+        }
+    })
 }
 
 // /**
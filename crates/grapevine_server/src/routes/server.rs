@@ -0,0 +1,17 @@
+use crate::signing::ServerKey;
+use rocket::State;
+
+/// GET REQUESTS ///
+
+/**
+ * Return the public key the server signs the proving data it returns with
+ * @notice: clients should pin this key and check X-Grapevine-Signature on /proof/params/<oid>
+ *
+ * @return - the hex encoded compressed baby jubjub pubkey of the server
+ * @return status:
+ *         - 200 if successful retrieval
+ */
+#[get("/pubkey")]
+pub async fn get_server_pubkey(server_key: &State<ServerKey>) -> String {
+    hex::encode(server_key.pubkey().compress())
+}
@@ -0,0 +1,82 @@
+use crate::catchers::GrapevineResponse;
+use crate::mongo::GrapevineDB;
+use grapevine_common::http::responses::{GlobalStats, TopConnector};
+use grapevine_common::MAX_TOP_CONNECTORS;
+use lazy_static::lazy_static;
+use rocket::{serde::json::Json, State};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/** How long computed global stats are served before the collections are counted again */
+const GLOBAL_STATS_TTL: Duration = Duration::from_secs(60);
+
+/** How many users are ranked by /stats/top-connectors if no limit is given */
+const DEFAULT_TOP_CONNECTORS: u32 = 10;
+
+lazy_static! {
+    static ref GLOBAL_STATS_CACHE: Mutex<Option<(Instant, GlobalStats)>> = Mutex::new(None);
+}
+
+/// GET REQUESTS ///
+
+/**
+ * Return aggregate counts across the grapevine service
+ * @notice: counts are cached for GLOBAL_STATS_TTL so may lag behind the database
+ *
+ * @return - a GlobalStats struct containing:
+ *         * users: the number of registered users
+ *         * phrases: the number of phrases created
+ *         * proofs: the number of degree proofs stored
+ *         * relationships: the number of relationships between users
+ * @return status:
+ *         - 200 if successful retrieval
+ *         - 500 if db fails or other unknown issue
+ */
+#[get("/global")]
+pub async fn get_global_stats(
+    db: &State<GrapevineDB>,
+) -> Result<Json<GlobalStats>, GrapevineResponse> {
+    // serve the cached stats if they have not expired
+    let cached = GLOBAL_STATS_CACHE.lock().unwrap().clone();
+    if let Some((computed, stats)) = cached {
+        if computed.elapsed() < GLOBAL_STATS_TTL {
+            return Ok(Json(stats));
+        }
+    }
+    // recount the collections and refresh the cache
+    let stats = match db.get_global_stats().await {
+        Ok(stats) => stats,
+        Err(e) => return Err(e.into()),
+    };
+    *GLOBAL_STATS_CACHE.lock().unwrap() = Some((Instant::now(), stats.clone()));
+    Ok(Json(stats))
+}
+
+/**
+ * Return the users with the most relationships for a leaderboard
+ * @notice: a relationship counts for both the sender and the recipient whether or not it has been
+ *          reciprocated, and deactivated users are not ranked
+ *
+ * @param limit - the number of users to return (default DEFAULT_TOP_CONNECTORS, clamped between
+ *                1 and MAX_TOP_CONNECTORS)
+ * @return - a vector of TopConnector structs ordered by most relationships then username,
+ *           each containing:
+ *         * username: the username of the user
+ *         * relationships: the number of relationships the user participates in
+ * @return status:
+ *         - 200 if successful retrieval
+ *         - 500 if db fails or other unknown issue
+ */
+#[get("/top-connectors?<limit>")]
+pub async fn get_top_connectors(
+    limit: Option<u32>,
+    db: &State<GrapevineDB>,
+) -> Result<Json<Vec<TopConnector>>, GrapevineResponse> {
+    let limit = limit
+        .unwrap_or(DEFAULT_TOP_CONNECTORS)
+        .clamp(1, MAX_TOP_CONNECTORS);
+    match db.get_top_connectors(limit).await {
+        Ok(connectors) => Ok(Json(connectors)),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -1,21 +1,40 @@
-use crate::catchers::{ErrorMessage, GrapevineResponse};
-use crate::guards::AuthenticatedUser;
+use crate::catchers::GrapevineResponse;
+use crate::degree_cache;
+use crate::guards::{AuthenticatedUser, RateLimited};
 use crate::mongo::GrapevineDB;
+use crate::registration::RegistrationConfig;
+use crate::session::SessionConfig;
+use crate::signing::ServerKey;
+use crate::utils::{
+    consume_challenge, issue_challenge, MAX_RELATIONSHIPS, MAX_RELATIONSHIP_REQUEST_SIZE, NDJSON,
+};
 use babyjubjub_rs::{decompress_point, decompress_signature, verify};
+use grapevine_common::crypto::{creation_hash, username_bytes};
 use grapevine_common::errors::GrapevineServerError;
 use grapevine_common::http::requests::GetNonceRequest;
-use grapevine_common::http::{requests::CreateUserRequest, responses::DegreeData};
-use grapevine_common::utils::convert_username_to_fr;
-use grapevine_common::MAX_USERNAME_CHARS;
+use grapevine_common::http::{
+    requests::CreateUserRequest,
+    responses::{
+        AvailableProof, BatchRelationshipResult, ConnectionGraph, Dashboard, DegreeData,
+        ReceivedAuthSecret, RelationshipData, RelationshipDiagnostics, RelationshipDirection,
+        RelationshipSummary, SessionData, SessionToken, SignedClaim,
+    },
+};
+use grapevine_common::nonce::Nonce;
+use grapevine_common::utils::{canonical_pubkey, fr_from_hex};
 use grapevine_common::{
-    http::requests::NewRelationshipRequest,
+    http::requests::{NewRelationshipRequest, UpdateRelationshipRequest},
     models::{relationship::Relationship, user::User},
 };
-use rocket::State;
+use grapevine_common::{MAX_GRAPH_DEPTH, MAX_RELATIONSHIP_BATCH, MAX_USERNAME_CHARS};
+use mongodb::bson::{oid::ObjectId, DateTime, Document};
+use rocket::{data::ToByteUnit, Data, State};
 
 use num_bigint::{BigInt, Sign};
-use rocket::http::Status;
+use rocket::http::{Accept, ContentType, Status};
+use rocket::response::stream::TextStream;
 use rocket::serde::json::Json;
+use rocket::Either;
 
 /// POST REQUESTS ///
 
@@ -25,110 +44,115 @@ use rocket::serde::json::Json;
  * @param data - the CreateUserRequest containing:
  *             * username: the username for the new user
  *             * pubkey: the public key used to authZ/authN and deriving AES encryption keys
- *             * signature: the signature over the username by pubkey
+ *             * challenge: the single-use challenge issued by GET /user/challenge
+ *             * signature: the signature over H|username, challenge| by pubkey
+ *             * invite: the invite token for this username (only required if registration is
+ *               closed)
  * @return status:
+ *             * 200 if the user already exists with the same username and pubkey (i.e. a retry)
  *             * 201 if success
  *             * 400 if username length exceeds 30 characters, username is not valid ASCII,
- *               invalid signature over username by pubkey, or issues deserializing request
- *             * 409 if username || pubkey are already in use by another user
+ *               invalid signature over username and challenge by pubkey, or issues
+ *               deserializing request
+ *             * 401 if the challenge was not issued, has expired, or was already used
+ *             * 403 if registration is closed and no valid invite for the username was given
+ *             * 409 if username || pubkey are already in use by another user (usernames differing
+ *               only in case and pubkeys encoding the same point are in use)
  *             * 500 if db fails or other unknown issue
  */
 #[post("/create", format = "json", data = "<request>")]
 pub async fn create_user(
     request: Json<CreateUserRequest>,
     db: &State<GrapevineDB>,
+    registration: &State<RegistrationConfig>,
 ) -> Result<GrapevineResponse, GrapevineResponse> {
+    // check the user is invited if registration is closed
+    if !registration.open {
+        let invited = match &request.invite {
+            Some(invite) => registration.verify_invite(invite, &request.username),
+            None => false,
+        };
+        if !invited {
+            return Err(GrapevineServerError::RegistrationClosed.into());
+        }
+    };
     // check username length is valid
-    if request.username.len() > MAX_USERNAME_CHARS {
-        return Err(GrapevineResponse::BadRequest(ErrorMessage(
-            Some(GrapevineServerError::UsernameTooLong(
-                request.username.clone(),
-            )),
-            None,
-        )));
+    // @dev counts characters rather than bytes so multibyte names fail the ascii check below
+    if request.username.chars().count() > MAX_USERNAME_CHARS {
+        return Err(GrapevineServerError::UsernameTooLong(request.username.clone()).into());
     };
     // check request is ascii
     if !request.username.is_ascii() {
-        return Err(GrapevineResponse::BadRequest(ErrorMessage(
-            Some(GrapevineServerError::UsernameNotAscii(
-                request.username.clone(),
-            )),
-            None,
-        )));
+        return Err(GrapevineServerError::UsernameNotAscii(request.username.clone()).into());
     };
-    // check the validity of the signature over the username
-    let message = BigInt::from_bytes_le(
-        Sign::Plus,
-        &convert_username_to_fr(&request.username).unwrap()[..],
-    );
+    // check the validity of the signature over the username and challenge
+    let hash = match creation_hash(&request.username, &request.challenge) {
+        Ok(hash) => hash,
+        Err(e) => return Err(e.into()),
+    };
+    let message = BigInt::from_bytes_le(Sign::Plus, &hash[..]);
     let pubkey_decompressed = decompress_point(request.pubkey).unwrap();
     let signature_decompressed = decompress_signature(&request.signature).unwrap();
     match verify(pubkey_decompressed, signature_decompressed, message) {
         true => (),
         false => {
-            return Err(GrapevineResponse::BadRequest(ErrorMessage(
-                Some(GrapevineServerError::Signature(String::from(
-                    "Could not verify user creation signature",
-                ))),
-                None,
-            )));
+            return Err(GrapevineServerError::Signature(String::from(
+                "Could not verify user creation signature",
+            ))
+            .into());
         }
     };
+    // store the pubkey in canonical form so every encoding of it collides
+    let pubkey = canonical_pubkey(&request.pubkey).unwrap();
     // check that the username or pubkey are not already used
-    match db
-        .check_creation_params(&request.username, &request.pubkey)
-        .await
-    {
+    match db.check_creation_params(&request.username, &pubkey).await {
         Ok(found) => match found {
             [true, true] => {
-                return Err(GrapevineResponse::Conflict(ErrorMessage(
-                    Some(GrapevineServerError::UserExists(request.username.clone())),
-                    None,
-                )));
+                // a resubmission of an already created user is treated as a success
+                // @dev checked before the challenge is redeemed, as a retry reuses the challenge
+                let existing = db.get_user(&request.username).await;
+                if existing.and_then(|user| user.pubkey) == Some(pubkey) {
+                    return Ok(GrapevineResponse::Ok(String::from("User already exists")));
+                }
+                return Err(GrapevineServerError::UserExists(request.username.clone()).into());
             }
             [true, false] => {
-                return Err(GrapevineResponse::Conflict(ErrorMessage(
-                    Some(GrapevineServerError::UsernameExists(
-                        request.username.clone(),
-                    )),
-                    None,
-                )));
+                return Err(GrapevineServerError::UsernameExists(request.username.clone()).into());
             }
             [false, true] => {
-                return Err(GrapevineResponse::Conflict(ErrorMessage(
-                    Some(GrapevineServerError::PubkeyExists(format!(
-                        "0x{}",
-                        hex::encode(request.pubkey.clone())
-                    ))),
-                    None,
-                )));
+                return Err(GrapevineServerError::PubkeyExists(format!(
+                    "0x{}",
+                    hex::encode(request.pubkey.clone())
+                ))
+                .into());
             }
             _ => (),
         },
-        Err(e) => {
-            return Err(GrapevineResponse::InternalError(ErrorMessage(
-                Some(e),
-                None,
-            )))
-        }
+        Err(e) => return Err(e.into()),
+    };
+    // redeem the challenge so the signed request cannot be replayed
+    if !consume_challenge(&request.challenge) {
+        return Err(GrapevineServerError::InvalidChallenge.into());
     };
     // create the new user in the database
     let user = User {
         id: None,
-        nonce: Some(0),
+        nonce: Some(Nonce::default()),
         username: Some(request.username.clone()),
-        pubkey: Some(request.pubkey.clone()),
+        pubkey: Some(pubkey),
         relationships: Some(vec![]),
         degree_proofs: Some(vec![]),
+        last_active: None,
+        deactivated: None,
+        sessions: None,
     };
+    // drop any stale materialized degrees left by a removed user of the same name
+    degree_cache::invalidate(db, &request.username);
     match db.create_user(user).await {
         Ok(_) => Ok(GrapevineResponse::Created(
             "User succefully created".to_string(),
         )),
-        Err(e) => Err(GrapevineResponse::InternalError(ErrorMessage(
-            Some(e),
-            None,
-        ))),
+        Err(e) => Err(e.into()),
     }
 }
 
@@ -142,25 +166,49 @@ pub async fn create_user(
  *             * ephemeral_key: the ephemeral pubkey that target can combine with their private
  *               key to derive AES key needed to decrypt auth secret
  *             * ciphertext: the encrypted auth secret
+ *             * nonce: the aes-gcm nonce the auth secret was encrypted under
  * @return status:
  *            * 201 if success
  *            * 400 if from == to or issues deserializing request
  *            * 401 if signanture or nonce mismatch for sender
+ *            * 403 if the sender already has MAX_RELATIONSHIPS relationships
  *            * 404 if from or to user does not exist
  *            * 409 if relationship already exists
+ *            * 413 if the request body exceeds MAX_RELATIONSHIP_REQUEST_SIZE bytes
  */
-#[post("/relationship", format = "json", data = "<request>")]
+#[post("/relationship", format = "json", data = "<data>")]
 pub async fn add_relationship(
     user: AuthenticatedUser,
-    request: Json<NewRelationshipRequest>,
+    data: Data<'_>,
     db: &State<GrapevineDB>,
 ) -> Result<Status, GrapevineResponse> {
+    // the request only carries a username and an encrypted auth secret, so cap it well below the
+    // global json limit
+    let buffer = match data
+        .open(MAX_RELATIONSHIP_REQUEST_SIZE.bytes())
+        .into_bytes()
+        .await
+    {
+        Ok(buffer) if buffer.is_complete() => buffer.into_inner(),
+        Ok(_) => {
+            return Err(GrapevineResponse::TooLarge(format!(
+                "Request body exceeds {} bytes",
+                MAX_RELATIONSHIP_REQUEST_SIZE
+            )))
+        }
+        Err(e) => return Err(GrapevineServerError::SerdeError(e.to_string()).into()),
+    };
+    let request = match serde_json::from_slice::<NewRelationshipRequest>(&buffer) {
+        Ok(request) => request,
+        Err(e) => {
+            return Err(
+                GrapevineServerError::SerdeError(format!("NewRelationshipRequest: {}", e)).into(),
+            )
+        }
+    };
     // ensure from != to
     if &user.0 == &request.to {
-        return Err(GrapevineResponse::BadRequest(ErrorMessage(
-            Some(GrapevineServerError::RelationshipSenderIsTarget),
-            None,
-        )));
+        return Err(GrapevineServerError::RelationshipSenderIsTarget.into());
     }
 
     let sender = db.get_user(&user.0).await.unwrap();
@@ -180,38 +228,313 @@ pub async fn add_relationship(
         recipient: Some(recipient),
         ephemeral_key: Some(request.ephemeral_key.clone()),
         ciphertext: Some(request.ciphertext.clone()),
+        nonce: request.nonce.clone(),
+        created_at: Some(DateTime::now()),
     };
 
-    match db.add_relationship(&relationship_doc).await {
-        Ok(_) => Ok(Status::Created),
+    match db
+        .add_relationship(&relationship_doc, *MAX_RELATIONSHIPS)
+        .await
+    {
+        Ok(_) => {
+            degree_cache::invalidate(db, &request.to);
+            Ok(Status::Created)
+        }
+        Err(e @ GrapevineServerError::RelationshipLimitReached(_))
+        | Err(e @ GrapevineServerError::RelationshipExists) => Err(e.into()),
         Err(e) => {
             println!("Error adding relationship: {:?}", e);
-            Err(GrapevineResponse::InternalError(ErrorMessage(
-                Some(GrapevineServerError::MongoError(String::from(
-                    "Failed to add relationship to db",
-                ))),
-                None,
-            )))
+            Err(
+                GrapevineServerError::MongoError(String::from("Failed to add relationship to db"))
+                    .into(),
+            )
+        }
+    }
+}
+
+/**
+ * Add many unidirectional relationships from the caller in one authenticated request
+ * @notice: each relationship is attempted independently so a failed item does not abort the rest
+ *
+ * @param data - a list of at most MAX_RELATIONSHIP_BATCH NewRelationshipRequests
+ * @return - a BatchRelationshipResult for each request in the order given containing:
+ *             * to: the username of the recipient
+ *             * status: the status code the relationship would receive from add_relationship
+ *             * error: the error adding the relationship if it failed
+ * @return status:
+ *            * 200 if the batch was processed
+ *            * 401 if signanture or nonce mismatch for sender
+ *            * 413 if the batch exceeds MAX_RELATIONSHIP_BATCH requests
+ *            * 500 if db fails or other unknown issue
+ */
+#[post("/relationships/batch", format = "json", data = "<request>")]
+pub async fn add_relationship_batch(
+    user: AuthenticatedUser,
+    request: Json<Vec<NewRelationshipRequest>>,
+    db: &State<GrapevineDB>,
+) -> Result<Json<Vec<BatchRelationshipResult>>, GrapevineResponse> {
+    if request.len() > MAX_RELATIONSHIP_BATCH {
+        return Err(GrapevineServerError::BatchTooLarge(request.len()).into());
+    }
+    // look up the sender and all recipients at once
+    let mut usernames: Vec<String> = request.iter().map(|item| item.to.clone()).collect();
+    usernames.push(user.0.clone());
+    let users = match db.get_users_by_username(&usernames).await {
+        Ok(users) => users,
+        Err(e) => return Err(e.into()),
+    };
+    let find_user = |username: &String| {
+        users
+            .iter()
+            .find(|found| found.username.as_ref() == Some(username))
+            .map(|found| found.id.unwrap())
+    };
+    let sender = find_user(&user.0).unwrap();
+
+    let mut results: Vec<BatchRelationshipResult> = vec![];
+    for item in request.iter() {
+        // would be nice to have a zk proof of correct encryption to each recipient...
+        let outcome = if item.to == user.0 {
+            Err(GrapevineServerError::RelationshipSenderIsTarget)
+        } else {
+            match find_user(&item.to) {
+                Some(recipient) => {
+                    let relationship_doc = Relationship {
+                        id: None,
+                        sender: Some(sender),
+                        recipient: Some(recipient),
+                        ephemeral_key: Some(item.ephemeral_key.clone()),
+                        ciphertext: Some(item.ciphertext.clone()),
+                        nonce: item.nonce.clone(),
+                        created_at: Some(DateTime::now()),
+                    };
+                    let res = db
+                        .add_relationship(&relationship_doc, *MAX_RELATIONSHIPS)
+                        .await;
+                    degree_cache::invalidate(db, &item.to);
+                    res.map(|_| ())
+                }
+                None => Err(GrapevineServerError::UserNotFound(item.to.clone())),
+            }
+        };
+        results.push(match outcome {
+            Ok(_) => BatchRelationshipResult {
+                to: item.to.clone(),
+                status: Status::Created.code,
+                error: None,
+            },
+            Err(e) => BatchRelationshipResult {
+                to: item.to.clone(),
+                status: GrapevineResponse::from(e.clone()).status().code,
+                error: Some(e),
+            },
+        });
+    }
+    Ok(Json(results))
+}
+
+/// PUT REQUESTS ///
+
+/**
+ * Replace the encrypted auth secret shared with a recipient in an existing relationship
+ * @notice: the relationship keeps its ObjectID and creation timestamp
+ *
+ * @param username - the username of the relationship recipient
+ * @param data - the UpdateRelationshipRequest containing:
+ *             * ephemeral_key: the new ephemeral pubkey that target can combine with their
+ *               private key to derive AES key needed to decrypt auth secret
+ *             * ciphertext: the newly encrypted auth secret
+ *             * nonce: the aes-gcm nonce the new auth secret was encrypted under
+ * @return status:
+ *            * 200 if success
+ *            * 401 if signature or nonce mismatch for sender
+ *            * 403 if the caller is only the recipient of a relationship with the target
+ *            * 404 if recipient or relationship does not exist
+ *            * 500 if db fails or other unknown issue
+ */
+#[put("/relationship/<username>", format = "json", data = "<request>")]
+pub async fn update_relationship(
+    user: AuthenticatedUser,
+    username: String,
+    request: Json<UpdateRelationshipRequest>,
+    db: &State<GrapevineDB>,
+) -> Result<Status, GrapevineResponse> {
+    let sender = db.get_user(&user.0).await.unwrap().id.unwrap();
+    let recipient = match db.get_user(&username).await {
+        Some(user) => user.id.unwrap(),
+        None => return Err(GrapevineServerError::UserNotFound(username).into()),
+    };
+    // only the sender of the relationship may replace the shared auth secret
+    let relationship = match db.get_relationship(&sender, &recipient).await {
+        Some(relationship) => relationship,
+        None => match db.get_relationship(&recipient, &sender).await {
+            Some(_) => return Err(GrapevineServerError::RelationshipNotSender(username).into()),
+            None => return Err(GrapevineServerError::RelationshipNotFound(user.0, username).into()),
+        },
+    };
+
+    match db
+        .update_relationship(
+            &relationship.id.unwrap(),
+            &request.ephemeral_key,
+            &request.ciphertext,
+            &request.nonce,
+        )
+        .await
+    {
+        Ok(_) => Ok(Status::Ok),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// DELETE REQUESTS ///
+
+/**
+ * Remove a relationship the caller sent, revoking the recipient's access to the caller's proofs
+ * @notice: the relationship is removed from both users' relationships arrays
+ *
+ * @param username - the username of the relationship recipient
+ * @return status:
+ *            * 200 if success
+ *            * 401 if signature or nonce mismatch for sender
+ *            * 403 if the caller is only the recipient of a relationship with the target
+ *            * 404 if recipient or relationship does not exist
+ *            * 500 if db fails or other unknown issue
+ */
+#[delete("/relationship/<username>")]
+pub async fn delete_relationship(
+    user: AuthenticatedUser,
+    username: String,
+    db: &State<GrapevineDB>,
+) -> Result<Status, GrapevineResponse> {
+    let sender = db.get_user(&user.0).await.unwrap().id.unwrap();
+    let recipient = match db.get_user(&username).await {
+        Some(user) => user.id.unwrap(),
+        None => return Err(GrapevineServerError::UserNotFound(username).into()),
+    };
+    // only the sender of the relationship may remove it
+    let relationship = match db.get_relationship(&sender, &recipient).await {
+        Some(relationship) => relationship,
+        None => match db.get_relationship(&recipient, &sender).await {
+            Some(_) => return Err(GrapevineServerError::RelationshipNotSender(username).into()),
+            None => return Err(GrapevineServerError::RelationshipNotFound(user.0, username).into()),
+        },
+    };
+
+    match db.delete_relationship(&relationship).await {
+        Ok(_) => {
+            degree_cache::invalidate(db, &username);
+            Ok(Status::Ok)
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/**
+ * Withdraw a pending relationship the caller sent before the recipient sent one back
+ * @notice: once the recipient has sent a relationship back it is confirmed, and must be removed
+ *          through DELETE /user/relationship/<username> instead
+ *
+ * @param username - the username of the relationship recipient
+ * @return status:
+ *            * 200 if success
+ *            * 401 if signature or nonce mismatch for sender
+ *            * 404 if recipient or pending relationship does not exist
+ *            * 409 if the recipient has sent a relationship back
+ *            * 500 if db fails or other unknown issue
+ */
+#[delete("/relationship/sent/<username>")]
+pub async fn withdraw_relationship(
+    user: AuthenticatedUser,
+    username: String,
+    db: &State<GrapevineDB>,
+) -> Result<Status, GrapevineResponse> {
+    let sender = db.get_user(&user.0).await.unwrap().id.unwrap();
+    let recipient = match db.get_user(&username).await {
+        Some(user) => user.id.unwrap(),
+        None => return Err(GrapevineServerError::UserNotFound(username).into()),
+    };
+    let relationship = match db.get_relationship(&sender, &recipient).await {
+        Some(relationship) => relationship,
+        None => return Err(GrapevineServerError::RelationshipNotFound(user.0, username).into()),
+    };
+    // a relationship sent back by the recipient confirms it
+    if db.get_relationship(&recipient, &sender).await.is_some() {
+        return Err(GrapevineServerError::RelationshipConfirmed(username).into());
+    }
+
+    match db.delete_relationship(&relationship).await {
+        Ok(_) => {
+            degree_cache::invalidate(db, &username);
+            Ok(Status::Ok)
         }
+        Err(e) => Err(e.into()),
     }
 }
 
 /// GET REQUESTS ///
 
 /**
- * @todo: remove / replace with get nonce
+ * Issue a single-use challenge that must be signed when creating a new user
+ *
+ * @return - the hex encoded 32 byte challenge
+ * @return status:
+ *            * 200 if success
+ */
+#[get("/challenge")]
+pub async fn get_challenge() -> String {
+    hex::encode(issue_challenge())
+}
+
+/**
+ * Return the public view of a user
+ * @notice: the nonce and relationship and degree proof references are only returned to the user
+ *          themselves by /user/<username>/full
+ *
+ * @param username - the username of the user to look up
+ * @return - a User struct containing only the username and pubkey
+ * @return status:
+ *            * 200 if success
+ *            * 404 if user not found
+ *            * 500 if db fails or other unknown issue
  */
 #[get("/<username>")]
 pub async fn get_user(
     username: String,
     db: &State<GrapevineDB>,
 ) -> Result<Json<User>, GrapevineResponse> {
-    match db.get_user(&username).await {
-        Some(user) => Ok(Json(user)),
-        None => Err(GrapevineResponse::NotFound(format!(
-            "User {} does not exist.",
-            username
-        ))),
+    match db.get_public_user(&username).await {
+        Ok(Some(user)) => Ok(Json(user)),
+        Ok(None) => Err(GrapevineServerError::UserNotFound(username).into()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/**
+ * Return the full user document to the user it belongs to
+ *
+ * @param username - the username of the user to look up (must be the authenticated user)
+ * @return - the full User struct, including the nonce, relationships, and degree_proofs
+ * @return status:
+ *            * 200 if success
+ *            * 401 if signature mismatch or nonce mismatch
+ *            * 403 if the authenticated user is not the user being looked up
+ *            * 404 if user not found
+ *            * 500 if db fails or other unknown issue
+ */
+#[get("/<username>/full")]
+pub async fn get_full_user(
+    user: AuthenticatedUser,
+    username: String,
+    db: &State<GrapevineDB>,
+) -> Result<Json<User>, GrapevineResponse> {
+    if user.0 != username {
+        return Err(GrapevineServerError::ProfileNotPermitted(username).into());
+    }
+    match db.get_full_user(&username).await {
+        Ok(Some(user)) => Ok(Json(user)),
+        Ok(None) => Err(GrapevineServerError::UserNotFound(username).into()),
+        Err(e) => Err(e.into()),
     }
 }
 
@@ -223,28 +546,24 @@ pub async fn get_nonce(
     // get pubkey & nonce for user
     let (nonce, pubkey) = match db.get_nonce(&request.username).await {
         Some((nonce, pubkey)) => (nonce, pubkey),
-        None => {
-            return Err(GrapevineResponse::NotFound(String::from(
-                "User not does not exist.",
-            )))
-        }
+        None => return Err(GrapevineServerError::UserNotFound(request.username.clone()).into()),
     };
     // check the validity of the signature over the username
-    let message = BigInt::from_bytes_le(
-        Sign::Plus,
-        &convert_username_to_fr(&request.username).unwrap()[..],
-    );
+    // @dev a stored username that bypassed registration checks may not encode
+    let username = match username_bytes(&request.username) {
+        Ok(username) => username,
+        Err(e) => return Err(e.into()),
+    };
+    let message = BigInt::from_bytes_le(Sign::Plus, &username[..]);
     let pubkey_decompressed = decompress_point(pubkey).unwrap();
     let signature_decompressed = decompress_signature(&request.signature).unwrap();
     match verify(pubkey_decompressed, signature_decompressed, message) {
         true => (),
         false => {
-            return Err(GrapevineResponse::BadRequest(ErrorMessage(
-                Some(GrapevineServerError::Signature(String::from(
-                    "Could not verify nonce recovery signature",
-                ))),
-                None,
-            )));
+            return Err(GrapevineServerError::Signature(String::from(
+                "Could not verify nonce recovery signature",
+            ))
+            .into());
         }
     };
     // return the stringified nonce
@@ -266,11 +585,400 @@ pub async fn get_pubkey(
     username: String,
     db: &State<GrapevineDB>,
 ) -> Result<String, GrapevineResponse> {
-    match db.get_pubkey(username).await {
+    match db.get_pubkey(username.clone()).await {
         Some(pubkey) => Ok(hex::encode(pubkey)),
-        None => Err(GrapevineResponse::NotFound(String::from(
-            "User not does not exist.",
-        ))),
+        None => Err(GrapevineServerError::UserNotFound(username).into()),
+    }
+}
+
+/**
+ * Return a server-signed answer to whether a user is within some degree of separation of a phrase
+ * @notice: unlike /verify/claim the answer is signed, so a negative ("not within n degrees") claim
+ *          can be shown to third parties. Only the user the claim is about can request it, so
+ *          nobody else can probe which phrases a user is or is not connected to
+ *
+ * @param username - the user the claim is about (must be the authenticated user)
+ * @param phrase_hash - the hex encoded poseidon hash of the phrase
+ * @param n - the highest degree of separation that satisfies the claim
+ * @return - a SignedClaim struct containing:
+ *             * username: the user the claim is about
+ *             * phrase_hash: the poseidon hash of the phrase
+ *             * max_degree: n
+ *             * within: true if the user holds a proof for the phrase at degree <= n
+ *             * issued_at: when the claim was answered in milliseconds since the unix epoch
+ *             * signature: the server's signature over the claim, verifiable with
+ *               crypto::verify_signed_claim against /server/pubkey
+ * @return status:
+ *            * 200 if the claim was checked, whether or not it holds
+ *            * 400 if the phrase hash is not a hex encoded canonical field element
+ *            * 401 if signature mismatch or nonce mismatch
+ *            * 403 if the authenticated user is not the user the claim is about
+ *            * 500 if db fails or other unknown issue
+ */
+#[get("/<username>/within/<phrase_hash>/<n>")]
+pub async fn get_signed_claim(
+    user: AuthenticatedUser,
+    username: String,
+    phrase_hash: String,
+    n: u8,
+    db: &State<GrapevineDB>,
+    server_key: &State<ServerKey>,
+) -> Result<Json<SignedClaim>, GrapevineResponse> {
+    if user.0 != username {
+        return Err(GrapevineServerError::ClaimNotPermitted(username).into());
+    }
+    let phrase_hash = match fr_from_hex(&phrase_hash) {
+        Ok(phrase_hash) => phrase_hash.to_bytes(),
+        Err(e) => {
+            return Err(GrapevineServerError::SerdeError(format!("phrase hash: {}", e)).into())
+        }
+    };
+    let within = match db.has_proof_within(&username, &phrase_hash, n).await {
+        Ok(within) => within,
+        Err(e) => return Err(e.into()),
+    };
+    let issued_at = DateTime::now().timestamp_millis();
+    let signature = server_key
+        .sign_within_claim(&username, &phrase_hash, n, within, issued_at)
+        .compress();
+    Ok(Json(SignedClaim {
+        username,
+        phrase_hash,
+        max_degree: n,
+        within,
+        issued_at,
+        signature,
+    }))
+}
+
+/**
+ * Return the username of the user with a given public key
+ * @notice: rate limited by client so the user base cannot be enumerated by key
+ * @dev ranked below /<username>/pubkey so a user named "by-pubkey" keeps their pubkey route
+ *
+ * @param pubkey - the hex encoded compressed public key to look up
+ * @return - the username of the user whose pubkey encodes the same point
+ * @return status:
+ *            * 200 if success
+ *            * 400 if the pubkey is not 32 hex encoded bytes
+ *            * 404 if no user has the pubkey
+ *            * 429 if the client has made too many lookups
+ *            * 500 if db fails or other unknown issue
+ */
+#[get("/by-pubkey/<pubkey>", rank = 2)]
+pub async fn get_user_by_pubkey(
+    _limit: RateLimited,
+    pubkey: String,
+    db: &State<GrapevineDB>,
+) -> Result<String, GrapevineResponse> {
+    let decoded: [u8; 32] = match hex::decode(pubkey.trim_start_matches("0x")) {
+        Ok(bytes) => match bytes.try_into() {
+            Ok(decoded) => decoded,
+            Err(_) => {
+                return Err(GrapevineServerError::SerdeError(String::from(
+                    "pubkey must be 32 bytes",
+                ))
+                .into())
+            }
+        },
+        Err(e) => return Err(GrapevineServerError::SerdeError(e.to_string()).into()),
+    };
+    match db.get_username_by_pubkey(&decoded).await {
+        Ok(Some(username)) => Ok(username),
+        Ok(None) => Err(GrapevineServerError::UserNotFound(pubkey).into()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/**
+ * Return the details of the relationship between the caller and a counterparty
+ * @notice: a relationship is confirmed once the counterparty has added one in the other direction
+ * @notice: if relationships exist in both directions the one sent by the caller is returned
+ * @dev ranked below /<username>/pubkey so a user named "relationship" keeps their pubkey route
+ *
+ * @param username - the username of the counterparty in the relationship
+ * @return - a RelationshipData struct containing:
+ *             * username: the username of the counterparty
+ *             * direction: "sent" if the caller is the sender, "received" if the recipient
+ *             * confirmed: whether the relationship has been reciprocated
+ *             * ephemeral_key: the ephemeral pubkey used to derive the AES key
+ *             * ciphertext: the encrypted auth secret
+ *             * nonce: the aes-gcm nonce (None if legacy aes-cbc)
+ * @return status:
+ *            * 200 if success
+ *            * 401 if signature mismatch or nonce mismatch
+ *            * 404 if counterparty or relationship does not exist
+ *            * 500 if db fails or other unknown issue
+ */
+#[get("/relationship/<username>", rank = 2)]
+pub async fn get_relationship(
+    user: AuthenticatedUser,
+    username: String,
+    db: &State<GrapevineDB>,
+) -> Result<Json<RelationshipData>, GrapevineResponse> {
+    let caller = db.get_user(&user.0).await.unwrap().id.unwrap();
+    let counterparty = match db.get_user(&username).await {
+        Some(user) => user.id.unwrap(),
+        None => return Err(GrapevineServerError::UserNotFound(username).into()),
+    };
+    let (relationship, direction) = match db.get_relationship(&caller, &counterparty).await {
+        Some(relationship) => (relationship, RelationshipDirection::Sent),
+        None => match db.get_relationship(&counterparty, &caller).await {
+            Some(relationship) => (relationship, RelationshipDirection::Received),
+            None => return Err(GrapevineServerError::RelationshipNotFound(user.0, username).into()),
+        },
+    };
+    // the relationship is confirmed if its recipient has one back to its sender
+    let (sender, recipient) = (
+        relationship.sender.unwrap(),
+        relationship.recipient.unwrap(),
+    );
+    let confirmed = match db.relationship_exists(&recipient, &sender).await {
+        Ok(confirmed) => confirmed,
+        Err(e) => return Err(e.into()),
+    };
+    Ok(Json(RelationshipData {
+        username,
+        direction,
+        confirmed,
+        ephemeral_key: relationship.ephemeral_key.unwrap(),
+        ciphertext: relationship.ciphertext.unwrap(),
+        nonce: relationship.nonce,
+    }))
+}
+
+/**
+ * Check whether the stored encryption of a relationship's auth secret is well formed
+ * @notice: helps tell a bad encryption by the sender from a client bug when the recipient cannot
+ *          decrypt, without returning the ciphertext or anything derived from the auth secret
+ * @dev reads the raw document so malformed fields are reported rather than failing to deserialize
+ *
+ * @param username - the username of the counterparty in the relationship
+ * @return - a RelationshipDiagnostics struct containing:
+ *             * username: the username of the counterparty
+ *             * direction: "sent" if the caller is the sender, "received" if the recipient
+ *             * ephemeral_key_valid: whether the ephemeral key decompresses to a curve point
+ *             * ciphertext_length: the number of stored ciphertext bytes
+ *             * legacy: whether the auth secret was encrypted with aes-cbc (no aes-gcm nonce)
+ *             * format_intact: whether the nonce and ciphertext have the expected lengths
+ * @return status:
+ *            * 200 if success
+ *            * 401 if signature mismatch or nonce mismatch
+ *            * 404 if counterparty or relationship does not exist
+ *            * 500 if db fails or other unknown issue
+ */
+#[get("/relationship/<username>/diagnose")]
+pub async fn diagnose_relationship(
+    user: AuthenticatedUser,
+    username: String,
+    db: &State<GrapevineDB>,
+) -> Result<Json<RelationshipDiagnostics>, GrapevineResponse> {
+    let caller = db.get_user(&user.0).await.unwrap().id.unwrap();
+    let counterparty = match db.get_user(&username).await {
+        Some(user) => user.id.unwrap(),
+        None => return Err(GrapevineServerError::UserNotFound(username).into()),
+    };
+    let sent = match db.get_relationship_document(&caller, &counterparty).await {
+        Ok(sent) => sent,
+        Err(e) => return Err(e.into()),
+    };
+    let (document, direction) = match sent {
+        Some(document) => (document, RelationshipDirection::Sent),
+        None => match db.get_relationship_document(&counterparty, &caller).await {
+            Ok(Some(document)) => (document, RelationshipDirection::Received),
+            Ok(None) => {
+                return Err(GrapevineServerError::RelationshipNotFound(user.0, username).into())
+            }
+            Err(e) => return Err(e.into()),
+        },
+    };
+    Ok(Json(diagnose(username, direction, &document)))
+}
+
+/**
+ * Return every relationship the caller is the sender or recipient of
+ * @notice: a counterparty the caller has relationships with in both directions is listed once
+ *          in each direction, with both entries confirmed
+ * @dev static, so it is matched before /<username>/full for a user named "relationships"
+ *
+ * @return - a RelationshipSummary struct for each relationship, oldest first:
+ *             * username: the username of the counterparty
+ *             * direction: "sent" if the caller added the counterparty (outbound), "received" if
+ *               the counterparty added the caller (inbound)
+ *             * confirmed: whether the counterparty has a relationship in the other direction
+ * @return status:
+ *            * 200 if success
+ *            * 401 if signature mismatch or nonce mismatch
+ *            * 500 if db fails or other unknown issue
+ */
+#[get("/relationships/full")]
+pub async fn get_relationship_summaries(
+    user: AuthenticatedUser,
+    db: &State<GrapevineDB>,
+) -> Result<Json<Vec<RelationshipSummary>>, GrapevineResponse> {
+    let caller = db.get_user(&user.0).await.unwrap().id.unwrap();
+    match db.get_relationship_summaries(&caller).await {
+        Ok(summaries) => Ok(Json(summaries)),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/**
+ * Return the auth secrets other users have encrypted to the caller
+ * @notice: lets a user who still controls their key but lost local state recover what they were
+ *          given, as each ciphertext can only be decrypted by the caller's private key
+ *
+ * @return - a ReceivedAuthSecret struct for each relationship the caller is the recipient of:
+ *             * sender: the username of the user who added the relationship
+ *             * ephemeral_key: the ephemeral pubkey used to derive the AES key
+ *             * ciphertext: the encrypted auth secret
+ *             * nonce: the aes-gcm nonce (None if legacy aes-cbc)
+ * @return status:
+ *            * 200 if success
+ *            * 401 if signature mismatch or nonce mismatch
+ *            * 500 if db fails or other unknown issue
+ */
+#[get("/secrets")]
+pub async fn get_received_secrets(
+    user: AuthenticatedUser,
+    db: &State<GrapevineDB>,
+) -> Result<Json<Vec<ReceivedAuthSecret>>, GrapevineResponse> {
+    let recipient = db.get_user(&user.0).await.unwrap().id.unwrap();
+    match db.get_received_relationships(&recipient).await {
+        Ok(secrets) => Ok(Json(secrets)),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/**
+ * Issue a session token that authenticates requests in place of a signed nonce
+ * @notice: the token is sent in the X-Session header alongside X-Username until it expires or is
+ *          revoked, so a user can cut off a device by revoking the session it holds
+ *
+ * @return - a SessionToken struct containing:
+ *             * id: the id used to list or revoke the session
+ *             * token: the signed session token
+ *             * expires_at: when the session expires (ms since unix epoch)
+ * @return status:
+ *            * 201 if success
+ *            * 401 if signature mismatch or nonce mismatch
+ *            * 403 if the request was authenticated by a session token
+ *            * 500 if db fails or other unknown issue
+ */
+#[post("/session")]
+pub async fn create_session(
+    user: AuthenticatedUser,
+    config: &State<SessionConfig>,
+    db: &State<GrapevineDB>,
+) -> Result<(Status, Json<SessionToken>), GrapevineResponse> {
+    // a stolen session token must not be able to outlive its revocation by minting another
+    if user.1.is_some() {
+        return Err(GrapevineServerError::SessionNotPermitted.into());
+    }
+    let (session, token) = config.issue(&user.0);
+    if let Err(e) = db.add_session(&user.0, &session).await {
+        return Err(e.into());
+    }
+    Ok((
+        Status::Created,
+        Json(SessionToken {
+            id: session.id,
+            token,
+            expires_at: session.expires_at.timestamp_millis(),
+        }),
+    ))
+}
+
+/**
+ * Return the caller's sessions that have not expired or been revoked
+ *
+ * @return - a SessionData struct for each active session containing:
+ *             * id: the id used to revoke the session
+ *             * created_at: when the session was issued (ms since unix epoch)
+ *             * expires_at: when the session expires (ms since unix epoch)
+ * @return status:
+ *            * 200 if success
+ *            * 401 if signature mismatch, nonce mismatch, or invalid session
+ *            * 500 if db fails or other unknown issue
+ */
+#[get("/sessions")]
+pub async fn get_sessions(
+    user: AuthenticatedUser,
+    db: &State<GrapevineDB>,
+) -> Result<Json<Vec<SessionData>>, GrapevineResponse> {
+    match db.get_sessions(&user.0).await {
+        Ok(sessions) => Ok(Json(
+            sessions
+                .into_iter()
+                .map(|session| SessionData {
+                    id: session.id,
+                    created_at: session.created_at.timestamp_millis(),
+                    expires_at: session.expires_at.timestamp_millis(),
+                })
+                .collect(),
+        )),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/**
+ * Revoke one of the caller's sessions so its token is rejected by every authenticated route
+ *
+ * @param id - the id of the session to revoke
+ * @return status:
+ *            * 200 if success
+ *            * 401 if signature mismatch, nonce mismatch, or invalid session
+ *            * 404 if the caller has no session with the id
+ *            * 500 if db fails or other unknown issue
+ */
+#[delete("/session/<id>")]
+pub async fn revoke_session(
+    user: AuthenticatedUser,
+    id: String,
+    db: &State<GrapevineDB>,
+) -> Result<Status, GrapevineResponse> {
+    match db.remove_session(&user.0, &id).await {
+        Ok(true) => Ok(Status::Ok),
+        Ok(false) => Err(GrapevineServerError::SessionNotFound(id).into()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/**
+ * Check whether a relationship links two users in the queried direction
+ * @notice: only a participant can query an edge so the relationship graph is not leaked
+ *
+ * @param from - the username of the relationship sender
+ * @param to - the username of the relationship recipient
+ * @return - true if a relationship from `from` to `to` exists
+ * @return status:
+ *            * 200 if success
+ *            * 401 if signature mismatch or nonce mismatch
+ *            * 403 if the caller is neither `from` nor `to`
+ *            * 404 if either user does not exist
+ *            * 500 if db fails or other unknown issue
+ */
+#[get("/exists/<from>/<to>")]
+pub async fn relationship_exists(
+    user: AuthenticatedUser,
+    from: String,
+    to: String,
+    db: &State<GrapevineDB>,
+) -> Result<Json<bool>, GrapevineResponse> {
+    if user.0 != from && user.0 != to {
+        return Err(GrapevineServerError::RelationshipNotParticipant.into());
+    }
+    let sender = match db.get_user(&from).await {
+        Some(user) => user.id.unwrap(),
+        None => return Err(GrapevineServerError::UserNotFound(from).into()),
+    };
+    let recipient = match db.get_user(&to).await {
+        Some(user) => user.id.unwrap(),
+        None => return Err(GrapevineServerError::UserNotFound(to).into()),
+    };
+    match db.relationship_exists(&sender, &recipient).await {
+        Ok(exists) => Ok(Json(exists)),
+        Err(e) => Err(e.into()),
     }
 }
 
@@ -283,6 +991,8 @@ pub async fn get_pubkey(
  *             * oid: the ObjectID of the proof to build from
  *             * relation: the separation degree of the proof
  *             * phrase_hash: the poseidon hash of the original phrase at the start of the chain
+ *           if the request accepts application/x-ndjson, each DegreeData is instead streamed as
+ *           one json object per line so the server never buffers the full list
  * @return status:
  *            * 200 if success
  *            * 401 if signature mismatch or nonce mismatch
@@ -292,20 +1002,260 @@ pub async fn get_pubkey(
 #[get("/degrees")]
 pub async fn get_all_degrees(
     user: AuthenticatedUser,
+    accept: Option<&Accept>,
     db: &State<GrapevineDB>,
-) -> Result<Json<Vec<DegreeData>>, GrapevineResponse> {
+) -> Result<Either<Json<Vec<DegreeData>>, (ContentType, TextStream![String])>, GrapevineResponse> {
     println!("in");
+    let ndjson = accept.map_or(false, |accept| {
+        accept
+            .media_types()
+            .any(|media_type| media_type == NDJSON.media_type())
+    });
+    if ndjson {
+        let degrees = match db.stream_all_degrees(user.0).await {
+            Ok(degrees) => degrees,
+            Err(e) => return Err(e.into()),
+        };
+        let stream = TextStream! {
+            for await degree in degrees {
+                match degree {
+                    Ok(degree) => {
+                        yield format!("{}\n", serde_json::to_string(&degree).unwrap());
+                    }
+                    Err(e) => {
+                        // the status has already been sent, so end the stream early
+                        tracing::warn!("Error streaming degrees: {:?}", e);
+                        break;
+                    }
+                }
+            }
+        };
+        return Ok(Either::Right((NDJSON.clone(), stream)));
+    }
     match db.get_all_degrees(user.0).await {
+        Some(proofs) => Ok(Either::Left(Json(proofs))),
+        None => Err(GrapevineServerError::MongoError(String::from(
+            "Error retrieving degrees in db",
+        ))
+        .into()),
+    }
+}
+
+/**
+ * Return the degree data for each phrase the caller has proven at a specific degree of separation
+ *
+ * @param degree - the degree of separation to return phrases for
+ * @return - a vector of DegreeData structs (empty if none) containing:
+ *             * degree: the separation degree of the proof (always the requested degree)
+ *             * relation: the username of the user the proof was built from
+ *             * phrase_hash: the poseidon hash of the original phrase at the start of the chain
+ * @return status:
+ *            * 200 if success
+ *            * 401 if signature mismatch or nonce mismatch
+ *            * 404 if user not found
+ *            * 500 if db fails or other unknown issue
+ */
+#[get("/degree/<degree>", rank = 2)]
+pub async fn get_degrees_at(
+    user: AuthenticatedUser,
+    degree: u8,
+    db: &State<GrapevineDB>,
+) -> Result<Json<Vec<DegreeData>>, GrapevineResponse> {
+    // degree 0 returns the phrases the caller holds knowledge proofs for
+    match db.get_degrees_at(user.0, degree).await {
         Some(proofs) => Ok(Json(proofs)),
-        None => Err(GrapevineResponse::InternalError(ErrorMessage(
-            Some(GrapevineServerError::MongoError(String::from(
-                "Error retrieving degrees in db",
-            ))),
-            None,
-        ))),
+        None => Err(GrapevineServerError::MongoError(String::from(
+            "Error retrieving degrees in db",
+        ))
+        .into()),
+    }
+}
+
+/**
+ * Return the degree data for each phrase the caller has proven since a given time
+ * @notice: lets clients that sync periodically fetch only the proofs added since their last sync
+ *
+ * @param timestamp - only proofs created after this time are returned, in milliseconds since the
+ *                    unix epoch
+ * @return - a vector of DegreeData structs (empty if none) containing:
+ *             * degree: the separation degree of the proof
+ *             * relation: the username of the user the proof was built from
+ *             * phrase_hash: the poseidon hash of the original phrase at the start of the chain
+ * @return status:
+ *            * 200 if success
+ *            * 401 if signature mismatch or nonce mismatch
+ *            * 404 if user not found
+ *            * 500 if db fails or other unknown issue
+ */
+#[get("/degrees/since/<timestamp>")]
+pub async fn get_degrees_since(
+    user: AuthenticatedUser,
+    timestamp: i64,
+    db: &State<GrapevineDB>,
+) -> Result<Json<Vec<DegreeData>>, GrapevineResponse> {
+    let since = DateTime::from_millis(timestamp);
+    match db.get_degrees_since(user.0, since).await {
+        Some(proofs) => Ok(Json(proofs)),
+        None => Err(GrapevineServerError::MongoError(String::from(
+            "Error retrieving degrees in db",
+        ))
+        .into()),
+    }
+}
+
+/**
+ * Return the chain of proofs for a phrase from its origin down to the caller's proof
+ *
+ * @param phrase_hash - the hex encoded poseidon hash of the phrase
+ * @return - a vector of DegreeData structs ordered from degree 1 to the caller's degree containing:
+ *             * degree: the separation degree of the proof
+ *             * relation: the username of the user the proof was built from (None at degree 1)
+ *             * phrase_hash: the poseidon hash of the original phrase at the start of the chain
+ * @return status:
+ *            * 200 if success
+ *            * 400 if the phrase hash is not a hex encoded canonical field element
+ *            * 401 if signature mismatch or nonce mismatch
+ *            * 404 if the caller has no proof of the phrase
+ *            * 500 if db fails or other unknown issue
+ */
+#[get("/phrase/<phrase_hash>/path")]
+pub async fn get_phrase_path(
+    user: AuthenticatedUser,
+    phrase_hash: String,
+    db: &State<GrapevineDB>,
+) -> Result<Json<Vec<DegreeData>>, GrapevineResponse> {
+    let hash = match fr_from_hex(&phrase_hash) {
+        Ok(hash) => hash.to_bytes(),
+        Err(e) => {
+            return Err(GrapevineServerError::SerdeError(format!("phrase hash: {}", e)).into())
+        }
+    };
+    match db.get_phrase_path(&user.0, &hash).await {
+        Ok(Some(path)) => Ok(Json(path)),
+        Ok(None) => Err(GrapevineServerError::ProofNotFound(phrase_hash).into()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/**
+ * Return the usernames of all users that have made an authenticated request since a given time
+ * @notice: activity is recorded at most once per LAST_ACTIVE_DEBOUNCE, so a user can be active up
+ *          to LAST_ACTIVE_DEBOUNCE earlier than their recorded last_active
+ *
+ * @param since - the earliest activity to include, in milliseconds since the unix epoch
+ * @return - a vector of the usernames of active users (empty if none)
+ * @return status:
+ *            * 200 if success
+ *            * 500 if db fails or other unknown issue
+ */
+#[get("/active?<since>")]
+pub async fn get_active_users(
+    since: i64,
+    db: &State<GrapevineDB>,
+) -> Result<Json<Vec<String>>, GrapevineResponse> {
+    match db.get_active_users(DateTime::from_millis(since)).await {
+        Ok(usernames) => Ok(Json(usernames)),
+        Err(e) => Err(e.into()),
     }
 }
 
+/**
+ * Return the graph of users reachable from the caller through relationships within a depth
+ *
+ * @param depth - the maximum degree of separation to explore (capped at MAX_GRAPH_DEPTH)
+ * @return - a ConnectionGraph struct containing:
+ *             * nodes: the usernames of all reachable users ordered by degree
+ *             * edges: each relationship traversed to reach a user with the degree of the user
+ * @return status:
+ *            * 200 if success
+ *            * 401 if signature mismatch or nonce mismatch
+ *            * 404 if user not found
+ *            * 500 if db fails or other unknown issue
+ */
+#[get("/graph?<depth>")]
+pub async fn get_connection_graph(
+    user: AuthenticatedUser,
+    depth: u8,
+    db: &State<GrapevineDB>,
+) -> Result<Json<ConnectionGraph>, GrapevineResponse> {
+    let depth = depth.min(MAX_GRAPH_DEPTH);
+    match db.get_connection_graph(&user.0, depth).await {
+        Ok(graph) => Ok(Json(graph)),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/**
+ * Return everything a client needs on app open in a single call
+ * @notice: proof bytes are never included; fetch a proof by oid to build from it
+ *
+ * @return - a Dashboard struct containing:
+ *             * phrases: the poseidon hashes of the phrases the caller created
+ *             * degrees: the degree data for each phrase the caller has proven
+ *             * available: the oid of each proof the caller can build from, with the degree the
+ *                          caller would prove by building on it
+ *             * pending: the usernames of users who sent the caller a relationship that the
+ *                        caller has not sent back
+ * @return status:
+ *            * 200 if success
+ *            * 401 if signature mismatch or nonce mismatch
+ *            * 404 if user not found
+ *            * 500 if db fails or other unknown issue
+ */
+#[get("/dashboard")]
+pub async fn get_dashboard(
+    user: AuthenticatedUser,
+    db: &State<GrapevineDB>,
+) -> Result<Json<Dashboard>, GrapevineResponse> {
+    let caller = match db.get_user(&user.0).await {
+        Some(caller) => caller.id.unwrap(),
+        None => return Err(GrapevineServerError::UserNotFound(user.0).into()),
+    };
+    let degrees = match db.get_all_degrees(user.0.clone()).await {
+        Some(degrees) => degrees,
+        None => {
+            return Err(GrapevineServerError::MongoError(String::from(
+                "Error retrieving degrees in db",
+            ))
+            .into())
+        }
+    };
+    // degree 1 proofs are the phrases the caller created
+    let phrases = degrees
+        .iter()
+        .filter(|degree| degree.degree == 1)
+        .map(|degree| degree.phrase_hash)
+        .collect();
+    let oids: Vec<ObjectId> = degree_cache::get_available_degrees(db, &user.0)
+        .await
+        .iter()
+        .filter_map(|oid| ObjectId::parse_str(oid).ok())
+        .collect();
+    let proof_degrees = match db.get_proof_degrees(&oids).await {
+        Ok(proof_degrees) => proof_degrees,
+        Err(e) => return Err(e.into()),
+    };
+    let available = oids
+        .iter()
+        .filter_map(|oid| {
+            Some(AvailableProof {
+                oid: oid.to_hex(),
+                degree: proof_degrees.get(oid)?.checked_add(1)?,
+            })
+        })
+        .collect();
+    let pending = match db.get_unreciprocated_senders(&caller).await {
+        Ok(pending) => pending,
+        Err(e) => return Err(e.into()),
+    };
+    Ok(Json(Dashboard {
+        phrases,
+        degrees,
+        available,
+        pending,
+    }))
+}
+
 // /**
 //  * Return a list of the usernames of all direct connections by a given user
 //  *
@@ -323,3 +1273,43 @@ pub async fn get_all_degrees(
 // ) -> Result<Json<Vec<String>>, Status> {
 //     todo!("implement get_relationships")
 // }
+
+/**
+ * Inspect the encryption fields of a raw relationship document
+ * @dev both schemes encrypt a 32 byte auth secret into 48 bytes: aes-gcm appends a 16 byte tag
+ *      and aes-cbc pads to a whole extra block
+ *
+ * @param username - the username of the counterparty in the relationship
+ * @param direction - whether the caller sent or received the relationship
+ * @param document - the raw relationship document
+ * @returns - the diagnostics of the stored ephemeral key and ciphertext
+ */
+fn diagnose(
+    username: String,
+    direction: RelationshipDirection,
+    document: &Document,
+) -> RelationshipDiagnostics {
+    let ephemeral_key_valid = match document.get_binary_generic("ephemeral_key") {
+        Ok(key) => match <[u8; 32]>::try_from(key.as_slice()) {
+            Ok(key) => decompress_point(key).is_ok(),
+            Err(_) => false,
+        },
+        Err(_) => false,
+    };
+    let ciphertext_length = document
+        .get_binary_generic("ciphertext")
+        .map_or(0, |ciphertext| ciphertext.len());
+    let nonce = document.get_binary_generic("nonce").ok();
+    let nonce_intact = match nonce {
+        Some(nonce) => nonce.len() == 12,
+        None => true,
+    };
+    RelationshipDiagnostics {
+        username,
+        direction,
+        ephemeral_key_valid,
+        ciphertext_length,
+        legacy: nonce.is_none(),
+        format_intact: ciphertext_length == 48 && nonce_intact,
+    }
+}
@@ -0,0 +1,73 @@
+use crate::catchers::GrapevineResponse;
+use crate::mongo::GrapevineDB;
+use babyjubjub_rs::{decompress_point, decompress_signature, verify};
+use grapevine_common::crypto::message_hash;
+use grapevine_common::errors::GrapevineServerError;
+use grapevine_common::http::requests::{VerifyClaimRequest, VerifySignatureRequest};
+use num_bigint::{BigInt, Sign};
+use rocket::{serde::json::Json, State};
+
+/**
+ * Check a claim that a user is within some degree of separation from a phrase
+ * @notice: answers with a boolean only, so the users between the claimant and the phrase origin
+ *          are never exposed. Unknown users are indistinguishable from users without a proof
+ *
+ * @param request - json VerifyClaimRequest containing:
+ *             * username: the user the claim is about
+ *             * phrase_hash: the poseidon hash of the phrase
+ *             * max_degree: the highest degree of separation that satisfies the claim
+ * @return - true if the user holds a proof for the phrase at or below max_degree
+ * @return status:
+ *             * 200 if the claim was checked, whether or not it holds
+ *             * 400 if the request body is malformed
+ *             * 500 if db fails
+ */
+#[post("/claim", format = "json", data = "<request>")]
+pub async fn verify_claim(
+    request: Json<VerifyClaimRequest>,
+    db: &State<GrapevineDB>,
+) -> Result<Json<bool>, GrapevineResponse> {
+    match db
+        .has_proof_within(&request.username, &request.phrase_hash, request.max_degree)
+        .await
+    {
+        Ok(holds) => Ok(Json(holds)),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/**
+ * Check a signature made by a user over an arbitrary message
+ * @notice: lets external services authenticate Grapevine users with challenge-response by issuing
+ *          a challenge and checking the user's signature over it against their stored pubkey
+ *
+ * @param request - json VerifySignatureRequest containing:
+ *             * username: the user who claims to have signed the message
+ *             * message: the signed message (signed as the sha3 hash H|message|)
+ *             * signature: the compressed baby jubjub signature over the message
+ * @return - true if the signature verifies against the user's pubkey
+ * @return status:
+ *             * 200 if the signature was checked, whether or not it verifies
+ *             * 400 if the request body, signature, or stored pubkey is malformed
+ *             * 404 if the user does not exist
+ */
+#[post("/signature", format = "json", data = "<request>")]
+pub async fn verify_signature(
+    request: Json<VerifySignatureRequest>,
+    db: &State<GrapevineDB>,
+) -> Result<Json<bool>, GrapevineResponse> {
+    let pubkey = match db.get_pubkey(request.username.clone()).await {
+        Some(pubkey) => pubkey,
+        None => return Err(GrapevineServerError::UserNotFound(request.username.clone()).into()),
+    };
+    let pubkey = match decompress_point(pubkey) {
+        Ok(pubkey) => pubkey,
+        Err(e) => return Err(GrapevineServerError::SerdeError(format!("pubkey: {}", e)).into()),
+    };
+    let signature = match decompress_signature(&request.signature) {
+        Ok(signature) => signature,
+        Err(e) => return Err(GrapevineServerError::SerdeError(format!("signature: {}", e)).into()),
+    };
+    let message = BigInt::from_bytes_le(Sign::Plus, &message_hash(&request.message));
+    Ok(Json(verify(pubkey, signature, message)))
+}
@@ -0,0 +1,103 @@
+use grapevine_common::crypto::new_challenge;
+use grapevine_common::models::session::Session;
+use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use mongodb::bson::DateTime;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/** How long a session token authenticates requests if SESSION_TTL_SECS is unset */
+pub const DEFAULT_SESSION_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/** Server configuration for issuing and checking session tokens */
+pub struct SessionConfig {
+    // the key session tokens are signed with
+    pub secret: Vec<u8>,
+    // how long a session token authenticates requests after it is issued
+    pub ttl: Duration,
+}
+
+/** The claims of a session token, which is only valid while its id is stored on the user */
+#[derive(Debug, Serialize, Deserialize)]
+struct SessionClaims {
+    sub: String,
+    sid: String,
+    exp: u64,
+}
+
+impl SessionConfig {
+    /**
+     * Read the session config from the environment
+     * @notice if SESSION_SECRET is unset a random secret is used and a warning is logged, since
+     *         sessions then do not survive restarts or work across instances
+     *
+     * @returns - the config from SESSION_SECRET and SESSION_TTL_SECS (default 24 hours)
+     */
+    pub fn from_env() -> Self {
+        let secret = match std::env::var("SESSION_SECRET") {
+            Ok(secret) => secret.into_bytes(),
+            Err(_) => {
+                tracing::warn!(
+                    "SESSION_SECRET is unset: sessions will not survive restarts or work across \
+                     instances"
+                );
+                new_challenge().to_vec()
+            }
+        };
+        let ttl = match std::env::var("SESSION_TTL_SECS") {
+            Ok(ttl) => ttl
+                .parse()
+                .map(Duration::from_secs)
+                .unwrap_or(DEFAULT_SESSION_TTL),
+            Err(_) => DEFAULT_SESSION_TTL,
+        };
+        SessionConfig { secret, ttl }
+    }
+
+    /**
+     * Issue a new session for a user
+     * @dev the session must be stored on the user before the token authenticates anything
+     *
+     * @param username - the username the session authenticates
+     * @returns - the session to store and the signed token to return to the user
+     */
+    pub fn issue(&self, username: &str) -> (Session, String) {
+        let created_at = DateTime::now();
+        let expires_at =
+            DateTime::from_millis(created_at.timestamp_millis() + self.ttl.as_millis() as i64);
+        let session = Session {
+            id: uuid::Uuid::new_v4().simple().to_string(),
+            created_at,
+            expires_at,
+        };
+        let claims = SessionClaims {
+            sub: username.to_string(),
+            sid: session.id.clone(),
+            exp: (expires_at.timestamp_millis() / 1000) as u64,
+        };
+        let token = encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(&self.secret),
+        )
+        .unwrap();
+        (session, token)
+    }
+
+    /**
+     * Check that a session token was signed by this server and has not expired
+     * @notice does not check revocation, which requires the session to still be stored on the user
+     *
+     * @param token - the session token from the X-Session header
+     * @returns - the username and session id the token was issued for, or None if invalid
+     */
+    pub fn verify(&self, token: &str) -> Option<(String, String)> {
+        match decode::<SessionClaims>(
+            token,
+            &DecodingKey::from_secret(&self.secret),
+            &Validation::default(),
+        ) {
+            Ok(data) => Some((data.claims.sub, data.claims.sid)),
+            Err(_) => None,
+        }
+    }
+}
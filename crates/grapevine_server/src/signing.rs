@@ -0,0 +1,89 @@
+use babyjubjub_rs::{Point, PrivateKey, Signature};
+use grapevine_common::crypto::{new_private_key, proving_data_hash, within_claim_hash};
+use grapevine_common::models::proof::ProvingData;
+use num_bigint::{BigInt, Sign};
+
+/** The key the server signs the proving data it returns with, so clients can trust it */
+pub struct ServerKey {
+    private_key: [u8; 32],
+}
+
+impl ServerKey {
+    /**
+     * Create a server key
+     *
+     * @param private_key - the raw baby jubjub private key to sign with
+     * @returns - the server key
+     */
+    pub fn new(private_key: [u8; 32]) -> Self {
+        ServerKey { private_key }
+    }
+
+    /**
+     * Read the server key from the environment
+     * @notice if SERVER_KEY is unset a random key is used, so signatures do not survive restarts
+     *
+     * @returns - the key from SERVER_KEY (hex encoded 32 byte private key)
+     */
+    pub fn from_env() -> Self {
+        let private_key = match std::env::var("SERVER_KEY") {
+            Ok(key) => hex::decode(key)
+                .ok()
+                .and_then(|key| key.try_into().ok())
+                .expect("SERVER_KEY must be a hex encoded 32 byte private key"),
+            Err(_) => new_private_key(),
+        };
+        Self::new(private_key)
+    }
+
+    /** Return the baby jubjub public key clients verify server signatures against */
+    pub fn pubkey(&self) -> Point {
+        self.private_key().public()
+    }
+
+    /**
+     * Sign proving data returned to a client
+     *
+     * @param data - the proving data being returned
+     * @returns - the signature over the sha3 hash of the proving data
+     */
+    pub fn sign_proving_data(&self, data: &ProvingData) -> Signature {
+        self.sign_hash(&proving_data_hash(data))
+    }
+
+    /**
+     * Sign the answer to whether a user is within some degree of separation from a phrase
+     *
+     * @param username - the user the claim is about
+     * @param phrase_hash - the poseidon hash of the phrase
+     * @param max_degree - the highest degree of separation the claim is about
+     * @param within - whether the user holds a proof at or below max_degree
+     * @param issued_at - when the claim was answered in milliseconds since the unix epoch
+     * @returns - the signature over the sha3 hash of the claim
+     */
+    pub fn sign_within_claim(
+        &self,
+        username: &str,
+        phrase_hash: &[u8; 32],
+        max_degree: u8,
+        within: bool,
+        issued_at: i64,
+    ) -> Signature {
+        self.sign_hash(&within_claim_hash(
+            username,
+            phrase_hash,
+            max_degree,
+            within,
+            issued_at,
+        ))
+    }
+
+    fn sign_hash(&self, hash: &[u8; 32]) -> Signature {
+        let message = BigInt::from_bytes_le(Sign::Plus, hash);
+        self.private_key().sign(message).unwrap()
+    }
+
+    fn private_key(&self) -> PrivateKey {
+        PrivateKey::import(self.private_key.to_vec()).unwrap()
+    }
+}
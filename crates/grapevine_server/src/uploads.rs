@@ -0,0 +1,165 @@
+use grapevine_common::errors::GrapevineServerError;
+use lazy_static::lazy_static;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/** How long an upload can remain in progress before its chunks are discarded */
+pub const UPLOAD_TTL: Duration = Duration::from_secs(600);
+
+/** Maximum number of uploads a single user can have in progress at once */
+pub const MAX_PENDING_UPLOADS_PER_USER: usize = 4;
+
+/** Maximum number of uploads that can be in progress at once across all users */
+pub const MAX_PENDING_UPLOADS: usize = 1024;
+
+/** Maximum number of chunks a single upload can be split into */
+pub const MAX_UPLOAD_CHUNKS: usize = 256;
+
+lazy_static! {
+    /** Maximum number of bytes a reassembled proof request body can contain */
+    pub static ref MAX_PROOF_SIZE: usize = env!("MAX_PROOF_SIZE").parse().unwrap();
+    /** In-progress chunked uploads keyed by upload id */
+    static ref UPLOADS: Mutex<HashMap<String, PendingUpload>> = Mutex::new(HashMap::new());
+}
+
+/** The request body a chunked upload reassembles into */
+#[derive(FromFormField, Debug, Clone, Copy, PartialEq)]
+pub enum UploadKind {
+    Phrase,
+    Degree,
+}
+
+struct PendingUpload {
+    owner: String,
+    kind: UploadKind,
+    chunks: BTreeMap<u32, Vec<u8>>,
+    started: Instant,
+}
+
+impl PendingUpload {
+    fn size(&self) -> usize {
+        self.chunks.values().map(|chunk| chunk.len()).sum()
+    }
+}
+
+/**
+ * Begin a new chunked upload
+ * @dev expired uploads are purged whenever a new upload is started
+ * @dev pending uploads hold their chunks in memory until finalized or expired, so the number in
+ *      progress is capped per user and in total
+ *
+ * @param owner - the username of the user that is allowed to add chunks to the upload
+ * @param kind - the request body the chunks will be reassembled into
+ * @returns - the id of the upload, or an error if the owner or server has too many uploads in
+ *            progress
+ */
+pub fn start(owner: &String, kind: UploadKind) -> Result<String, GrapevineServerError> {
+    let id = Uuid::new_v4().to_string();
+    let mut uploads = UPLOADS.lock().unwrap();
+    uploads.retain(|_, upload| upload.started.elapsed() < UPLOAD_TTL);
+    let owned = uploads
+        .values()
+        .filter(|upload| upload.owner == *owner)
+        .count();
+    if owned >= MAX_PENDING_UPLOADS_PER_USER {
+        return Err(GrapevineServerError::UploadLimitReached(
+            MAX_PENDING_UPLOADS_PER_USER,
+        ));
+    }
+    if uploads.len() >= MAX_PENDING_UPLOADS {
+        return Err(GrapevineServerError::UploadLimitReached(
+            MAX_PENDING_UPLOADS,
+        ));
+    }
+    uploads.insert(
+        id.clone(),
+        PendingUpload {
+            owner: owner.clone(),
+            kind,
+            chunks: BTreeMap::new(),
+            started: Instant::now(),
+        },
+    );
+    Ok(id)
+}
+
+/**
+ * Store a chunk of an in-progress upload, replacing any chunk previously sent at the same index
+ * @dev uploads owned by another user are reported as not found
+ * @dev empty chunks add nothing to the size of an upload, so the number of chunks is capped too
+ *
+ * @param owner - the username of the user adding the chunk
+ * @param id - the id of the upload
+ * @param index - the position of the chunk within the reassembled body
+ * @param chunk - the bytes of the chunk
+ * @returns - an error if the upload does not exist or would exceed MAX_PROOF_SIZE or
+ *            MAX_UPLOAD_CHUNKS
+ */
+pub fn store_chunk(
+    owner: &String,
+    id: &String,
+    index: u32,
+    chunk: Vec<u8>,
+) -> Result<(), GrapevineServerError> {
+    let mut uploads = UPLOADS.lock().unwrap();
+    let upload = match uploads.get_mut(id) {
+        Some(upload) if upload.owner == *owner && upload.started.elapsed() < UPLOAD_TTL => upload,
+        _ => return Err(GrapevineServerError::UploadNotFound(id.clone())),
+    };
+    if !upload.chunks.contains_key(&index) && upload.chunks.len() >= MAX_UPLOAD_CHUNKS {
+        return Err(GrapevineServerError::UploadChunkLimit(MAX_UPLOAD_CHUNKS));
+    }
+    upload.chunks.insert(index, chunk);
+    let size = upload.size();
+    if size > *MAX_PROOF_SIZE {
+        uploads.remove(id);
+        return Err(GrapevineServerError::UploadTooLarge(size));
+    }
+    Ok(())
+}
+
+/**
+ * Reassemble the chunks of an upload in order and remove it from the store
+ * @dev an upload missing a chunk is kept so the chunk can still be sent and the upload finalized
+ *
+ * @param owner - the username of the user finalizing the upload
+ * @param id - the id of the upload
+ * @returns - the kind of the upload and the reassembled body, or an error if the upload does not
+ *            exist, is missing a chunk, or exceeds MAX_PROOF_SIZE
+ */
+pub fn finalize(
+    owner: &String,
+    id: &String,
+) -> Result<(UploadKind, Vec<u8>), GrapevineServerError> {
+    let mut uploads = UPLOADS.lock().unwrap();
+    let upload = match uploads.get(id) {
+        Some(upload) if upload.owner == *owner => upload,
+        _ => return Err(GrapevineServerError::UploadNotFound(id.clone())),
+    };
+    if upload.started.elapsed() >= UPLOAD_TTL {
+        uploads.remove(id);
+        return Err(GrapevineServerError::UploadNotFound(id.clone()));
+    }
+    let size = upload.size();
+    if size > *MAX_PROOF_SIZE {
+        uploads.remove(id);
+        return Err(GrapevineServerError::UploadTooLarge(size));
+    }
+    // chunks are ordered by index so any gap means a chunk was never received
+    let missing = upload
+        .chunks
+        .keys()
+        .enumerate()
+        .find(|(expected, index)| **index != *expected as u32);
+    if let Some((expected, _)) = missing {
+        return Err(GrapevineServerError::UploadIncomplete(expected as u32));
+    }
+    let upload = uploads.remove(id).unwrap();
+    let mut body = Vec::with_capacity(size);
+    for chunk in upload.chunks.into_values() {
+        body.extend(chunk);
+    }
+    Ok((upload.kind, body))
+}
@@ -1,40 +1,207 @@
-use grapevine_common::{Fr, Params, G1, G2};
+use crate::verification::{ProofBackend, VERIFIER};
+use grapevine_circuits::errors::GrapevineCircuitError;
+use grapevine_circuits::{CIRCUIT_VERSION, PARAMS_R1CS_DIGEST_FILE};
+use grapevine_common::crypto::new_challenge;
+use grapevine_common::errors::GrapevineServerError;
+use grapevine_common::http::responses::CircuitInfo;
+use grapevine_common::{Fq, Fr, Params, G1, G2};
 use lazy_static::lazy_static;
 use nova_scotia::circom::circuit::R1CS;
 use nova_scotia::circom::reader::load_r1cs;
 use nova_scotia::FileLocation;
+use rocket::http::ContentType;
+use std::collections::HashMap;
 use std::env::current_dir;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/** How long a user creation challenge can be redeemed after it is issued */
+pub const CHALLENGE_TTL: Duration = Duration::from_secs(300);
+
+/** How stale a user's last_active timestamp must be before an authenticated request updates it */
+pub const LAST_ACTIVE_DEBOUNCE: Duration = Duration::from_secs(3600);
+
+/** The window over which rate limited routes count requests from a client */
+pub const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(60);
+
+/** How many requests a client can make to a rate limited route within RATE_LIMIT_WINDOW */
+pub const RATE_LIMIT_REQUESTS: u32 = 10;
+
+/** The smallest gzip-compressed proof possible (a gzip header and trailer around no data) */
+pub const MIN_PROOF_SIZE: usize = 18;
+
+/** The largest NewRelationshipRequest body accepted, far above a username and auth secret */
+pub const MAX_RELATIONSHIP_REQUEST_SIZE: usize = 4 * 1024;
 
 lazy_static! {
-    pub static ref PUBLIC_PARAMS: Params = use_public_params().unwrap();
+    pub static ref CIRCUIT_INFO: CircuitInfo = use_circuit_info().unwrap();
+    pub static ref VERIFICATION_TIMEOUT: Duration =
+        Duration::from_millis(env!("VERIFICATION_TIMEOUT_MS").parse().unwrap());
+    pub static ref MAX_RELATIONSHIPS: usize = env!("MAX_RELATIONSHIPS").parse().unwrap();
+    static ref CHALLENGES: Mutex<HashMap<[u8; 32], Instant>> = Mutex::new(HashMap::new());
+    pub static ref STATIC_DIR: PathBuf = resolve_dir(
+        std::env::var("STATIC_DIR").ok(),
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("static")
+    )
+    .unwrap_or_else(|e| panic!("Invalid STATIC_DIR: {}", e));
+    pub static ref ARTIFACTS_DIR: PathBuf = resolve_dir(
+        std::env::var("ARTIFACTS_DIR").ok(),
+        current_dir().unwrap().join("static")
+    )
+    .unwrap_or_else(|e| panic!("Invalid ARTIFACTS_DIR: {}", e));
+    /** The content type of newline-delimited json streamed by list routes */
+    pub static ref NDJSON: ContentType = ContentType::new("application", "x-ndjson");
+}
+
+/**
+ * Resolve a configurable directory to an absolute path
+ *
+ * @param configured - the directory set in the environment, if any
+ * @param default - the directory to use if none is configured
+ * @returns - the canonicalized directory, or an error naming the directory that does not exist
+ */
+pub fn resolve_dir(configured: Option<String>, default: PathBuf) -> Result<PathBuf, String> {
+    let dir = match configured {
+        Some(dir) => PathBuf::from(dir),
+        None => default,
+    };
+    match dir.canonicalize() {
+        Ok(dir) if dir.is_dir() => Ok(dir),
+        _ => Err(format!("Directory {} does not exist", dir.display())),
+    }
 }
 
 // @TODO: lazy static implementation for public params and r1cs
 
 pub fn use_public_params() -> Result<Params, Box<dyn std::error::Error>> {
     // get the path to grapevine (will create if it does not exist)
-    let filepath = current_dir().unwrap().join("static/public_params.json");
+    let filepath = ARTIFACTS_DIR.join("public_params.json");
     println!("Filepath: {}", filepath.display());
     // read in params file
-    let public_params_file = std::fs::read_to_string(filepath).expect("Unable to read file");
+    let public_params_file = std::fs::read_to_string(&filepath)
+        .map_err(|e| format!("Unable to read {}: {}", filepath.display(), e))?;
 
     // parse file into params struct
-    let public_params: Params =
-        serde_json::from_str(&public_params_file).expect("Incorrect public params format");
+    let public_params: Params = serde_json::from_str(&public_params_file)
+        .map_err(|e| format!("Incorrect public params format: {}", e))?;
 
     Ok(public_params)
 }
 
 pub fn use_r1cs() -> Result<R1CS<Fr>, Box<dyn std::error::Error>> {
     // get the path to grapevine (will create if it does not exist)
-    let filepath = current_dir().unwrap().join("static/grapevine.r1cs");
+    let filepath = ARTIFACTS_DIR.join("grapevine.r1cs");
     // read in params file
     Ok(load_r1cs::<G1, G2>(&FileLocation::PathBuf(filepath)))
 }
 
+/**
+ * Read the r1cs digest recorded when the public params were set up
+ *
+ * @returns - the contents of the digest file, or an error naming the file if it cannot be read
+ */
+pub fn use_params_r1cs_digest() -> Result<String, Box<dyn std::error::Error>> {
+    let filepath = ARTIFACTS_DIR.join(PARAMS_R1CS_DIGEST_FILE);
+    std::fs::read_to_string(&filepath)
+        .map_err(|e| format!("Unable to read {}: {}", filepath.display(), e).into())
+}
+
 pub fn use_wasm() -> Result<PathBuf, Box<dyn std::error::Error>> {
     // get the path to grapevine (will create if it does not exist)
-    let filepath = current_dir().unwrap().join("static/grapevine.wasm");
+    let filepath = ARTIFACTS_DIR.join("grapevine.wasm");
     Ok(filepath)
 }
+
+pub fn use_circuit_info() -> Result<CircuitInfo, Box<dyn std::error::Error>> {
+    // hash the artifacts clients must match to produce verifiable proofs
+    let r1cs = std::fs::read(ARTIFACTS_DIR.join("grapevine.r1cs"))?;
+    let public_params = std::fs::read(ARTIFACTS_DIR.join("public_params.json"))?;
+    Ok(CircuitInfo {
+        r1cs_hash: sha256::digest(r1cs.as_slice()),
+        public_params_hash: sha256::digest(public_params.as_slice()),
+        version: String::from(CIRCUIT_VERSION),
+    })
+}
+
+/**
+ * Issue a new single-use user creation challenge
+ * @dev expired challenges are purged whenever a new challenge is issued
+ *
+ * @returns - the challenge that must be signed alongside the username
+ */
+pub fn issue_challenge() -> [u8; 32] {
+    let challenge = new_challenge();
+    let mut challenges = CHALLENGES.lock().unwrap();
+    challenges.retain(|_, issued| issued.elapsed() < CHALLENGE_TTL);
+    challenges.insert(challenge, Instant::now());
+    challenge
+}
+
+/**
+ * Redeem a user creation challenge so that it cannot be used again
+ *
+ * @param challenge - the challenge included in the user creation request
+ * @returns - true if the challenge was issued by this server and has not expired
+ */
+pub fn consume_challenge(challenge: &[u8; 32]) -> bool {
+    match CHALLENGES.lock().unwrap().remove(challenge) {
+        Some(issued) => issued.elapsed() < CHALLENGE_TTL,
+        None => false,
+    }
+}
+
+/**
+ * Decompress and verify a proof on the blocking thread pool, giving up once a deadline passes
+ * @dev decompression and verification are CPU-bound so both run under spawn_blocking, keeping the
+ *      async workers free to serve other routes while proofs are checked. A blocking task cannot
+ *      be cancelled, so a timed out verification runs to completion and its result is discarded.
+ *      Verifications wait for a permit from verification::VERIFIER so at most
+ *      VERIFICATION_CONCURRENCY run at once
+ *
+ * @param backend - the proof system to verify with
+ * @param proof - the gzip-compressed proof to verify
+ * @param iterations - the number of folds the proof claims (degree * 2)
+ * @param timeout - how long to wait for verification before returning VerificationTimeout
+ * @returns - the verification result, or a server error if the proof is shorter than
+ *            MIN_PROOF_SIZE, no verifier was free, the deadline passed, or the task died
+ */
+pub async fn verify_proof_with_timeout(
+    backend: &ProofBackend,
+    proof: Vec<u8>,
+    iterations: usize,
+    timeout: Duration,
+) -> Result<Result<(Vec<Fr>, Vec<Fq>), GrapevineCircuitError>, GrapevineServerError> {
+    // reject proofs too short to decompress before taking a verifier
+    if proof.len() < MIN_PROOF_SIZE {
+        return Err(GrapevineServerError::EmptyProof(proof.len()));
+    }
+    VERIFIER.verify(backend, proof, iterations, timeout).await
+}
+
+/**
+ * Decompress and verify many independent proofs in parallel, giving up once a deadline passes
+ * @dev the batch is verified across the rayon pool under permits from verification::VERIFIER, so
+ *      importing or re-verifying many proofs does not pay for each verification in sequence
+ *
+ * @param backend - the proof system to verify with
+ * @param proofs - the gzip-compressed proofs to verify, each with its number of folds
+ * @param timeout - how long to wait for the whole batch before returning VerificationTimeout
+ * @returns - the verification result of each proof in the order given, or a server error if any
+ *            proof is shorter than MIN_PROOF_SIZE, no verifier was free, the deadline passed, or
+ *            the task died
+ */
+pub async fn verify_proofs_with_timeout(
+    backend: &ProofBackend,
+    proofs: Vec<(Vec<u8>, usize)>,
+    timeout: Duration,
+) -> Result<Vec<Result<(Vec<Fr>, Vec<Fq>), GrapevineCircuitError>>, GrapevineServerError> {
+    // reject proofs too short to decompress before taking a verifier
+    if let Some((proof, _)) = proofs
+        .iter()
+        .find(|(proof, _)| proof.len() < MIN_PROOF_SIZE)
+    {
+        return Err(GrapevineServerError::EmptyProof(proof.len()));
+    }
+    VERIFIER.verify_batch(backend, proofs, timeout).await
+}
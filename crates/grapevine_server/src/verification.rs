@@ -0,0 +1,226 @@
+use crate::utils::{use_params_r1cs_digest, use_public_params, CIRCUIT_INFO, VERIFICATION_TIMEOUT};
+use grapevine_circuits::errors::GrapevineCircuitError;
+use grapevine_circuits::nova::check_compatibility;
+use grapevine_circuits::proof_system::{CompressedVerifier, NovaProofSystem};
+use grapevine_common::errors::GrapevineServerError;
+use grapevine_common::{Fq, Fr, Params};
+use lazy_static::lazy_static;
+use rocket::tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use std::sync::Arc;
+use std::time::Duration;
+
+lazy_static! {
+    /** The limiter every proof verification made by the server is run through */
+    pub static ref VERIFIER: VerificationLimiter = VerificationLimiter::from_env();
+}
+
+/** Server configuration selecting the proof system proofs are verified with */
+#[derive(Clone)]
+pub struct ProofBackend {
+    // shared with each blocking verification task
+    system: Arc<dyn CompressedVerifier>,
+}
+
+impl ProofBackend {
+    /**
+     * Create a proof backend
+     *
+     * @param system - the proof system to verify proofs with
+     * @returns - the proof backend
+     */
+    pub fn new(system: Arc<dyn CompressedVerifier>) -> Self {
+        ProofBackend { system }
+    }
+
+    /**
+     * Create a Nova backend, checking the public params were set up for the circuit's r1cs
+     *
+     * @param r1cs_hash - the sha256 digest of the r1cs of the grapevine circuit
+     * @param recorded - the r1cs digest recorded alongside the public params
+     * @param public_params - the public params to verify proofs with
+     * @returns - the backend, or an Artifact error naming the mismatched digests
+     */
+    pub fn nova(
+        r1cs_hash: &str,
+        recorded: &str,
+        public_params: Params,
+    ) -> Result<Self, GrapevineCircuitError> {
+        check_compatibility(r1cs_hash, recorded)?;
+        Ok(Self::new(Arc::new(NovaProofSystem::new(public_params))))
+    }
+
+    /**
+     * Read the proof backend from the environment
+     * @notice fails on an unknown PROOF_SYSTEM rather than silently verifying with another one,
+     *         and on circuit artifacts that do not belong together rather than failing every
+     *         verification
+     *
+     * @returns - the backend named by PROOF_SYSTEM (default "nova", the only backend so far), or
+     *            an error describing why it could not be loaded
+     */
+    pub fn from_env() -> Result<Self, String> {
+        let name = std::env::var("PROOF_SYSTEM").unwrap_or_else(|_| String::from("nova"));
+        match name.as_str() {
+            "nova" => {
+                let recorded = use_params_r1cs_digest().map_err(|e| e.to_string())?;
+                let public_params = use_public_params().map_err(|e| e.to_string())?;
+                Self::nova(&CIRCUIT_INFO.r1cs_hash, &recorded, public_params)
+                    .map_err(|e| format!("Incompatible circuit artifacts: {}", e))
+            }
+            _ => Err(format!("Unknown PROOF_SYSTEM {}", name)),
+        }
+    }
+}
+
+/** Server configuration bounding how many proofs are verified at once */
+pub struct VerificationLimiter {
+    // one permit is held for the duration of each verification
+    permits: Arc<Semaphore>,
+    // the total number of permits
+    concurrency: usize,
+    // how long a verification waits for a permit before VerificationBusy is returned
+    queue_timeout: Duration,
+}
+
+impl VerificationLimiter {
+    /**
+     * Create a verification limiter
+     *
+     * @param permits - the maximum number of verifications that can run at once
+     * @param queue_timeout - how long a verification waits for a free permit
+     * @returns - the verification limiter
+     */
+    pub fn new(permits: usize, queue_timeout: Duration) -> Self {
+        VerificationLimiter {
+            permits: Arc::new(Semaphore::new(permits)),
+            concurrency: permits,
+            queue_timeout,
+        }
+    }
+
+    /**
+     * Read the verification limits from the environment
+     *
+     * @returns - the limiter from VERIFICATION_CONCURRENCY (default the number of cpu cores) and
+     *            VERIFICATION_QUEUE_TIMEOUT_MS (default the verification timeout)
+     */
+    pub fn from_env() -> Self {
+        let permits = std::env::var("VERIFICATION_CONCURRENCY")
+            .ok()
+            .and_then(|permits| permits.parse::<usize>().ok())
+            .filter(|permits| *permits > 0)
+            .unwrap_or_else(|| {
+                std::thread::available_parallelism()
+                    .map(|cores| cores.get())
+                    .unwrap_or(1)
+            });
+        let queue_timeout = std::env::var("VERIFICATION_QUEUE_TIMEOUT_MS")
+            .ok()
+            .and_then(|millis| millis.parse::<u64>().ok())
+            .map(Duration::from_millis)
+            .unwrap_or(*VERIFICATION_TIMEOUT);
+        Self::new(permits, queue_timeout)
+    }
+
+    /**
+     * Return the number of verifications that could start without waiting
+     */
+    pub fn available_permits(&self) -> usize {
+        self.permits.available_permits()
+    }
+
+    /**
+     * Decompress and verify a proof on the blocking thread pool once a permit is free
+     * @dev the permit moves into the blocking task, so a verification that outlives its deadline
+     *      keeps its permit until it actually finishes and cannot be overlapped by new work
+     *
+     * @param backend - the proof system to verify with
+     * @param proof - the compressed proof to verify
+     * @param iterations - the number of folds the proof claims (degree * 2)
+     * @param timeout - how long to wait for verification before returning VerificationTimeout
+     * @returns - the verification result, or a server error if no permit was free within the
+     *            queue timeout, the deadline passed, or the task died
+     */
+    pub async fn verify(
+        &self,
+        backend: &ProofBackend,
+        proof: Vec<u8>,
+        iterations: usize,
+        timeout: Duration,
+    ) -> Result<Result<(Vec<Fr>, Vec<Fq>), GrapevineCircuitError>, GrapevineServerError> {
+        let permit = self.acquire(1).await?;
+        let system = backend.system.clone();
+        let task = rocket::tokio::task::spawn_blocking(move || {
+            let _permit = permit;
+            system.verify_compressed(&proof, iterations)
+        });
+        Self::await_task(task, timeout).await
+    }
+
+    /**
+     * Decompress and verify many independent proofs in parallel once permits are free
+     * @dev holds one permit per proof (up to every permit) while the batch runs on the rayon pool
+     *
+     * @param backend - the proof system to verify with
+     * @param proofs - the compressed proofs to verify, each with its number of folds
+     * @param timeout - how long to wait for the whole batch before returning VerificationTimeout
+     * @returns - the verification result of each proof in the order given, or a server error if
+     *            no permits were free within the queue timeout, the deadline passed, or the task
+     *            died
+     */
+    pub async fn verify_batch(
+        &self,
+        backend: &ProofBackend,
+        proofs: Vec<(Vec<u8>, usize)>,
+        timeout: Duration,
+    ) -> Result<Vec<Result<(Vec<Fr>, Vec<Fq>), GrapevineCircuitError>>, GrapevineServerError> {
+        let permits = proofs.len().clamp(1, self.concurrency.max(1));
+        let permit = self.acquire(permits as u32).await?;
+        let system = backend.system.clone();
+        let task = rocket::tokio::task::spawn_blocking(move || {
+            let _permit = permit;
+            system.verify_compressed_batch(&proofs)
+        });
+        Self::await_task(task, timeout).await
+    }
+
+    /**
+     * Wait up to the queue timeout for a number of permits to be free
+     *
+     * @param permits - the number of permits to take
+     * @returns - the permits, or VerificationBusy if they were not freed within the queue timeout
+     */
+    async fn acquire(&self, permits: u32) -> Result<OwnedSemaphorePermit, GrapevineServerError> {
+        let acquire = self.permits.clone().acquire_many_owned(permits);
+        match rocket::tokio::time::timeout(self.queue_timeout, acquire).await {
+            Ok(Ok(permit)) => Ok(permit),
+            Ok(Err(_)) => Err(GrapevineServerError::InternalError),
+            Err(_) => Err(GrapevineServerError::VerificationBusy(
+                self.queue_timeout.as_millis() as u64,
+            )),
+        }
+    }
+
+    /**
+     * Wait up to a deadline for a blocking verification task to finish
+     *
+     * @param task - the blocking verification task
+     * @param timeout - how long to wait before returning VerificationTimeout
+     * @returns - the output of the task, or a server error if the deadline passed or the task died
+     */
+    async fn await_task<T>(
+        task: rocket::tokio::task::JoinHandle<T>,
+        timeout: Duration,
+    ) -> Result<T, GrapevineServerError> {
+        match rocket::tokio::time::timeout(timeout, task).await {
+            Ok(Ok(res)) => Ok(res),
+            Ok(Err(e)) => {
+                println!("Proof verification task failed: {:?}", e);
+                Err(GrapevineServerError::InternalError)
+            }
+            Err(_) => Err(GrapevineServerError::VerificationTimeout(
+                timeout.as_millis() as u64,
+            )),
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use mongodb::bson::oid::ObjectId;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/** How long a single webhook delivery attempt can take before it is abandoned */
+pub const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/** How many times a webhook delivery is attempted before the notification is dropped */
+pub const WEBHOOK_ATTEMPTS: u32 = 2;
+
+/** How long to wait before retrying a failed webhook delivery */
+pub const WEBHOOK_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/** Server configuration for notifying an external service when proofs are added */
+pub struct WebhookConfig {
+    // the url new proofs are posted to (notifications are disabled if None)
+    pub url: Option<String>,
+    client: Client,
+}
+
+/** The payload posted to the webhook when a proof is added */
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ProofNotification {
+    // the hex encoded poseidon hash of the phrase at the start of the chain
+    pub phrase_hash: String,
+    pub degree: u8,
+    // None if the proof anonymously originated its phrase
+    pub username: Option<String>,
+    pub oid: String,
+}
+
+impl ProofNotification {
+    pub fn new(phrase_hash: &[u8; 32], degree: u8, username: Option<&str>, oid: &ObjectId) -> Self {
+        ProofNotification {
+            phrase_hash: hex::encode(phrase_hash),
+            degree,
+            username: username.map(|username| username.to_string()),
+            oid: oid.to_hex(),
+        }
+    }
+}
+
+impl WebhookConfig {
+    /**
+     * Create a webhook config
+     *
+     * @param url - the url to post proof notifications to, or None to disable notifications
+     * @returns - the webhook config
+     */
+    pub fn new(url: Option<String>) -> Self {
+        let client = Client::builder().timeout(WEBHOOK_TIMEOUT).build().unwrap();
+        WebhookConfig { url, client }
+    }
+
+    /**
+     * Read the webhook config from the environment
+     *
+     * @returns - the config from PROOF_WEBHOOK_URL (notifications are disabled if unset)
+     */
+    pub fn from_env() -> Self {
+        Self::new(std::env::var("PROOF_WEBHOOK_URL").ok())
+    }
+
+    /**
+     * Post a proof notification to the webhook in the background
+     * @notice fire-and-forget: delivery failures are logged and never surface to the request
+     *
+     * @param notification - the proof notification to deliver
+     */
+    pub fn notify(&self, notification: ProofNotification) {
+        let url = match &self.url {
+            Some(url) => url.clone(),
+            None => return,
+        };
+        let client = self.client.clone();
+        tokio::spawn(async move {
+            for attempt in 1..=WEBHOOK_ATTEMPTS {
+                let res = client.post(&url).json(&notification).send().await;
+                let error = match res {
+                    Ok(res) if res.status().is_success() => return,
+                    Ok(res) => format!("status {}", res.status()),
+                    Err(e) => e.to_string(),
+                };
+                tracing::warn!(
+                    "Proof webhook delivery for {} failed (attempt {}/{}): {}",
+                    notification.oid,
+                    attempt,
+                    WEBHOOK_ATTEMPTS,
+                    error
+                );
+                if attempt < WEBHOOK_ATTEMPTS {
+                    tokio::time::sleep(WEBHOOK_RETRY_DELAY).await;
+                }
+            }
+        });
+    }
+}